@@ -0,0 +1,78 @@
+/*!
+Histogram colouring at 4K on one thread against one thread per physical core.
+
+Run with `cargo bench --bench histogram`. The pixels come from [`compute::reference::render`],
+so no GPU is needed. Each run records every escaped pixel into a new histogram, updates its
+ranges, and colours the whole screen, as a view's first fully-escaped frame does.
+*/
+
+use std::time::{Duration, Instant};
+
+use rayon::ThreadPoolBuilder;
+use wgpu_mandelbrot::{
+    colour::{ColourRange, HistogramColouring},
+    compute,
+    pixel::{Complex, IteratedPixel, Pixel},
+    screen,
+};
+
+const SIZE: screen::Size = screen::Size {
+    width: 3840,
+    height: 2160,
+};
+
+/// `--benchmark-location`'s default.
+const ORIGIN: Complex = Complex {
+    real: -0.74529,
+    imaginary: 0.113075,
+};
+
+const RUNS: u32 = 20;
+
+/// The mean time to record and colour `pixels` on `threads` threads.
+fn time(threads: usize, pixels: &[Pixel], escaped: &[Pixel]) -> Duration {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .unwrap();
+    let mut colour_ranges = vec![ColourRange::default(); pixels.len()];
+    pool.install(|| {
+        let start = Instant::now();
+        for _ in 0..RUNS {
+            colour_ranges.fill(ColourRange::default());
+            let mut histogram_colouring = HistogramColouring::new().with_smoothing(true);
+            histogram_colouring.record_escaped(escaped);
+            histogram_colouring.update_ranges();
+            histogram_colouring.colour(pixels, &mut colour_ranges);
+        }
+        start.elapsed() / RUNS
+    })
+}
+
+fn main() {
+    let pixels = compute::reference::render(SIZE, ORIGIN, 1.0, compute::DEFAULT_MAX_ITERATIONS);
+    let escaped: Vec<Pixel> = pixels
+        .iter()
+        .copied()
+        .filter(|pixel| pixel.is_escaped())
+        .collect();
+    println!(
+        "{}x{}, {} of {} pixels escaped, mean of {} runs",
+        SIZE.width,
+        SIZE.height,
+        escaped.len(),
+        pixels.len(),
+        RUNS
+    );
+
+    let serial = time(1, &pixels, &escaped);
+    println!("serial:   {:?}", serial);
+    let threads = num_cpus::get_physical();
+    let parallel = time(threads, &pixels, &escaped);
+    println!(
+        "parallel: {:?} on {} threads ({:.1}x)",
+        parallel,
+        threads,
+        serial.as_secs_f64() / parallel.as_secs_f64()
+    );
+}
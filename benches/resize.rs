@@ -0,0 +1,95 @@
+/*!
+Creating the two pixels buffers at 4K, as a resize does, by writing the starting pixels into them
+while they're mapped at creation against staging them through `Queue::write_buffer`.
+
+Run with `cargo bench --bench resize`. Needs a GPU that allows buffers this large; the device is
+requested with all of the adapter's limits, since [`wgpu::Limits::default`] doesn't.
+*/
+
+use std::time::{Duration, Instant};
+
+use wgpu_mandelbrot::{
+    pixel::Pixel,
+    renderer::{create_pixels, create_pixels_buffers},
+    screen, typed_buffer,
+};
+
+const SIZE: screen::Size = screen::Size {
+    width: 3840,
+    height: 2160,
+};
+
+const RUNS: u32 = 20;
+
+/// The mean time for `create` to return both buffers and the GPU to finish with them.
+fn time(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    create: impl Fn() -> typed_buffer::DoubleBuffer<Pixel>,
+) -> Duration {
+    let mut total = Duration::ZERO;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        let buffers = create();
+        queue.submit(None);
+        device.poll(wgpu::Maintain::Wait);
+        total += start.elapsed();
+        buffers.destroy();
+    }
+    total / RUNS
+}
+
+/// How the buffers were created before [`typed_buffer::Builder::with_mapped_at_creation`].
+fn create_staged(device: &wgpu::Device, queue: &wgpu::Queue) -> typed_buffer::DoubleBuffer<Pixel> {
+    let create = |label| {
+        typed_buffer::Builder::new(SIZE.width as u64 * SIZE.height as u64)
+            .with_label(label)
+            .with_usage(wgpu::BufferUsages::STORAGE)
+            .with_usage(wgpu::BufferUsages::COPY_SRC)
+            .with_usage(wgpu::BufferUsages::COPY_DST)
+            .create(device)
+    };
+    let buffers =
+        typed_buffer::DoubleBuffer::new(create("pixels_buffer_1"), create("pixels_buffer_2"));
+    buffers.write(queue, &create_pixels(SIZE));
+    buffers
+}
+
+fn main() {
+    let instance = wgpu::Instance::new(wgpu::Backends::all());
+    let adapter =
+        match pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+        {
+            Some(adapter) => adapter,
+            None => {
+                println!("skipping: no adapter available");
+                return;
+            }
+        };
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: Some("device"),
+            features: wgpu::Features::empty(),
+            limits: adapter.limits(),
+        },
+        None,
+    ))
+    .unwrap();
+    println!(
+        "{} ({:?}), {}x{}, mean of {} runs",
+        adapter.get_info().name,
+        adapter.get_info().backend,
+        SIZE.width,
+        SIZE.height,
+        RUNS
+    );
+
+    let staged = time(&device, &queue, || create_staged(&device, &queue));
+    println!("staged:             {:?}", staged);
+    let mapped = time(&device, &queue, || create_pixels_buffers(&device, SIZE));
+    println!(
+        "mapped at creation: {:?} ({:.1}x faster)",
+        mapped,
+        staged.as_secs_f64() / mapped.as_secs_f64()
+    );
+}
@@ -0,0 +1,37 @@
+//! Named views that are saved to disk and can be returned to with a single key.
+
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{compute::FractalKind, pixel::ComplexF64};
+
+/// Where bookmarks are read from at startup and written to when one is saved.
+pub const PATH: &str = "bookmarks.json";
+
+/// Everything needed to render a saved view again.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub origin: ComplexF64,
+    pub zoom: f64,
+    pub max_iterations: u32,
+    pub fractal_kind: FractalKind,
+}
+
+/// Read the bookmarks at `path`. A missing file has no bookmarks.
+pub fn load(path: impl AsRef<Path>) -> io::Result<Vec<Bookmark>> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    Ok(serde_json::from_reader(io::BufReader::new(file))?)
+}
+
+/// Overwrite the file at `path` with `bookmarks`.
+pub fn save(path: impl AsRef<Path>, bookmarks: &[Bookmark]) -> io::Result<()> {
+    let file = fs::File::create(path)?;
+    serde_json::to_writer_pretty(io::BufWriter::new(file), bookmarks)?;
+    Ok(())
+}
@@ -1,35 +1,351 @@
 //! Colouring algorithms.
 
+use std::{fs, io, ops::Range, path::Path};
+
 use bytemuck::{Pod, Zeroable};
+use clap::ValueEnum;
 use fnv::{FnvHashMap, FnvHashSet};
 use log::trace;
-use rayon::prelude::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
+use rayon::prelude::{
+    IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator, ParallelSlice,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    compute,
+    pixel::{Complex, IteratedPixel, ESCAPED, INTERIOR, ITERATING},
+    screen,
+};
 
-use crate::{pixel::Pixel, screen};
+/**
+[`bytemuck`]-compatible colour output for a single pixel.
 
-/// [`bytemuck`]-compatible colour output for a single pixel.
+`position` is where the pixel samples `render.wgsl#sample_palette`, from `0.0` to `1.0`.
+*/
 #[repr(C)]
-#[derive(Pod, Zeroable, Clone, Copy, Debug)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ColourRange {
     pub escaped: u32,
-    pub value: f32,
+    pub position: f32,
 }
 
 impl Default for ColourRange {
     fn default() -> Self {
         Self {
-            escaped: 0,
-            value: 0.0,
+            escaped: ITERATING,
+            position: 0.0,
+        }
+    }
+}
+
+/**
+The smallest range of indices that covers both `first` and `second`.
+
+The colouring algorithms return the range of `colour_ranges` that they changed, so that only it
+has to be uploaded to the GPU.
+*/
+pub fn merge_dirty(
+    first: Option<Range<usize>>,
+    second: Option<Range<usize>>,
+) -> Option<Range<usize>> {
+    match (first, second) {
+        (Some(first), Some(second)) => {
+            Some(first.start.min(second.start)..first.end.max(second.end))
+        }
+        (first, None) => first,
+        (None, second) => second,
+    }
+}
+
+/**
+Move each of `shown` `alpha` of the way toward the same pixel in `targets`, an exponential moving
+average for [`crate::renderer::Renderer::set_colour_smoothing`]. Only pixels that are escaped in
+both have their positions eased; any other change is shown at once.
+
+Returns the range of `shown` that hasn't reached its targets yet.
+*/
+pub fn ease_colour_ranges(
+    shown: &mut [ColourRange],
+    targets: &[ColourRange],
+    alpha: f32,
+) -> Option<Range<usize>> {
+    debug_assert_eq!(shown.len(), targets.len());
+    shown
+        .par_iter_mut()
+        .enumerate()
+        .map(|(index, shown)| {
+            let target = targets[index];
+            if shown.escaped != ESCAPED || target.escaped != ESCAPED {
+                *shown = target;
+                return None;
+            }
+            let position = shown.position + (target.position - shown.position) * alpha;
+            if (target.position - position).abs() < SETTLED_POSITION {
+                shown.position = target.position;
+                None
+            } else {
+                shown.position = position;
+                Some(index..index + 1)
+            }
+        })
+        .reduce(|| None, merge_dirty)
+}
+
+/// Set `colour_range` to `new`, and return its `index` as a dirty range if that changed it.
+fn update(colour_range: &mut ColourRange, new: ColourRange, index: usize) -> Option<Range<usize>> {
+    if *colour_range == new {
+        None
+    } else {
+        *colour_range = new;
+        Some(index..index + 1)
+    }
+}
+
+/// How many colours every [`Palette`] is resampled to for `render.wgsl#palette`.
+pub const PALETTE_STOPS: usize = 256;
+
+/**
+A gradient that escaped pixels are coloured with.
+
+`render.wgsl#sample_palette` interpolates between [`Palette::colours`], from the first at
+position `0.0` to the last at `1.0`. Since the position is stored in each [`ColourRange`],
+changing palettes doesn't require any iterations to be redone.
+*/
+pub trait Palette {
+    /// sRGB colours with components from `0.0` to `1.0`. A single colour is used for every escaped pixel.
+    fn colours(&self) -> Vec<[f32; 3]>;
+
+    /// Write [`Palette::colours`] to `path` as JSON, which [`Custom::load`] reads back.
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let file = fs::File::create(path)?;
+        serde_json::to_writer_pretty(io::BufWriter::new(file), &self.colours())?;
+        Ok(())
+    }
+}
+
+/// Dark to light green. The default.
+pub struct Green;
+
+impl Palette for Green {
+    fn colours(&self) -> Vec<[f32; 3]> {
+        vec![
+            [15.0 / 255.0, 66.0 / 255.0, 7.0 / 255.0],
+            [88.0 / 255.0, 150.0 / 255.0, 52.0 / 255.0],
+            [200.0 / 255.0, 230.0 / 255.0, 160.0 / 255.0],
+            [1.0, 1.0, 1.0],
+        ]
+    }
+}
+
+/// Black to white.
+pub struct Grayscale;
+
+impl Palette for Grayscale {
+    fn colours(&self) -> Vec<[f32; 3]> {
+        vec![[0.0, 0.0, 0.0], [1.0, 1.0, 1.0]]
+    }
+}
+
+/// Deep blue through white to dark red.
+pub struct FireIce;
+
+impl Palette for FireIce {
+    fn colours(&self) -> Vec<[f32; 3]> {
+        vec![
+            [0.0, 0.05, 0.2],
+            [0.2, 0.6, 1.0],
+            [1.0, 1.0, 1.0],
+            [1.0, 0.6, 0.1],
+            [0.4, 0.0, 0.0],
+        ]
+    }
+}
+
+/// The default gradient of [Ultra Fractal](https://www.ultrafractal.com/).
+pub struct UltraFractal;
+
+impl Palette for UltraFractal {
+    fn colours(&self) -> Vec<[f32; 3]> {
+        vec![
+            [0.0 / 255.0, 7.0 / 255.0, 100.0 / 255.0],
+            [32.0 / 255.0, 107.0 / 255.0, 203.0 / 255.0],
+            [237.0 / 255.0, 255.0 / 255.0, 255.0 / 255.0],
+            [255.0 / 255.0, 170.0 / 255.0, 0.0 / 255.0],
+            [0.0 / 255.0, 2.0 / 255.0, 0.0 / 255.0],
+        ]
+    }
+}
+
+/// A user-supplied gradient.
+pub struct Custom(pub Vec<[f32; 3]>);
+
+impl Custom {
+    /// Read a palette written by [`Palette::save`]: a JSON array of `[r, g, b]` colours.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = fs::File::open(path)?;
+        let colours: Vec<[f32; 3]> = serde_json::from_reader(io::BufReader::new(file))?;
+        if colours.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "a palette needs at least one colour",
+            ));
+        }
+        Ok(Custom(colours))
+    }
+}
+
+impl Palette for Custom {
+    fn colours(&self) -> Vec<[f32; 3]> {
+        self.0.clone()
+    }
+}
+
+/// The built-in [`Palette`]s, by name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum PaletteName {
+    Green,
+    Grayscale,
+    FireIce,
+    UltraFractal,
+}
+
+impl PaletteName {
+    pub fn palette(self) -> Box<dyn Palette> {
+        match self {
+            PaletteName::Green => Box::new(Green),
+            PaletteName::Grayscale => Box::new(Grayscale),
+            PaletteName::FireIce => Box::new(FireIce),
+            PaletteName::UltraFractal => Box::new(UltraFractal),
+        }
+    }
+
+    /// The palette after this one, for cycling through them.
+    pub fn next(self) -> Self {
+        match self {
+            PaletteName::Green => PaletteName::Grayscale,
+            PaletteName::Grayscale => PaletteName::FireIce,
+            PaletteName::FireIce => PaletteName::UltraFractal,
+            PaletteName::UltraFractal => PaletteName::Green,
+        }
+    }
+}
+
+/**
+[`bytemuck`]-compatible layout of `render.wgsl#Palette`: the gradient, sampled at
+[`PALETTE_STOPS`] evenly spaced positions.
+
+It's the same size whatever the palette and the screen, so swapping palettes only uploads
+[`PALETTE_STOPS`] colours, and each pixel's [`ColourRange::position`] stays valid. Uniform arrays
+need 16-byte elements, so each colour is padded to a `vec4`.
+*/
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug)]
+pub struct PaletteUniform {
+    pub colours: [[f32; 4]; PALETTE_STOPS],
+}
+
+impl PaletteUniform {
+    /// Lay out `palette` for the GPU, interpolating between its colours. A single colour is repeated.
+    pub fn new(palette: &dyn Palette) -> Self {
+        let colours = palette.colours();
+        assert!(!colours.is_empty(), "a palette needs at least one colour");
+
+        let mut uniform = Self::zeroed();
+        for (index, colour) in uniform.colours.iter_mut().enumerate() {
+            // Where this stop falls in `colours`.
+            let position = index as f32 / (PALETTE_STOPS - 1) as f32 * (colours.len() - 1) as f32;
+            let lower = (position.floor() as usize).min(colours.len().saturating_sub(2));
+            let upper = (lower + 1).min(colours.len() - 1);
+            let fraction = position - lower as f32;
+            let mix = |first: f32, second: f32| first * (1.0 - fraction) + second * fraction;
+            let (first, second) = (colours[lower], colours[upper]);
+            *colour = [
+                mix(first[0], second[0]),
+                mix(first[1], second[1]),
+                mix(first[2], second[2]),
+                1.0,
+            ];
+        }
+        uniform
+    }
+}
+
+/**
+[`bytemuck`]-compatible layout of `render.wgsl#PostProcessing`: adjustments made to every
+fragment after it's coloured, without touching the iterations.
+
+The default changes nothing.
+*/
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, PartialEq)]
+pub struct PostProcessing {
+    /// Above `1.0` lightens the mid-tones, and below `1.0` darkens them.
+    pub gamma: f32,
+    /// Added to every channel, from `-1.0` to `1.0`.
+    pub brightness: f32,
+    /// Scales each channel's distance from mid-grey.
+    pub contrast: f32,
+    _padding: f32,
+}
+
+impl Default for PostProcessing {
+    fn default() -> Self {
+        Self {
+            gamma: 1.0,
+            brightness: 0.0,
+            contrast: 1.0,
+            _padding: 0.0,
         }
     }
 }
 
+/**
+The fractional part of a pixel's [continuous escape
+count](https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Continuous_(smooth)_coloring),
+given its final `current_value`, and the `escape_radius` and `power` it was iterated with.
+
+Uses the renormalization `nu = log_d(log2(|z|) / log2(R))` where `R` is `escape_radius`
+and `d` is `power`, and returns `1 - nu`. A `|z|` barely over `R` sends `log2(|z|) / log2(R)`
+toward `1` (and below it from rounding), where the outer `log2` heads to negative
+infinity, so `|z|` is clamped to at least `R` and the result to `0.0..=1.0`.
+*/
+pub fn smooth_fraction(current_value: Complex, escape_radius: f32, power: u32) -> f32 {
+    let length = current_value.abs().max(escape_radius);
+    let nu = (length.log2() / escape_radius.log2()).log2() / (power as f32).log2();
+    (1.0 - nu).clamp(0.0, 1.0)
+}
+
+/// How many pixels each rayon task counts in [`HistogramColouring::record_escaped`].
+const RECORD_CHUNK_SIZE: usize = 16 * 1024;
+
+/// How close a temporally smoothed position has to get to its target before it snaps to it.
+const SETTLED_POSITION: f32 = 1e-4;
+
 /// Histogram-based colouring algorithm ([Wikipedia](https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Histogram_coloring)).
+///
+/// Pixels are bucketed by `iteration_count`. With [`HistogramColouring::with_smoothing`],
+/// each pixel is placed between its bucket and the next using [`smooth_fraction`], which
+/// removes the concentric bands.
+///
+/// While a view refines, every newly escaped pixel shifts the whole histogram, so colours swing
+/// from frame to frame. [`HistogramColouring::with_min_sample_fraction`] holds off colouring
+/// until enough pixels have escaped, and [`HistogramColouring::with_temporal_smoothing`] eases
+/// pixels toward their new positions.
+///
+/// Recording and colouring run on rayon's global thread pool, which the binary sizes with
+/// `--threads`.
 pub struct HistogramColouring {
+    smoothing: bool,
+    min_sample_fraction: f32,
+    temporal_smoothing: f32,
+    escape_radius: f32,
+    power: u32,
     total_samples: usize,
     bucket_labels: Vec<u32>,
     histogram: FnvHashMap<u32, u32>,
-    histogram_ranges: FnvHashMap<u32, f32>,
+    /// The start and end of each bucket's colour range.
+    histogram_ranges: FnvHashMap<u32, (f32, f32)>,
 }
 
 impl HistogramColouring {
@@ -37,8 +353,13 @@ impl HistogramColouring {
         let total_samples = 0;
         let bucket_labels: Vec<u32> = Vec::new();
         let histogram: FnvHashMap<u32, u32> = FnvHashMap::default();
-        let histogram_ranges: FnvHashMap<u32, f32> = FnvHashMap::default();
+        let histogram_ranges: FnvHashMap<u32, (f32, f32)> = FnvHashMap::default();
         Self {
+            smoothing: false,
+            min_sample_fraction: 0.0,
+            temporal_smoothing: 0.0,
+            escape_radius: compute::DEFAULT_ESCAPE_RADIUS,
+            power: compute::DEFAULT_POWER,
             total_samples,
             bucket_labels,
             histogram,
@@ -46,6 +367,57 @@ impl HistogramColouring {
         }
     }
 
+    /// Toggle smooth (continuous) colouring.
+    pub fn with_smoothing(mut self, smoothing: bool) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /**
+    Only colour once at least `min_sample_fraction` of the pixels have escaped. See
+    [`HistogramColouring::is_ready`].
+
+    Defaults to `0.0`, which colours from the first escaped pixel.
+    */
+    pub fn with_min_sample_fraction(mut self, min_sample_fraction: f32) -> Self {
+        self.min_sample_fraction = min_sample_fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    /**
+    Blend each already coloured pixel's new position with its old one, keeping
+    `temporal_smoothing` of the old. `0.0` jumps straight to the new position, and values
+    near `1.0` ease in slowly.
+
+    Positions only reach their targets when [`HistogramColouring::colour`] is called again, so
+    callers should keep calling it while it returns a dirty range, even once nothing escapes.
+    */
+    pub fn with_temporal_smoothing(mut self, temporal_smoothing: f32) -> Self {
+        self.temporal_smoothing = temporal_smoothing.clamp(0.0, 0.99);
+        self
+    }
+
+    /// Whether colours take more than one [`HistogramColouring::colour`] to settle.
+    pub fn is_temporally_smoothed(&self) -> bool {
+        self.temporal_smoothing > 0.0
+    }
+
+    /// Whether enough of `pixel_count` pixels have been recorded to colour them, per
+    /// [`HistogramColouring::with_min_sample_fraction`].
+    pub fn is_ready(&self, pixel_count: usize) -> bool {
+        self.total_samples as f32 >= self.min_sample_fraction * pixel_count as f32
+    }
+
+    /// Set the escape radius that pixels are iterated with, for [`smooth_fraction`].
+    pub fn set_escape_radius(&mut self, escape_radius: f32) {
+        self.escape_radius = escape_radius;
+    }
+
+    /// Set the power that pixels are iterated with, for [`smooth_fraction`].
+    pub fn set_power(&mut self, power: u32) {
+        self.power = power;
+    }
+
     pub fn reset(&mut self) {
         self.total_samples = 0;
         self.bucket_labels.clear();
@@ -53,82 +425,168 @@ impl HistogramColouring {
         self.histogram_ranges.clear();
     }
 
-    /// Update the colour output (`colour_ranges`) given some newly escaped pixels (`newly_escaped_pixels`).
-    pub fn update_colours(
+    /**
+    Update the colour output (`colour_ranges`) given some newly escaped pixels (`newly_escaped_pixels`).
+
+    Returns the range of `colour_ranges` that changed. See [`merge_dirty`].
+    */
+    pub fn update_colours<P: IteratedPixel>(
         &mut self,
         screen_size: screen::Size,
-        all_pixels: &[Pixel],
-        newly_escaped_pixels: &[Pixel],
+        all_pixels: &[P],
+        newly_escaped_pixels: &[P],
         colour_ranges: &mut [ColourRange],
-    ) {
+    ) -> Option<Range<usize>> {
         trace!("begin compute_colour_ranges");
 
+        let mut dirty = None;
         if !newly_escaped_pixels.is_empty() {
             debug_assert!(colour_ranges.len() == (screen_size.width * screen_size.height) as usize);
 
-            self.histogram_ranges.clear();
+            self.record_escaped(newly_escaped_pixels);
+            if self.is_ready(colour_ranges.len()) {
+                self.update_ranges();
+                dirty = self.colour(all_pixels, colour_ranges);
+            }
+        }
 
-            for pixel in newly_escaped_pixels {
-                debug_assert!(pixel.escaped == 1);
+        trace!("end compute_colour_ranges");
+        dirty
+    }
 
-                colour_ranges[pixel.y as usize * screen_size.width as usize + pixel.x as usize]
-                    .escaped = 1;
+    /**
+    Add `newly_escaped_pixels` to the histogram, without colouring anything.
 
-                let value = self
-                    .histogram
-                    .entry(pixel.iteration_count)
-                    .or_insert_with(|| {
-                        self.bucket_labels.push(pixel.iteration_count);
-                        0
-                    });
-                *value += 1;
-                self.total_samples += 1;
-            }
+    Each chunk of [`RECORD_CHUNK_SIZE`] pixels is counted into its own map in parallel, and the
+    maps are reduced pairwise, so no map is shared between threads. Only the merged counts touch
+    the histogram.
 
-            debug_assert_eq!(
-                self.total_samples,
-                self.histogram.values().map(|value| *value as usize).sum()
-            );
-
-            debug_assert!(
-                self.bucket_labels.len()
-                    == self
-                        .bucket_labels
-                        .iter()
-                        .copied()
-                        .collect::<FnvHashSet<u32>>()
-                        .len(),
-                "bucket_labels contains duplicates: {:?}",
-                self.bucket_labels
-            );
-            self.bucket_labels.sort();
-
-            let mut acc = 0;
-            let total_samples = self.total_samples as f32;
-            for bucket_label in &self.bucket_labels {
-                self.histogram_ranges
-                    .insert(*bucket_label, acc as f32 / total_samples);
-                acc += self.histogram.get(bucket_label).unwrap();
-            }
+    Call [`HistogramColouring::update_ranges`] before [`HistogramColouring::colour`] afterwards.
+    */
+    pub fn record_escaped<P: IteratedPixel>(&mut self, newly_escaped_pixels: &[P]) {
+        let counts = newly_escaped_pixels
+            .par_chunks(RECORD_CHUNK_SIZE)
+            .map(|pixels| {
+                let mut counts: FnvHashMap<u32, u32> = FnvHashMap::default();
+                for pixel in pixels {
+                    debug_assert!(pixel.is_escaped());
+                    *counts.entry(pixel.iteration_count()).or_insert(0) += 1;
+                }
+                counts
+            })
+            .reduce(FnvHashMap::default, |mut first, second| {
+                for (iteration_count, count) in second {
+                    *first.entry(iteration_count).or_insert(0) += count;
+                }
+                first
+            });
 
-            colour_ranges
-                .par_iter_mut()
-                .enumerate()
-                .for_each(|(index, colour_range)| {
-                    let pixel = all_pixels[index];
-                    if pixel.escaped == 1 {
-                        colour_range.value = self
-                            .histogram_ranges
-                            .get(&pixel.iteration_count)
-                            .copied()
-                            .unwrap_or_else(|| {
-                                panic!("{} was not in histogram_ranges", pixel.iteration_count)
-                            })
-                    }
-                });
+        for (iteration_count, count) in counts {
+            let value = self.histogram.entry(iteration_count).or_insert_with(|| {
+                self.bucket_labels.push(iteration_count);
+                0
+            });
+            *value += count;
+            self.total_samples += count as usize;
         }
 
-        trace!("end compute_colour_ranges");
+        debug_assert_eq!(
+            self.total_samples,
+            self.histogram.values().map(|value| *value as usize).sum()
+        );
+    }
+
+    /// Recompute each bucket's colour range from the histogram.
+    pub fn update_ranges(&mut self) {
+        debug_assert!(
+            self.bucket_labels.len()
+                == self
+                    .bucket_labels
+                    .iter()
+                    .copied()
+                    .collect::<FnvHashSet<u32>>()
+                    .len(),
+            "bucket_labels contains duplicates: {:?}",
+            self.bucket_labels
+        );
+        self.bucket_labels.sort();
+
+        self.histogram_ranges.clear();
+        let mut acc = 0;
+        let total_samples = self.total_samples as f32;
+        for bucket_label in &self.bucket_labels {
+            let start = acc as f32 / total_samples;
+            acc += self.histogram.get(bucket_label).unwrap();
+            self.histogram_ranges
+                .insert(*bucket_label, (start, acc as f32 / total_samples));
+        }
+    }
+
+    /**
+    Colour every escaped pixel in `all_pixels` with the current colour ranges.
+
+    `all_pixels` and `colour_ranges` are indexed the same way. Every escaped pixel must have
+    been recorded with [`HistogramColouring::record_escaped`].
+
+    Returns the range of `colour_ranges` that changed. Adding pixels to the histogram shifts
+    every bucket, so this usually spans all of the escaped pixels.
+
+    Doesn't check [`HistogramColouring::is_ready`], since once a view has finished iterating,
+    its pixels should be coloured however few escaped.
+    */
+    pub fn colour<P: IteratedPixel>(
+        &self,
+        all_pixels: &[P],
+        colour_ranges: &mut [ColourRange],
+    ) -> Option<Range<usize>> {
+        colour_ranges
+            .par_iter_mut()
+            .enumerate()
+            .map(|(index, colour_range)| {
+                let pixel = all_pixels[index];
+                if !pixel.is_escaped() {
+                    return None;
+                }
+
+                let (start, end) = self
+                    .histogram_ranges
+                    .get(&pixel.iteration_count())
+                    .copied()
+                    .unwrap_or_else(|| {
+                        panic!("{} was not in histogram_ranges", pixel.iteration_count())
+                    });
+                let position = if self.smoothing {
+                    start
+                        + (end - start)
+                            * smooth_fraction(pixel.current_value(), self.escape_radius, self.power)
+                } else {
+                    start
+                };
+                let position = if colour_range.escaped == ESCAPED {
+                    self.blend(colour_range.position, position)
+                } else {
+                    position
+                };
+                update(
+                    colour_range,
+                    ColourRange {
+                        escaped: ESCAPED,
+                        position,
+                    },
+                    index,
+                )
+            })
+            .reduce(|| None, merge_dirty)
+    }
+
+    /// Move `old` toward `new`, per [`HistogramColouring::with_temporal_smoothing`].
+    fn blend(&self, old: f32, new: f32) -> f32 {
+        let blended = new + (old - new) * self.temporal_smoothing;
+        if (blended - new).abs() < SETTLED_POSITION {
+            new
+        } else {
+            blended
+        }
     }
 }
 
@@ -137,3 +595,428 @@ impl Default for HistogramColouring {
         Self::new()
     }
 }
+
+/// Which algorithm colours escaped pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColouringMode {
+    /// [`HistogramColouring`], by iteration count.
+    Histogram,
+    /// [`OrbitTrapColouring`], by how close each orbit came to the trap.
+    OrbitTrap,
+    /// [`DistanceColouring`], by how far each pixel is from the set.
+    Distance,
+    /**
+    By [`smooth_fraction`]'s continuous escape count, scaled logarithmically to
+    [`crate::renderer::Renderer::max_iterations`], in `render.wgsl#smooth_colour_range`.
+
+    Pixels stay on the GPU, in screen order, so nothing is copied back between frames. Interior
+    pixels are always flat, and it needs [`crate::compute::Precision::Single`].
+    */
+    GpuSmooth,
+    /// [`RootColouring`], by which root [`compute::FractalKind::Newton`] converged to.
+    Root,
+    /// [`EscapeTimeColouring`], by iteration count without equalising it.
+    EscapeTime,
+}
+
+/**
+Orbit trap colouring ([Wikipedia](https://en.wikipedia.org/wiki/Orbit_trap)).
+
+Escaped pixels are coloured by [`IteratedPixel::min_trap_distance`], the closest their orbit
+came to the [`compute::TrapShape`] selected in the compute shader. Orbits that touch the trap
+sample the end of the palette, and the position falls off exponentially with distance.
+*/
+pub struct OrbitTrapColouring {
+    falloff: f32,
+}
+
+impl OrbitTrapColouring {
+    pub fn new() -> Self {
+        Self { falloff: 4.0 }
+    }
+
+    /// How quickly the colour fades with distance from the trap. Higher gives thinner bands.
+    pub fn with_falloff(mut self, falloff: f32) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /**
+    Colour every escaped pixel in `all_pixels`, which is indexed the same way as `colour_ranges`.
+
+    Returns the range of `colour_ranges` that changed, which only covers the newly escaped pixels.
+    */
+    pub fn colour<P: IteratedPixel>(
+        &self,
+        all_pixels: &[P],
+        colour_ranges: &mut [ColourRange],
+    ) -> Option<Range<usize>> {
+        colour_ranges
+            .par_iter_mut()
+            .enumerate()
+            .map(|(index, colour_range)| {
+                let pixel = all_pixels[index];
+                if !pixel.is_escaped() {
+                    return None;
+                }
+
+                let position = (-self.falloff * pixel.min_trap_distance()).exp();
+                update(
+                    colour_range,
+                    ColourRange {
+                        escaped: ESCAPED,
+                        position,
+                    },
+                    index,
+                )
+            })
+            .reduce(|| None, merge_dirty)
+    }
+}
+
+impl Default for OrbitTrapColouring {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/**
+Distance estimation colouring ([Wikipedia](https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Distance_estimates)).
+
+Escaped pixels are coloured by [`IteratedPixel::distance_estimate`], from the start of the
+palette at the set's boundary to the end of it `thickness` pixels away. This draws the boundary
+as a sharp, anti-aliased line even when few iterations have run. Pixels that never escape aren't
+coloured, so the interior stays black.
+*/
+pub struct DistanceColouring {
+    thickness: f32,
+}
+
+impl DistanceColouring {
+    pub fn new() -> Self {
+        Self { thickness: 4.0 }
+    }
+
+    /// How many pixels away from the boundary the palette reaches its end.
+    pub fn with_thickness(mut self, thickness: f32) -> Self {
+        self.thickness = thickness;
+        self
+    }
+
+    /**
+    Colour every escaped pixel in `all_pixels`, which is indexed the same way as `colour_ranges`.
+
+    Returns the range of `colour_ranges` that changed, which only covers the newly escaped pixels.
+    */
+    pub fn colour<P: IteratedPixel>(
+        &self,
+        all_pixels: &[P],
+        colour_ranges: &mut [ColourRange],
+    ) -> Option<Range<usize>> {
+        colour_ranges
+            .par_iter_mut()
+            .enumerate()
+            .map(|(index, colour_range)| {
+                let pixel = all_pixels[index];
+                if !pixel.is_escaped() {
+                    return None;
+                }
+
+                let position = (pixel.distance_estimate() / self.thickness).clamp(0.0, 1.0);
+                update(
+                    colour_range,
+                    ColourRange {
+                        escaped: ESCAPED,
+                        position,
+                    },
+                    index,
+                )
+            })
+            .reduce(|| None, merge_dirty)
+    }
+}
+
+impl Default for DistanceColouring {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/**
+Escape time colouring: each escaped pixel samples the palette at its
+[`IteratedPixel::iteration_count`] divided by the maximum iteration count.
+
+Unlike [`HistogramColouring`], a pixel's colour doesn't depend on any other pixel, so colours
+never shift as a view refines, and nothing has to be recorded. Most pixels escape early, though,
+so most of the view samples the start of the palette.
+*/
+pub struct EscapeTimeColouring;
+
+impl EscapeTimeColouring {
+    /**
+    Colour every escaped pixel in `all_pixels`, which is indexed the same way as `colour_ranges`,
+    for pixels iterated up to `max_iterations`.
+
+    Returns the range of `colour_ranges` that changed, which only covers the newly escaped pixels
+    unless `max_iterations` changed.
+    */
+    pub fn colour<P: IteratedPixel>(
+        &self,
+        all_pixels: &[P],
+        max_iterations: u32,
+        colour_ranges: &mut [ColourRange],
+    ) -> Option<Range<usize>> {
+        colour_ranges
+            .par_iter_mut()
+            .enumerate()
+            .map(|(index, colour_range)| {
+                let pixel = all_pixels[index];
+                if !pixel.is_escaped() {
+                    return None;
+                }
+
+                let position = pixel.iteration_count() as f32 / max_iterations.max(1) as f32;
+                update(
+                    colour_range,
+                    ColourRange {
+                        escaped: ESCAPED,
+                        position: position.min(1.0),
+                    },
+                    index,
+                )
+            })
+            .reduce(|| None, merge_dirty)
+    }
+}
+
+/**
+[`ColourRange::escaped`] for a pixel coloured by [`HeatmapColouring`]. `render.wgsl` maps its
+`position` to a fixed blue-to-red scale instead of sampling the palette.
+*/
+pub const HEATMAP: u32 = 3;
+
+/**
+A debugging view of where the compute cost goes: every escaped or interior pixel is coloured by
+its [`IteratedPixel::iteration_count`] divided by the maximum iteration count, from blue for
+pixels that escaped immediately to red for ones that ran to the maximum.
+
+Views near the boundary of the set are slow because most of their pixels are red here.
+*/
+pub struct HeatmapColouring;
+
+impl HeatmapColouring {
+    /**
+    Colour every pixel in `all_pixels` that has escaped or reached `max_iterations`. `all_pixels`
+    is indexed the same way as `colour_ranges`. Pixels that are still iterating are left alone.
+
+    Returns the range of `colour_ranges` that changed.
+    */
+    pub fn colour<P: IteratedPixel>(
+        &self,
+        all_pixels: &[P],
+        max_iterations: u32,
+        colour_ranges: &mut [ColourRange],
+    ) -> Option<Range<usize>> {
+        colour_ranges
+            .par_iter_mut()
+            .enumerate()
+            .map(|(index, colour_range)| {
+                let pixel = all_pixels[index];
+                if pixel.is_iterating() && pixel.iteration_count() < max_iterations {
+                    return None;
+                }
+
+                let position = pixel.iteration_count() as f32 / max_iterations.max(1) as f32;
+                update(
+                    colour_range,
+                    ColourRange {
+                        escaped: HEATMAP,
+                        position: position.min(1.0),
+                    },
+                    index,
+                )
+            })
+            .reduce(|| None, merge_dirty)
+    }
+}
+
+/// How many roots [`compute::FractalKind::Newton`] can converge to.
+const ROOT_COUNT: u32 = 3;
+
+/**
+Colouring for [`compute::FractalKind::Newton`], by which root each pixel converged to.
+
+The palette is split into one section per root, and each pixel is placed in its root's section
+by [`IteratedPixel::iteration_count`]: pixels that converge immediately sample the end of the
+section, and slower ones fall toward its start. Palettes with several hues give each root its
+own.
+*/
+pub struct RootColouring {
+    falloff: f32,
+}
+
+impl RootColouring {
+    pub fn new() -> Self {
+        Self { falloff: 0.1 }
+    }
+
+    /// How quickly the colour fades with iteration count. Higher darkens slow pixels sooner.
+    pub fn with_falloff(mut self, falloff: f32) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /**
+    Colour every converged pixel in `all_pixels`, which is indexed the same way as `colour_ranges`.
+
+    Returns the range of `colour_ranges` that changed, which only covers the newly converged pixels.
+    */
+    pub fn colour<P: IteratedPixel>(
+        &self,
+        all_pixels: &[P],
+        colour_ranges: &mut [ColourRange],
+    ) -> Option<Range<usize>> {
+        colour_ranges
+            .par_iter_mut()
+            .enumerate()
+            .map(|(index, colour_range)| {
+                let pixel = all_pixels[index];
+                if !pixel.is_escaped() {
+                    return None;
+                }
+
+                let shade = (-self.falloff * pixel.iteration_count() as f32).exp();
+                let position =
+                    (pixel.root_index().min(ROOT_COUNT - 1) as f32 + shade) / ROOT_COUNT as f32;
+                update(
+                    colour_range,
+                    ColourRange {
+                        escaped: ESCAPED,
+                        position,
+                    },
+                    index,
+                )
+            })
+            .reduce(|| None, merge_dirty)
+    }
+}
+
+impl Default for RootColouring {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How quickly [`InteriorColouring::OrbitTrap`] fades with distance from the trap. The same as
+/// [`OrbitTrapColouring`]'s default.
+const INTERIOR_TRAP_FALLOFF: f32 = 4.0;
+
+/**
+How pixels that never escape are coloured, alongside the [`ColouringMode`] that colours the
+escaped ones.
+
+A pixel is in the interior once it reaches the maximum iteration count without escaping. Its
+[`ColourRange::escaped`] is set to [`INTERIOR`], and `render.wgsl` samples the palette at its
+`position` without [`ColouringMode`]'s shaping.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InteriorColouring {
+    /// Leave the interior black.
+    Flat,
+    /// By `|z|` after the last iteration, which stays within `2.0` for interior points.
+    Magnitude,
+    /// By [`IteratedPixel::min_trap_distance`], like [`OrbitTrapColouring`].
+    OrbitTrap,
+}
+
+impl InteriorColouring {
+    /// Corresponds to `compute.wgsl#interior_colouring`.
+    pub fn index(self) -> u32 {
+        match self {
+            InteriorColouring::Flat => 0,
+            InteriorColouring::Magnitude => 1,
+            InteriorColouring::OrbitTrap => 2,
+        }
+    }
+
+    /**
+    Colour every pixel in `all_pixels` that reached `max_iterations` without escaping. `all_pixels`
+    is indexed the same way as `colour_ranges`.
+
+    Returns the range of `colour_ranges` that changed. [`InteriorColouring::Flat`] changes nothing.
+    */
+    pub fn colour<P: IteratedPixel>(
+        self,
+        all_pixels: &[P],
+        max_iterations: u32,
+        colour_ranges: &mut [ColourRange],
+    ) -> Option<Range<usize>> {
+        if self == InteriorColouring::Flat {
+            return None;
+        }
+
+        colour_ranges
+            .par_iter_mut()
+            .enumerate()
+            .map(|(index, colour_range)| {
+                let pixel = all_pixels[index];
+                if !pixel.is_iterating() || pixel.iteration_count() < max_iterations {
+                    return None;
+                }
+
+                let position = match self {
+                    InteriorColouring::Flat => unreachable!(),
+                    InteriorColouring::Magnitude => pixel.current_value().abs() / 2.0,
+                    InteriorColouring::OrbitTrap => {
+                        (-INTERIOR_TRAP_FALLOFF * pixel.min_trap_distance()).exp()
+                    }
+                };
+                update(
+                    colour_range,
+                    ColourRange {
+                        escaped: INTERIOR,
+                        position: position.clamp(0.0, 1.0),
+                    },
+                    index,
+                )
+            })
+            .reduce(|| None, merge_dirty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escaped(position: f32) -> ColourRange {
+        ColourRange {
+            escaped: ESCAPED,
+            position,
+        }
+    }
+
+    #[test]
+    fn ease_colour_ranges_moves_alpha_of_the_way() {
+        let mut shown = [escaped(0.0), ColourRange::default(), escaped(0.5)];
+        let targets = [escaped(1.0), escaped(0.25), escaped(0.5)];
+
+        let unsettled = ease_colour_ranges(&mut shown, &targets, 0.25);
+        assert_eq!(shown[0], escaped(0.25));
+        // Newly escaped and unchanged pixels are shown as they are.
+        assert_eq!(shown[1], escaped(0.25));
+        assert_eq!(shown[2], escaped(0.5));
+        assert_eq!(unsettled, Some(0..1));
+    }
+
+    #[test]
+    fn ease_colour_ranges_settles() {
+        let mut shown = [escaped(0.0)];
+        let targets = [escaped(1.0)];
+        let mut frames = 0;
+        while ease_colour_ranges(&mut shown, &targets, 0.5).is_some() {
+            frames += 1;
+            assert!(frames < 20);
+        }
+        assert_eq!(shown, targets);
+    }
+}
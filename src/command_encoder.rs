@@ -1,5 +1,17 @@
 //! Extensions for [`wgpu::CommandEncoder`].
 
+/**
+Where [`CommandEncoderExt::with_timestamps`] writes its timestamps: `begin_index` before,
+and `begin_index + 1` after.
+
+`query_set` must be a [`wgpu::QueryType::Timestamp`] query set.
+*/
+#[derive(Clone, Copy)]
+pub struct PassTimestamps<'a> {
+    pub query_set: &'a wgpu::QuerySet,
+    pub begin_index: u32,
+}
+
 /// Extension trait for [`wgpu::CommandEncoder`].
 pub trait CommandEncoderExt {
     /// A scoped alternative to [`wgpu::CommandEncoder::begin_compute_pass`].
@@ -15,6 +27,17 @@ pub trait CommandEncoderExt {
         descriptor: &wgpu::RenderPassDescriptor<'pass, '_>,
         function: impl FnOnce(&mut wgpu::RenderPass<'pass>) -> A,
     ) -> A;
+
+    /**
+    Write timestamps before and after `function`, e.g. around a call to [`CommandEncoderExt::with_compute_pass`].
+
+    Does nothing extra when `timestamps` is `None`.
+    */
+    fn with_timestamps<A>(
+        &mut self,
+        timestamps: Option<PassTimestamps>,
+        function: impl FnOnce(&mut Self) -> A,
+    ) -> A;
 }
 
 impl CommandEncoderExt for wgpu::CommandEncoder {
@@ -35,4 +58,19 @@ impl CommandEncoderExt for wgpu::CommandEncoder {
         let mut render_pass = self.begin_render_pass(descriptor);
         function(&mut render_pass)
     }
+
+    fn with_timestamps<A>(
+        &mut self,
+        timestamps: Option<PassTimestamps>,
+        function: impl FnOnce(&mut Self) -> A,
+    ) -> A {
+        if let Some(timestamps) = timestamps {
+            self.write_timestamp(timestamps.query_set, timestamps.begin_index);
+        }
+        let result = function(self);
+        if let Some(timestamps) = timestamps {
+            self.write_timestamp(timestamps.query_set, timestamps.begin_index + 1);
+        }
+        result
+    }
 }
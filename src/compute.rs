@@ -1,8 +1,286 @@
 //! Compute shader functions.
 
-/// Workgroup size for `compute.wsgl#mandelbrot`.
+use std::path::Path;
+
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+
+use crate::{pixel::Complex, screen, shader};
+
+pub mod orbit;
+pub mod reference;
+
+/// The initial value of [`Uniforms::escape_radius`].
+pub const DEFAULT_ESCAPE_RADIUS: f32 = 2.0;
+
+/// The initial value of [`Uniforms::max_iterations`].
+pub const DEFAULT_MAX_ITERATIONS: u32 = 1000;
+
+/// The initial value of [`Uniforms::power`], which iterates the Mandelbrot set itself.
+pub const DEFAULT_POWER: u32 = 2;
+
+/**
+The initial value of `compute.wgsl#iterations_per_frame`.
+
+One iteration per frame keeps each compute pass short, however many pixels are left.
+*/
+pub const DEFAULT_ITERATIONS_PER_FRAME: u32 = 1;
+
+/**
+Scales [`Uniforms::max_iterations`] with the zoom, for
+[`crate::renderer::Renderer::set_auto_iterations`]: `base` at zoom `1`, and `per_doubling` more
+each time the zoom doubles. Deeper views need more iterations before their detail shows.
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoIterations {
+    pub base: u32,
+    pub per_doubling: f64,
+}
+
+impl AutoIterations {
+    pub fn new() -> Self {
+        Self {
+            base: DEFAULT_MAX_ITERATIONS,
+            per_doubling: 100.0,
+        }
+    }
+
+    pub fn with_base(mut self, base: u32) -> Self {
+        self.base = base;
+        self
+    }
+
+    pub fn with_per_doubling(mut self, per_doubling: f64) -> Self {
+        self.per_doubling = per_doubling;
+        self
+    }
+
+    /// The maximum iteration count for `zoom`. Zooming out past `1` stays at `base`.
+    pub fn max_iterations(&self, zoom: f64) -> u32 {
+        let extra = self.per_doubling * zoom.log2().max(0.0);
+        (self.base as f64 + extra).round().min(u32::MAX as f64) as u32
+    }
+}
+
+impl Default for AutoIterations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The largest supported [`Uniforms::power`].
+pub const MAX_POWER: u32 = 8;
+
+/**
+[`bytemuck`]-compatible layout of `compute.wgsl#Uniforms`, which `render.wgsl` binds as well: the
+view and iteration settings that both shaders read, in one uniform buffer.
+
+The fields are ordered so that each sits at a multiple of its WGSL alignment with no padding:
+the `vec2`s, aligned to 8 bytes, come first, then the 4-byte scalars, for 32 bytes in all, which is
+already a multiple of the struct's 16-byte alignment in the uniform address space.
+*/
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, PartialEq)]
+pub struct Uniforms {
+    /// The size that pixels are iterated at, or the whole view's size when rendering it in tiles.
+    pub screen_size: screen::Size,
+    /// The centre of the view, rounded to `f32`. `compute_f64.wgsl` binds its own `f64` copy.
+    pub origin: Complex,
+    /**
+    The view spans from `-(2 / zoom)` to `2 / zoom` along the screen's shorter side, and further
+    along the longer side, so that pixels stay square. Rounded to `f32` like `origin`.
+    */
+    pub zoom: f32,
+    /// A pixel has escaped once its value is at least `escape_radius` from zero.
+    pub escape_radius: f32,
+    /**
+    Pixels stop iterating once they reach `max_iterations`, and pick up where they left off if it's
+    raised.
+    */
+    pub max_iterations: u32,
+    /// Iterate `z^power + c`, where `power` is from 2 to [`MAX_POWER`].
+    pub power: u32,
+}
+
+/// The fractal iterated by the compute shader.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum FractalKind {
+    Mandelbrot,
+    /// The Julia set for `z^2 + c`, where `c` is written to `compute.wgsl#julia_c`.
+    Julia {
+        c: Complex,
+    },
+    /// The Burning Ship fractal, which takes the absolute value of both components of `z` before squaring.
+    BurningShip,
+    /**
+    Newton's method for `z^3 - 1`, in `newton.wgsl`. Pixels converge to a root instead of escaping,
+    so they're coloured with [`crate::colour::RootColouring`].
+
+    Not available at [`Precision::Double`].
+    */
+    Newton,
+    /**
+    [`FractalKind::Mandelbrot`] on the left half of the screen, and on the right the Julia set for
+    `c`, which is written to `compute.wgsl#julia_c`. See `compute.wgsl#split`.
+
+    Not available at [`Precision::Double`].
+    */
+    Split {
+        c: Complex,
+    },
+}
+
+impl FractalKind {
+    /// The `compute.wgsl` entry point that iterates this fractal.
+    pub fn entry_point(&self) -> &'static str {
+        match self {
+            FractalKind::Mandelbrot => "mandelbrot",
+            FractalKind::Julia { .. } => "julia",
+            FractalKind::BurningShip => "burning_ship",
+            FractalKind::Newton => "newton",
+            FractalKind::Split { .. } => "split",
+        }
+    }
+}
+
+/// What `compute.wgsl#trap_distance` measures each pixel's orbit against, for [`crate::colour::OrbitTrapColouring`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrapShape {
+    /// The origin.
+    Point,
+    /// The real axis.
+    Line,
+    /// Both axes.
+    Cross,
+}
+
+impl TrapShape {
+    /// The value of `compute.wgsl#trap_shape` that selects this shape.
+    pub fn index(&self) -> u32 {
+        match self {
+            TrapShape::Point => 0,
+            TrapShape::Line => 1,
+            TrapShape::Cross => 2,
+        }
+    }
+}
+
+/**
+The floating point precision of the compute shader.
+
+With [`Precision::Single`], neighbouring pixels become indistinguishable once the distance
+between them drops below `f32`'s resolution (about `1.2e-7` near `1.0`). Pixels are
+`4.0 / (zoom * width)` apart, so on a 1920 pixel wide window the image turns blocky past
+`zoom` ≈ `2e4`, about 100 scroll steps in. [`Precision::Double`] holds up until `zoom` ≈ `1e13`.
+[`Precision::Perturbed`] goes further, until the `f64` origin itself runs out of precision.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision {
+    /// Use `compute.wgsl`.
+    Single,
+    /// Use `compute_f64.wgsl`. Requires [`wgpu::Features::SHADER_FLOAT64`].
+    Double,
+    /**
+    Use `compute.wgsl` with `perturbation.wgsl`, which iterates [`FractalKind::Mandelbrot`] as
+    single precision offsets from an [`orbit::ReferenceOrbit`]. Other fractals are iterated at
+    single precision as usual.
+    */
+    Perturbed,
+}
+
+impl Precision {
+    /**
+    The shader files that are concatenated into the compute shader at this precision.
+
+    Except at double precision, `newton.wgsl` is appended for [`FractalKind::Newton`].
+    */
+    pub fn shader_files(&self) -> &'static [&'static str] {
+        match self {
+            Precision::Single => &["compute.wgsl", "newton.wgsl"],
+            Precision::Double => &["compute_f64.wgsl"],
+            Precision::Perturbed => &["compute.wgsl", "newton.wgsl", "perturbation.wgsl"],
+        }
+    }
+
+    /**
+    The compute shader's source code, with its entry points compiled for `workgroup_size`
+    invocations per workgroup, and `compute.wgsl#mandelbrot_multisampled` for `samples_per_pixel`
+    samples. Read from `shader_dir` if there is one; see [`shader::read`].
+
+    wgpu can't override shader constants when creating a pipeline, so the values written in the
+    WGSL are replaced in the source instead.
+    */
+    pub fn shader_source(
+        &self,
+        workgroup_size: u32,
+        samples_per_pixel: u32,
+        shader_dir: Option<&Path>,
+    ) -> Result<String, shader::ShaderReadError> {
+        let source = self
+            .shader_files()
+            .iter()
+            .map(|name| shader::read(name, shader_dir))
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n");
+        Ok(source
+            .replace(
+                &format!("@workgroup_size(1, {}, 1)", MANDELBROT_WORKGROUP_SIZE_Y),
+                &format!("@workgroup_size(1, {}, 1)", workgroup_size),
+            )
+            .replace(
+                &format!(
+                    "let MANDELBROT_WORKGROUP_SIZE_Y = {}u;",
+                    MANDELBROT_WORKGROUP_SIZE_Y
+                ),
+                &format!("let MANDELBROT_WORKGROUP_SIZE_Y = {}u;", workgroup_size),
+            )
+            .replace(
+                "let SAMPLES_PER_PIXEL = 1u;",
+                &format!("let SAMPLES_PER_PIXEL = {}u;", samples_per_pixel),
+            ))
+    }
+
+    /**
+    The entry point that iterates `fractal_kind` at this precision, with `samples_per_pixel`
+    samples. Only [`Precision::Single`]'s [`FractalKind::Mandelbrot`] takes more than one; see
+    [`supports_multisampling`](Precision::supports_multisampling).
+    */
+    pub fn entry_point(&self, fractal_kind: FractalKind, samples_per_pixel: u32) -> &'static str {
+        match (self, fractal_kind) {
+            (Precision::Single, FractalKind::Mandelbrot) if samples_per_pixel > 1 => {
+                "mandelbrot_multisampled"
+            }
+            (Precision::Perturbed, FractalKind::Mandelbrot) => "mandelbrot_perturbed",
+            _ => fractal_kind.entry_point(),
+        }
+    }
+
+    /// Whether [`Precision::entry_point`] has a multisampled version for `fractal_kind`.
+    pub fn supports_multisampling(&self, fractal_kind: FractalKind) -> bool {
+        matches!(
+            (self, fractal_kind),
+            (Precision::Single, FractalKind::Mandelbrot)
+        )
+    }
+}
+
+/// The workgroup size written in `compute.wsgl#mandelbrot`. See [`workgroup_size`].
 pub const MANDELBROT_WORKGROUP_SIZE_Y: u32 = 64;
 
+/**
+The workgroup size to compile `compute.wgsl` with on a device with `limits`: `requested` if
+given, or [`MANDELBROT_WORKGROUP_SIZE_Y`], clamped to what the device supports.
+
+The best size varies between GPUs. `--workgroup-size` with `--benchmark` compares them.
+*/
+pub fn workgroup_size(requested: Option<u32>, limits: &wgpu::Limits) -> u32 {
+    requested
+        .unwrap_or(MANDELBROT_WORKGROUP_SIZE_Y)
+        .min(limits.max_compute_workgroup_size_y)
+        .min(limits.max_compute_invocations_per_workgroup)
+        .max(1)
+}
+
 /// Corresponds to `compute.wsgl#MANDELBROT_DISPATCH_SIZE_Y`.
 pub const MANDELBROT_DISPATCH_SIZE_Y: u32 = 1024;
 
@@ -66,13 +344,89 @@ use 1024 for the dispatch `y` size. Which means we need to divide `total_work` i
 of `1024 (dispatch y size) * 64 (workgroup y size) = 65536`. This gives `(total_work / (1024 * 64) + 1, 1024, 1)`.
 This means the correct pixel index formula is `global_invocation_id.x * (1024 * 64) + global_invocation_id.y`.
 
+The `x` size can still pass `max_per_dimension` once `total_work` exceeds `65535 * 65536`. Then
+the `x` rows are stacked along `z`: a dispatch size of `(x, 1024, z)` covers `x * z` chunks, and
+the index becomes `(global_invocation_id.z * x + global_invocation_id.x) * (1024 * 64) + global_invocation_id.y`,
+where the shader reads `x` from `@builtin(num_workgroups)`. `x` is rounded up so that `x * z`
+covers every chunk, which can dispatch up to `z - 1` redundant chunks on top of the one above.
+
+64 is a guess that suits my GPU, so `workgroup_size` is a parameter, and the shader is compiled
+with the same size (see [`Precision::shader_source`]). The chunks are then `1024 * workgroup_size`
+long. The workgroup size must be the same one the shader was compiled with.
+
 [^stackoverflow-workgroups]: <https://stackoverflow.com/questions/34638336/calculating-the-right-number-of-workgroups-and-their-size-opencl>
 
 [^geforce-20]: <https://en.wikipedia.org/wiki/GeForce_20_series#GeForce_20_(20xx)_series_for_desktops>
 */
-pub fn mandelbrot_dispatch_size(total_work: usize) -> (u32, u32, u32) {
-    let x = (total_work / (MANDELBROT_DISPATCH_SIZE_Y * MANDELBROT_WORKGROUP_SIZE_Y) as usize + 1)
-        .try_into()
-        .unwrap();
-    (x, MANDELBROT_DISPATCH_SIZE_Y, 1)
+pub fn mandelbrot_dispatch_size(
+    total_work: usize,
+    workgroup_size: u32,
+    max_per_dimension: u32,
+) -> (u32, u32, u32) {
+    let chunks = total_work / (MANDELBROT_DISPATCH_SIZE_Y * workgroup_size) as usize + 1;
+    let max_per_dimension = max_per_dimension as usize;
+
+    let z = (chunks + max_per_dimension - 1) / max_per_dimension;
+    let x = (chunks + z - 1) / z;
+    assert!(
+        z <= max_per_dimension,
+        "{} invocations don't fit in one dispatch",
+        total_work
+    );
+
+    (x as u32, MANDELBROT_DISPATCH_SIZE_Y, z as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The most workgroups per dimension that `wgpu::Limits::default` allows.
+    const MAX_PER_DIMENSION: u32 = 65535;
+
+    const WORKGROUP_SIZE: u32 = 64;
+
+    /// Check that the dispatch for `total_work` covers it without passing the limit.
+    fn assert_covers(total_work: usize) {
+        let (x, y, z) = mandelbrot_dispatch_size(total_work, WORKGROUP_SIZE, MAX_PER_DIMENSION);
+        for dimension in [x, y, z] {
+            assert!(
+                dimension <= MAX_PER_DIMENSION,
+                "{:?} for {}",
+                (x, y, z),
+                total_work
+            );
+        }
+        let invocations = x as usize * y as usize * z as usize * WORKGROUP_SIZE as usize;
+        assert!(
+            invocations >= total_work,
+            "{:?} for {}",
+            (x, y, z),
+            total_work
+        );
+    }
+
+    #[test]
+    fn dispatch_covers_one_pixel() {
+        assert_covers(1);
+    }
+
+    #[test]
+    fn dispatch_covers_one_dimension_of_workgroups() {
+        assert_covers(65535 * 64);
+    }
+
+    #[test]
+    fn dispatch_covers_8k() {
+        assert_covers(7680 * 4320);
+    }
+
+    #[test]
+    fn dispatch_spills_into_z() {
+        let total_work =
+            2 * MAX_PER_DIMENSION as usize * (MANDELBROT_DISPATCH_SIZE_Y * WORKGROUP_SIZE) as usize;
+        assert_covers(total_work);
+        let (_, _, z) = mandelbrot_dispatch_size(total_work, WORKGROUP_SIZE, MAX_PER_DIMENSION);
+        assert!(z > 1);
+    }
 }
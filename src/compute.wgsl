@@ -14,15 +14,27 @@ fn multiply_complex(first: Complex, second: Complex) -> Complex {
   return Complex(a - c, b);
 }
 
+// `value^power`, for `power >= 1`.
+fn power_complex(value: Complex, power: u32) -> Complex {
+  var result = value;
+  for (var i = 1u; i < power; i = i + 1u) {
+    result = multiply_complex(result, value);
+  }
+  return result;
+}
+
 fn add_complex(first: Complex, second: Complex) -> Complex {
   return Complex(first.real + second.real, first.imaginary + second.imaginary);
 }
 
-fn length_complex(value: Complex) -> f32 {
-  return sqrt(pow(value.real, 2.0) + pow(value.imaginary, 2.0));
+fn abs_complex(value: Complex) -> Complex {
+  return Complex(abs(value.real), abs(value.imaginary));
 }
 
-let ESCAPE_THRESHOLD: f32 = 2.0;
+fn squared_length_complex(value: Complex) -> f32 {
+  let z = vec2<f32>(value.real, value.imaginary);
+  return dot(z, z);
+}
 
 struct Pixel{
   x : u32,
@@ -30,6 +42,17 @@ struct Pixel{
   escaped : u32,
   current_value : Complex,
   iteration_count : u32,
+  // The closest `current_value` has come to the orbit trap.
+  min_trap_distance : f32,
+  // `dz/dc`, the derivative of `current_value` with respect to the pixel's coordinate.
+  derivative : Complex,
+  // Set by `distance_estimate` every iteration, but only meaningful once the pixel has escaped.
+  distance_estimate : f32,
+  // Which root `newton` converged to. Unused by the other fractals.
+  root_index : u32,
+  // `current_value` minus `reference_orbit[reference_index]`. Only used by `perturbation.wgsl`.
+  delta : Complex,
+  reference_index : u32,
 }
 
 /*
@@ -52,78 +75,151 @@ We can run the iteration logic on a compute shader and store the resulting colou
 on the screen, and sample the results texture for its color.
 */
 
-@group(0) @binding(0) var<uniform> screen_size : vec2<u32>;
+// See `compute.rs#Uniforms`, which documents each field and why they're in this order.
+// View the set from `-(2 / zoom)` to `2 / zoom` along the screen's shorter side, centred on
+// `origin`, and iterate `f_c(z) = z^power + c` until `escape_radius` or `max_iterations`.
+struct Uniforms{
+  screen_size : vec2<u32>,
+  origin : vec2<f32>,
+  zoom : f32,
+  escape_radius : f32,
+  max_iterations : u32,
+  power : u32,
+}
+
+@group(0) @binding(0) var<uniform> uniforms : Uniforms;
+
+// Bindings 1 and 2 are `compute_f64.wgsl#zoom` and `compute_f64.wgsl#origin`.
 
-// View the set from `(-(2 / zoom), -(2 / zoom))` to `(2 / zoom, 2 / zoom)`
-@group(0) @binding(1) var<uniform> zoom : f32;
+// The constant `c` for `julia`.
+@group(0) @binding(3) var<uniform> julia_c : vec2<f32>;
 
-// Center the image on `origin`,
-@group(0) @binding(2) var<uniform> origin : vec2<f32>;
+// Where `pixel.x` and `pixel.y` start within the `uniforms.screen_size` view, when rendering it in
+// tiles.
+@group(0) @binding(4) var<uniform> tile_offset : vec2<u32>;
+
+// The orbit trap measured by `trap_distance`. See `compute.rs#TrapShape`.
+@group(0) @binding(5) var<uniform> trap_shape : u32;
+
+// The most times each pixel is iterated per dispatch. The rest pick up where they left off next frame.
+@group(0) @binding(6) var<uniform> iterations_per_frame : u32;
+
+// See `colour.rs#InteriorColouring`. Anything but flat needs every interior pixel's orbit.
+@group(0) @binding(7) var<uniform> interior_colouring : u32;
 
 @group(1) @binding(0) var<storage, read> input : array<Pixel>;
 @group(1) @binding(1) var<storage, read_write> output : array<Pixel>;
 
+// How many jittered samples `mandelbrot_multisampled` iterates per pixel. Replaced by
+// `compute.rs#Precision::shader_source`, like the workgroup size.
+let SAMPLES_PER_PIXEL = 1u;
+
 let MANDELBROT_DISPATCH_SIZE_Y = 1024u;
 let MANDELBROT_WORKGROUP_SIZE_Y = 64u;
 
-@compute @workgroup_size(1, 64, 1)
-fn mandelbrot(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
-  let index = global_invocation_id.x * (MANDELBROT_DISPATCH_SIZE_Y * MANDELBROT_WORKGROUP_SIZE_Y) + global_invocation_id.y;
-  
-  let pixel = input[index];
-  
-  let x = pixel.x;
-  output[index].x = x;
+// See `compute.rs#mandelbrot_dispatch_size`. Rows of `x` workgroups are stacked along `z` when
+// `x` alone would exceed the dispatch limit.
+fn pixel_index(global_invocation_id: vec3<u32>, num_workgroups: vec3<u32>) -> u32 {
+  let row = global_invocation_id.z * num_workgroups.x + global_invocation_id.x;
+  return row * (MANDELBROT_DISPATCH_SIZE_Y * MANDELBROT_WORKGROUP_SIZE_Y) + global_invocation_id.y;
+}
 
-  let y = pixel.y;
-  output[index].y = y;
-  
-  let zoom_inv = 2.0 / zoom;
+// The width and height of a pixel on the complex plane. See `pixel.rs#pixel_size`.
+fn pixel_size() -> f32 {
+  return 4.0 / (uniforms.zoom * f32(min(uniforms.screen_size.x, uniforms.screen_size.y)));
+}
+
+// The point on the complex plane that `pixel` covers.
+fn pixel_coordinate(pixel: Pixel) -> Complex {
+  let x = f32(pixel.x + tile_offset.x) - f32(uniforms.screen_size.x) / 2.0;
+  let y = f32(pixel.y + tile_offset.y) - f32(uniforms.screen_size.y) / 2.0;
+  return Complex(x * pixel_size() + uniforms.origin.x, y * pixel_size() + uniforms.origin.y);
+}
 
-  let c = Complex(
-    2.0 * zoom_inv * f32(x) / f32(screen_size.x) - zoom_inv + origin.x,
-    2.0 * zoom_inv * f32(y) / f32(screen_size.y) - zoom_inv + origin.y
-  );
+// How far `value` is from the orbit trap: the origin, the real axis, or both axes.
+fn trap_distance(value: Complex) -> f32 {
+  let z = vec2<f32>(value.real, value.imaginary);
+  var distance = length(z);
+  switch trap_shape {
+    case 1u: {
+      distance = abs(z.y);
+    }
+    case 2u: {
+      distance = min(abs(z.x), abs(z.y));
+    }
+    default: {}
+  }
+  return distance;
+}
+
+/*
+An estimate of the distance, in pixels, from the point that `z` was iterated from to the edge of the
+set ([Wikipedia](https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Distance_estimates)),
+given its derivative `dz`.
 
-  let starting_value : Complex = pixel.current_value;
+Zero when there's nothing to estimate from, e.g. for interior points whose `|z| < 1`.
+*/
+fn distance_estimate(z: Complex, dz: Complex) -> f32 {
+  let length_z = sqrt(squared_length_complex(z));
+  let length_dz = sqrt(squared_length_complex(dz));
+  let estimate = length_z * log(length_z) / (length_dz * pixel_size());
+  return select(0.0, max(estimate, 0.0), length_dz > 0.0);
+}
+
+/*
+Iterate `starting_value` once under `f_c(z) = z^power + c`, and return the resulting pixel.
+
+`dc` is the derivative of `c` with respect to the pixel's coordinate: `1.0` when `c` is the pixel's
+coordinate, and `0.0` when it's fixed.
+*/
+fn iterate(pixel: Pixel, starting_value: Complex, c: Complex, dc: f32) -> Pixel {
+  // Hold capped pixels as they are, so `iteration_count` never exceeds `max_iterations`.
+  if pixel.iteration_count >= uniforms.max_iterations {
+    return pixel;
+  }
+
+  var result = pixel;
+
+  // Compare squared lengths to avoid a `sqrt`.
+  let escape_threshold = uniforms.escape_radius * uniforms.escape_radius;
 
   // conditions:
   // 
-  // length_complex(starting_value) > ESCAPE_THRESHOLD implies xx < 0
-  // length_complex(starting_value) == ESCAPE_THRESHOLD implies xx == 0
-  // length_complex(starting_value) < ESCAPE_THRESHOLD implies xx > 0
+  // squared_length_complex(starting_value) > escape_threshold implies xx < 0
+  // squared_length_complex(starting_value) == escape_threshold implies xx == 0
+  // squared_length_complex(starting_value) < escape_threshold implies xx > 0
   // 
-  // length_complex(starting_value) >= ESCAPE_THRESHOLD implies xx <= 0
+  // squared_length_complex(starting_value) >= escape_threshold implies xx <= 0
 
-  let escape_threshold_minus_length = ESCAPE_THRESHOLD - length_complex(starting_value);
+  let escape_threshold_minus_squared_length = escape_threshold - squared_length_complex(starting_value);
 
-  // length_complex(starting_value) >= ESCAPE_THRESHOLD implies max(xx, 0.0) == 0
+  // squared_length_complex(starting_value) >= escape_threshold implies max(xx, 0.0) == 0
   //
-  // If `escape_threshold_minus_length` is negative, then `max` outputs `0.0`.
-  let escape_threshold_minus_length_max_0 = max(escape_threshold_minus_length, 0.0);
+  // If `escape_threshold_minus_squared_length` is negative, then `max` outputs `0.0`.
+  let escape_threshold_minus_squared_length_max_0 = max(escape_threshold_minus_squared_length, 0.0);
     
-  // escape_threshold_minus_length_max_0 == 0.0 should imply iteration_counts_out[index].escaped == 1u;
-  // escape_threshold_minus_length_max_0 > 0.0 should imply iteration_counts_out[index].escaped == 0u;
+  // escape_threshold_minus_squared_length_max_0 == 0.0 should imply iteration_counts_out[index].escaped == 1u;
+  // escape_threshold_minus_squared_length_max_0 > 0.0 should imply iteration_counts_out[index].escaped == 0u;
   //
-  // escape_threshold_minus_length_max_0 == 0.0 implies sign(escape_threshold_minus_length_max_0) == 0.0
-  // escape_threshold_minus_length_max_0 > 0.0 implies sign(escape_threshold_minus_length_max_0) == 1.0
+  // escape_threshold_minus_squared_length_max_0 == 0.0 implies sign(escape_threshold_minus_squared_length_max_0) == 0.0
+  // escape_threshold_minus_squared_length_max_0 > 0.0 implies sign(escape_threshold_minus_squared_length_max_0) == 1.0
   //
-  // escaped == u32(1.0 - sign(escape_threshold_minus_length_max_0))
+  // escaped == u32(1.0 - sign(escape_threshold_minus_squared_length_max_0))
   //
-  // escape_threshold_minus_length_max_0 == 0.0 implies
+  // escape_threshold_minus_squared_length_max_0 == 0.0 implies
   //   escaped == u32(1.0 - 0.0)
   //   escaped == u32(1.0)
   //   escaped == 1u
   //
-  // escape_threshold_minus_length_max_0 > 0.0 implies
+  // escape_threshold_minus_squared_length_max_0 > 0.0 implies
   //   escaped == u32(1.0 - 1.0)
   //   escaped == u32(0.0)
   //   escaped == 0u
-  output[index].escaped =
-    1u - u32(sign(escape_threshold_minus_length_max_0));
+  result.escaped =
+    1u - u32(sign(escape_threshold_minus_squared_length_max_0));
 
   let escaped_last_iteration : u32 = pixel.escaped;
-  output[index].iteration_count =
+  result.iteration_count =
     pixel.iteration_count +
     // Add nothing when the point has already escaped,
     // add 1 when the point hasn't escaped.
@@ -136,9 +232,9 @@ fn mandelbrot(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     (1u - escaped_last_iteration);
 
   let escaped_last_iteration : f32 = f32(escaped_last_iteration);
-  output[index].current_value =
+  result.current_value =
     // set to `starting_value` when the point has escaped.
-    // set to `add_complex(multiply_complex(starting_value, starting_value), c)`
+    // set to `add_complex(power_complex(starting_value, power), c)`
     add_complex(
       // escaped == 1u implies
       //   scale_complex(1.0, starting_value),
@@ -149,14 +245,265 @@ fn mandelbrot(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
       //   Complex(0.0, 0.0),
       scale_complex(escaped_last_iteration, starting_value),
       // escaped == 1u implies
-      //   scale_complex(1.0 - 1.0, add_complex(multiply_complex(starting_value, starting_value), c))
-      //   scale_complex(0.0, add_complex(multiply_complex(starting_value, starting_value), c))
+      //   scale_complex(1.0 - 1.0, add_complex(power_complex(starting_value, power), c))
+      //   scale_complex(0.0, add_complex(power_complex(starting_value, power), c))
       //   Complex(0.0, 0.0)
       //
       // escaped == 0u implies
-      //   scale_complex(1.0 - 0.0, add_complex(multiply_complex(starting_value, starting_value), c))
-      //   scale_complex(1.0, add_complex(multiply_complex(starting_value, starting_value), c))
-      //   add_complex(multiply_complex(starting_value, starting_value), c)
-      scale_complex(1.0 - escaped_last_iteration, add_complex(multiply_complex(starting_value, starting_value), c))
+      //   scale_complex(1.0 - 0.0, add_complex(power_complex(starting_value, power), c))
+      //   scale_complex(1.0, add_complex(power_complex(starting_value, power), c))
+      //   add_complex(power_complex(starting_value, power), c)
+      scale_complex(1.0 - escaped_last_iteration, add_complex(power_complex(starting_value, uniforms.power), c))
+    );
+
+  // `dz' = power * z^(power - 1) * dz + dc`, held once the pixel has escaped like `current_value`.
+  result.derivative =
+    add_complex(
+      scale_complex(escaped_last_iteration, pixel.derivative),
+      scale_complex(
+        1.0 - escaped_last_iteration,
+        add_complex(
+          scale_complex(
+            f32(uniforms.power),
+            multiply_complex(power_complex(starting_value, uniforms.power - 1u), pixel.derivative)
+          ),
+          Complex(dc, 0.0)
+        )
+      )
     );
+  result.distance_estimate = distance_estimate(starting_value, pixel.derivative);
+
+  // An escaped pixel's `current_value` stops changing, so its closest approach does too.
+  result.min_trap_distance =
+    min(pixel.min_trap_distance, trap_distance(result.current_value));
+
+  return result;
+}
+
+// Whether `pixel` should be iterated again in this dispatch, after `i` iterations.
+fn keep_iterating(pixel: Pixel, i: u32) -> bool {
+  return i < iterations_per_frame && pixel.escaped == 0u && pixel.iteration_count < uniforms.max_iterations;
+}
+
+// `Pixel.escaped` for points that are known to be inside the set, so never need iterating.
+let INTERIOR = 2u;
+
+/*
+Whether `c` is inside the main cardioid or the period-2 bulb of `z^2 + c`, where every orbit is bounded
+([Wikipedia](https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Cardioid_/_bulb_checking)).
+*/
+fn in_cardioid_or_bulb(c: Complex) -> bool {
+  let x = c.real - 0.25;
+  let y_squared = c.imaginary * c.imaginary;
+  let q = x * x + y_squared;
+  let in_cardioid = q * (q + x) <= 0.25 * y_squared;
+  let in_bulb = (c.real + 1.0) * (c.real + 1.0) + y_squared <= 0.0625;
+  return in_cardioid || in_bulb;
+}
+
+@compute @workgroup_size(1, 64, 1)
+fn mandelbrot(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let index = pixel_index(global_invocation_id, num_workgroups);
+  
+  var pixel = input[index];
+  let c = pixel_coordinate(pixel);
+
+  // These points would otherwise iterate until `max_iterations`. Only checked before the first
+  // iteration, since the answer never changes.
+  if uniforms.power == 2u && interior_colouring == 0u && pixel.iteration_count == 0u && in_cardioid_or_bulb(c) {
+    pixel.escaped = INTERIOR;
+    output[index] = pixel;
+    return;
+  }
+
+  for (var i = 0u; keep_iterating(pixel, i); i = i + 1u) {
+    pixel = iterate(pixel, pixel.current_value, c, 1.0);
+  }
+  output[index] = pixel;
+}
+
+/*
+Where within its pixel sample number `sample` goes, from `-0.5` to `0.5` along each axis. Follows
+the [R2 sequence](https://extremelearning.com.au/unreasonable-effectiveness-of-quasirandom-sequences/),
+which spreads any number of samples evenly, and starts at the centre.
+*/
+fn sample_offset(sample: u32) -> vec2<f32> {
+  return fract(vec2<f32>(0.5) + f32(sample) * vec2<f32>(0.7548777, 0.5698403)) - 0.5;
+}
+
+// `iteration_count` plus `colour.rs#smooth_fraction`, for an escaped pixel.
+fn smooth_count(pixel: Pixel) -> f32 {
+  let length = max(sqrt(squared_length_complex(pixel.current_value)), uniforms.escape_radius);
+  let nu = log2(log2(length) / log2(uniforms.escape_radius)) / log2(f32(uniforms.power));
+  return f32(pixel.iteration_count) + clamp(1.0 - nu, 0.0, 1.0);
+}
+
+// `pixel` as `pixel.rs#Pixel::new` made it, before any iterations.
+fn fresh_pixel(pixel: Pixel) -> Pixel {
+  return Pixel(pixel.x, pixel.y, 0u, ZERO_COMPLEX, 0u, 3.40282347e38, ZERO_COMPLEX, 0.0, 0u, ZERO_COMPLEX, 0u);
+}
+
+/*
+`mandelbrot`, with `SAMPLES_PER_PIXEL` points jittered across each pixel by `sample_offset`, so the
+boundary of the set is anti-aliased without storing more pixels.
+
+Every sample is iterated to the end in one go, since there's nowhere to keep the others' state
+between dispatches. The pixel keeps the centre sample's orbit, for the interior colourings and
+distance estimates, but escapes if most of its samples did, with their mean smooth count. That's
+stored back as an `iteration_count` and a `current_value` that `colour.rs#smooth_fraction` turns
+back into the same fraction.
+*/
+@compute @workgroup_size(1, 64, 1)
+fn mandelbrot_multisampled(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let index = pixel_index(global_invocation_id, num_workgroups);
+
+  var pixel = input[index];
+  if pixel.escaped != 0u || pixel.iteration_count >= uniforms.max_iterations {
+    output[index] = pixel;
+    return;
+  }
+
+  let centre = pixel_coordinate(pixel);
+  var escaped_samples = 0u;
+  var total_count = 0.0;
+  for (var sample = 0u; sample < SAMPLES_PER_PIXEL; sample = sample + 1u) {
+    let offset = sample_offset(sample) * pixel_size();
+    let c = Complex(centre.real + offset.x, centre.imaginary + offset.y);
+
+    var sample_pixel = fresh_pixel(pixel);
+    while sample_pixel.escaped == 0u && sample_pixel.iteration_count < uniforms.max_iterations {
+      sample_pixel = iterate(sample_pixel, sample_pixel.current_value, c, 1.0);
+    }
+    if sample_pixel.escaped != 0u {
+      escaped_samples = escaped_samples + 1u;
+      total_count = total_count + smooth_count(sample_pixel);
+    }
+    if sample == 0u {
+      pixel = sample_pixel;
+    }
+  }
+
+  if escaped_samples * 2u > SAMPLES_PER_PIXEL {
+    let mean = total_count / f32(escaped_samples);
+    pixel.escaped = 1u;
+    pixel.iteration_count = u32(floor(mean));
+    // `|z| = escape_radius^(power^(1 - fraction))` is where `smooth_fraction` gives `fraction`.
+    pixel.current_value =
+      Complex(pow(uniforms.escape_radius, pow(f32(uniforms.power), 1.0 - fract(mean))), 0.0);
+  } else {
+    if pixel.escaped != 0u {
+      pixel.current_value = ZERO_COMPLEX;
+    }
+    pixel.escaped = 0u;
+    pixel.iteration_count = uniforms.max_iterations;
+  }
+  output[index] = pixel;
+}
+
+/*
+The [Julia set](https://en.wikipedia.org/wiki/Julia_set) iterates the same function as `mandelbrot`,
+but `c` is fixed and each pixel's coordinate is the starting value.
+*/
+@compute @workgroup_size(1, 64, 1)
+fn julia(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let index = pixel_index(global_invocation_id, num_workgroups);
+  
+  var pixel = input[index];
+
+  for (var i = 0u; keep_iterating(pixel, i); i = i + 1u) {
+    // Pixels start at `ZERO_COMPLEX` with a zero derivative, which is only correct for `mandelbrot`.
+    var starting_value : Complex = pixel.current_value;
+    if pixel.iteration_count == 0u {
+      starting_value = pixel_coordinate(pixel);
+      pixel.derivative = Complex(1.0, 0.0);
+    }
+
+    pixel = iterate(pixel, starting_value, Complex(julia_c.x, julia_c.y), 0.0);
+  }
+  output[index] = pixel;
+}
+
+/*
+The point on the complex plane that `pixel` covers when the screen is split down the middle, so
+that each half is a view of its own, `f32(screen_size.x) / 2.0` pixels wide. The left half is
+centred on `origin` at `zoom`, and the right half on zero at a zoom of 1.
+*/
+fn split_coordinate(pixel: Pixel) -> Complex {
+  let half_width = f32(uniforms.screen_size.x) / 2.0;
+  let left = f32(pixel.x + tile_offset.x) < half_width;
+  let x = f32(pixel.x + tile_offset.x) - select(half_width * 1.5, half_width * 0.5, left);
+  let y = f32(pixel.y + tile_offset.y) - f32(uniforms.screen_size.y) / 2.0;
+  let size = 4.0 / (select(1.0, uniforms.zoom, left) * min(half_width, f32(uniforms.screen_size.y)));
+  let centre = select(vec2<f32>(0.0, 0.0), uniforms.origin, left);
+  return Complex(x * size + centre.x, y * size + centre.y);
+}
+
+/*
+`mandelbrot` on the left half of the screen and, on the right, the Julia set for `julia_c`, so the
+two can be compared side by side. See `split_coordinate`.
+*/
+@compute @workgroup_size(1, 64, 1)
+fn split(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let index = pixel_index(global_invocation_id, num_workgroups);
+
+  var pixel = input[index];
+  let coordinate = split_coordinate(pixel);
+
+  if f32(pixel.x + tile_offset.x) < f32(uniforms.screen_size.x) / 2.0 {
+    if uniforms.power == 2u && interior_colouring == 0u && pixel.iteration_count == 0u && in_cardioid_or_bulb(coordinate) {
+      pixel.escaped = INTERIOR;
+      output[index] = pixel;
+      return;
+    }
+
+    for (var i = 0u; keep_iterating(pixel, i); i = i + 1u) {
+      pixel = iterate(pixel, pixel.current_value, coordinate, 1.0);
+    }
+  } else {
+    for (var i = 0u; keep_iterating(pixel, i); i = i + 1u) {
+      // As in `julia`, the coordinate is the starting value.
+      var starting_value : Complex = pixel.current_value;
+      if pixel.iteration_count == 0u {
+        starting_value = coordinate;
+        pixel.derivative = Complex(1.0, 0.0);
+      }
+
+      pixel = iterate(pixel, starting_value, Complex(julia_c.x, julia_c.y), 0.0);
+    }
+  }
+  output[index] = pixel;
+}
+
+/*
+The [Burning Ship fractal](https://en.wikipedia.org/wiki/Burning_Ship_fractal) iterates
+`f_c(z) = (|Re(z)| + i|Im(z)|)^2 + c` from `z = 0`, i.e. `mandelbrot` with both components of `z`
+made positive before squaring.
+*/
+@compute @workgroup_size(1, 64, 1)
+fn burning_ship(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let index = pixel_index(global_invocation_id, num_workgroups);
+  
+  var pixel = input[index];
+  let c = pixel_coordinate(pixel);
+
+  for (var i = 0u; keep_iterating(pixel, i); i = i + 1u) {
+    // The derivative ignores the sign flips from `abs_complex`, so distance estimates are approximate.
+    pixel = iterate(pixel, abs_complex(pixel.current_value), c, 1.0);
+  }
+  output[index] = pixel;
 }
\ No newline at end of file
@@ -0,0 +1,93 @@
+/*!
+High precision reference orbits for [`crate::compute::Precision::Perturbed`].
+
+Past `zoom` ≈ `1e13`, not even `f64` can tell neighbouring pixels apart. Perturbation
+([Wikipedia](https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Perturbation_theory_and_series_approximation))
+iterates a single point, the reference, at as many bits as the zoom needs, here on the CPU. Each
+pixel then only iterates its small difference from the reference in `perturbation.wgsl`, which
+`f32` holds with full relative precision however small it gets.
+
+The reference is iterated in fixed point, as a [`BigInt`] scaled by `2^bits`.
+*/
+
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, ToPrimitive};
+
+use crate::pixel::{Complex, ComplexF64};
+
+/// Bits kept below the pixel size, so rounding in the reference stays out of sight.
+const GUARD_BITS: u32 = 64;
+
+/// Where a reference orbit stops if it never escapes. Larger than any sensible escape radius.
+const REFERENCE_ESCAPE_RADIUS: f64 = 1e3;
+
+/**
+The fractional bits that a view at `zoom` needs to iterate its reference orbit with.
+
+Pixels are about `4.0 / zoom` apart divided by the screen size, so each doubling of the zoom
+needs one more bit.
+*/
+pub fn precision_bits(zoom: f64) -> u32 {
+    GUARD_BITS + zoom.max(1.0).log2().ceil() as u32
+}
+
+/// `value * 2^bits`, truncated. `f64`'s exponent tops out at 1023, so `bits` can't be more than about 1000.
+fn to_fixed(value: f64, bits: u32) -> BigInt {
+    BigInt::from_f64(value * 2f64.powi(bits as i32)).unwrap_or_default()
+}
+
+/// The inverse of [`to_fixed`], rounded to the nearest `f64`.
+fn from_fixed(value: &BigInt, bits: u32) -> f64 {
+    // Only the top bits matter to an `f64`, so drop the rest before converting.
+    let shift = bits.saturating_sub(GUARD_BITS);
+    (value >> shift).to_f64().unwrap_or_default() / 2f64.powi((bits - shift) as i32)
+}
+
+/// The Mandelbrot set's orbit of one point, `Z_0 = 0`, `Z_(n+1) = Z_n^2 + c`.
+#[derive(Clone, Debug)]
+pub struct ReferenceOrbit {
+    /// The point whose orbit this is, `c`.
+    pub centre: ComplexF64,
+    /**
+    `Z_0` to `Z_n`, rounded to `f32`. Stops early once `Z_n` escapes, and always has at least two
+    values, so that `perturbation.wgsl#mandelbrot_perturbed` can rebase onto `Z_0`.
+    */
+    pub values: Vec<Complex>,
+}
+
+impl ReferenceOrbit {
+    /**
+    Iterate `centre` for up to `max_iterations`, at [`precision_bits`] for `zoom`.
+
+    The iterations themselves are exact to `2^-bits`. `centre` is only as precise as an `f64`, so
+    the view can't be moved in steps smaller than that, but each pixel's offset from it can.
+    */
+    pub fn new(centre: ComplexF64, zoom: f64, max_iterations: u32) -> Self {
+        let bits = precision_bits(zoom);
+        let c_real = to_fixed(centre.real, bits);
+        let c_imaginary = to_fixed(centre.imaginary, bits);
+        let escape_threshold = REFERENCE_ESCAPE_RADIUS * REFERENCE_ESCAPE_RADIUS;
+
+        let mut real = BigInt::default();
+        let mut imaginary = BigInt::default();
+        let mut values = vec![Complex::ZERO];
+        for _ in 0..max_iterations.max(1) {
+            // `(a + bi)^2 = a^2 - b^2 + 2abi`, shifting back down by `bits` after each product.
+            let next_real = ((&real * &real - &imaginary * &imaginary) >> bits) + &c_real;
+            let next_imaginary = ((&real * &imaginary) >> (bits - 1)) + &c_imaginary;
+            real = next_real;
+            imaginary = next_imaginary;
+
+            let value = ComplexF64 {
+                real: from_fixed(&real, bits),
+                imaginary: from_fixed(&imaginary, bits),
+            };
+            values.push(Complex::from(value));
+            if value.real * value.real + value.imaginary * value.imaginary > escape_threshold {
+                break;
+            }
+        }
+
+        ReferenceOrbit { centre, values }
+    }
+}
@@ -0,0 +1,162 @@
+/*!
+A CPU implementation of `compute.wgsl#mandelbrot`, for checking the shader's output.
+
+It follows the shader step by step at the same precision, so a pixel's `iteration_count` should
+only differ from the GPU's where `f32` rounding tips it over the escape radius one iteration
+earlier or later.
+*/
+
+use rayon::prelude::*;
+
+use crate::{
+    compute::{DEFAULT_ESCAPE_RADIUS, DEFAULT_POWER},
+    pixel::{Complex, IteratedPixel, Pixel, INTERIOR},
+    screen,
+};
+
+/// `compute.wgsl#pixel_coordinate`, with no tile offset.
+fn pixel_coordinate(x: u32, y: u32, size: screen::Size, origin: Complex, zoom: f32) -> Complex {
+    let pixel_size = 4.0 / (zoom * size.width.min(size.height) as f32);
+
+    Complex {
+        real: (x as f32 - size.width as f32 / 2.0) * pixel_size + origin.real,
+        imaginary: (y as f32 - size.height as f32 / 2.0) * pixel_size + origin.imaginary,
+    }
+}
+
+/// `compute.wgsl#in_cardioid_or_bulb`.
+fn in_cardioid_or_bulb(c: Complex) -> bool {
+    let x = c.real - 0.25;
+    let y_squared = c.imaginary * c.imaginary;
+    let q = x * x + y_squared;
+    q * (q + x) <= 0.25 * y_squared || (c.real + 1.0) * (c.real + 1.0) + y_squared <= 0.0625
+}
+
+/**
+Iterate every pixel of a `size` view of the Mandelbrot set until it escapes or reaches `max_iterations`.
+
+Uses [`DEFAULT_ESCAPE_RADIUS`] and [`DEFAULT_POWER`]. The pixels are in the same order as
+[`crate::renderer::Pixels::all`], and only `x`, `y`, `escaped`, `current_value` and
+`iteration_count` are computed.
+*/
+pub fn render(size: screen::Size, origin: Complex, zoom: f32, max_iterations: u32) -> Vec<Pixel> {
+    debug_assert!(DEFAULT_POWER == 2);
+    let escape_threshold = DEFAULT_ESCAPE_RADIUS * DEFAULT_ESCAPE_RADIUS;
+
+    (0..size.width * size.height)
+        .into_par_iter()
+        .map(|index| {
+            let mut pixel = Pixel::new(index % size.width, index / size.width);
+            let c = pixel_coordinate(pixel.x, pixel.y, size, origin, zoom);
+            if in_cardioid_or_bulb(c) {
+                pixel.escaped = INTERIOR;
+                return pixel;
+            }
+
+            // Like the shader, a pixel is iterated once more in the pass that finds it has escaped.
+            while pixel.escaped == 0 && pixel.iteration_count < max_iterations {
+                if pixel.current_value.norm_sqr() >= escape_threshold {
+                    pixel.escaped = 1;
+                }
+                pixel.iteration_count += 1;
+                pixel.current_value = pixel.current_value * pixel.current_value + c;
+            }
+
+            pixel
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixel::{ESCAPED, ITERATING};
+
+    const MAX_ITERATIONS: u32 = 1000;
+
+    /// The pixel at `real + imaginary i`, the centre of a 2x2 view.
+    fn pixel_at(real: f32, imaginary: f32) -> Pixel {
+        let size = screen::Size {
+            width: 2,
+            height: 2,
+        };
+        let origin = Complex { real, imaginary };
+        render(size, origin, 1.0, MAX_ITERATIONS)[3]
+    }
+
+    #[test]
+    fn known_escape_counts() {
+        // The main cardioid and the period 2 bulb are found without iterating.
+        assert_eq!(pixel_at(0.0, 0.0).escaped, INTERIOR);
+        assert_eq!(pixel_at(-1.0, 0.0).escaped, INTERIOR);
+
+        // Just past the cardioid's cusp, so the orbit lingers near 0.5 before escaping.
+        let pixel = pixel_at(0.26, 0.0);
+        assert_eq!((pixel.escaped, pixel.iteration_count), (ESCAPED, 31));
+
+        // Already past the escape radius after one iteration.
+        let pixel = pixel_at(2.0, 2.0);
+        assert_eq!((pixel.escaped, pixel.iteration_count), (ESCAPED, 2));
+
+        // Inside the period 3 bulb, which isn't checked for, so it iterates to the end.
+        let pixel = pixel_at(-0.122, 0.745);
+        assert_eq!(
+            (pixel.escaped, pixel.iteration_count),
+            (ITERATING, MAX_ITERATIONS)
+        );
+    }
+
+    /**
+    The shader agrees with this, apart from a few pixels where `f32` rounding on the GPU tips the
+    orbit over the escape radius an iteration earlier or later.
+    */
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn matches_the_gpu() {
+        use crate::{
+            colour::ColouringMode,
+            pixel::ComplexF64,
+            renderer::{self, PrecisionPixels, Renderer},
+        };
+
+        let adapter = match renderer::test_adapter() {
+            Some(adapter) => adapter,
+            None => return,
+        };
+        let size = screen::Size {
+            width: 64,
+            height: 48,
+        };
+        let origin = ComplexF64 {
+            real: -0.5,
+            imaginary: 0.0,
+        };
+        let max_iterations = 200;
+        let mut renderer =
+            Renderer::new(&adapter, renderer::HEADLESS_FORMAT, size, origin, 1.0).unwrap();
+        renderer.set_colouring_mode(ColouringMode::Histogram);
+        renderer.set_max_iterations(max_iterations);
+        renderer.render_to_vec(origin, 1.0);
+
+        let gpu = match &renderer.pixels {
+            PrecisionPixels::Single(pixels) => &pixels.all,
+            PrecisionPixels::Double(_) => unreachable!("renderers start at single precision"),
+        };
+        let cpu = render(renderer.screen_size, origin.into(), 1.0, max_iterations);
+        assert_eq!(gpu.len(), cpu.len());
+
+        let mismatches = gpu
+            .iter()
+            .zip(&cpu)
+            .filter(|(gpu, cpu)| {
+                gpu.escaped != cpu.escaped || gpu.iteration_count.abs_diff(cpu.iteration_count) > 1
+            })
+            .count();
+        assert!(
+            mismatches <= cpu.len() / 100,
+            "{} of {} pixels differ",
+            mismatches,
+            cpu.len()
+        );
+    }
+}
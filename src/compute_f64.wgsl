@@ -0,0 +1,244 @@
+/*
+A double-precision copy of `compute.wgsl`, for devices with `wgpu::Features::SHADER_FLOAT64`.
+
+The bindings and entry points are the same. See `compute.wgsl` for how `iterate` works.
+*/
+
+struct Complex{real: f64, imaginary: f64}
+
+fn scale_complex(first: f64, second: Complex) -> Complex {
+  return Complex(first * second.real, first * second.imaginary);
+}
+
+fn multiply_complex(first: Complex, second: Complex) -> Complex {
+  let a = first.real * second.real;
+  let b = first.real * second.imaginary + first.imaginary * second.real;
+  let c = first.imaginary * second.imaginary;
+
+  return Complex(a - c, b);
+}
+
+// `value^power`, for `power >= 1`.
+fn power_complex(value: Complex, power: u32) -> Complex {
+  var result = value;
+  for (var i = 1u; i < power; i = i + 1u) {
+    result = multiply_complex(result, value);
+  }
+  return result;
+}
+
+fn add_complex(first: Complex, second: Complex) -> Complex {
+  return Complex(first.real + second.real, first.imaginary + second.imaginary);
+}
+
+fn abs_complex(value: Complex) -> Complex {
+  return Complex(abs(value.real), abs(value.imaginary));
+}
+
+fn squared_length_complex(value: Complex) -> f64 {
+  return value.real * value.real + value.imaginary * value.imaginary;
+}
+
+// `current_value` comes first so that the struct has no padding. See `pixel.rs#PixelF64`.
+struct Pixel{
+  current_value : Complex,
+  derivative : Complex,
+  x : u32,
+  y : u32,
+  escaped : u32,
+  iteration_count : u32,
+  min_trap_distance : f32,
+  distance_estimate : f32,
+}
+
+// The same as `compute.wgsl#Uniforms`. `zoom` and `origin` are read from their own `f64` bindings
+// instead of the `f32` ones here.
+struct Uniforms{
+  screen_size : vec2<u32>,
+  origin_f32 : vec2<f32>,
+  zoom_f32 : f32,
+  escape_radius : f32,
+  max_iterations : u32,
+  power : u32,
+}
+
+@group(0) @binding(0) var<uniform> uniforms : Uniforms;
+
+@group(0) @binding(1) var<uniform> zoom : f64;
+
+@group(0) @binding(2) var<uniform> origin : vec2<f64>;
+
+@group(0) @binding(3) var<uniform> julia_c : vec2<f32>;
+
+@group(0) @binding(4) var<uniform> tile_offset : vec2<u32>;
+
+@group(0) @binding(5) var<uniform> trap_shape : u32;
+
+@group(0) @binding(6) var<uniform> iterations_per_frame : u32;
+
+@group(0) @binding(7) var<uniform> interior_colouring : u32;
+
+@group(1) @binding(0) var<storage, read> input : array<Pixel>;
+@group(1) @binding(1) var<storage, read_write> output : array<Pixel>;
+
+let MANDELBROT_DISPATCH_SIZE_Y = 1024u;
+let MANDELBROT_WORKGROUP_SIZE_Y = 64u;
+
+fn pixel_index(global_invocation_id: vec3<u32>, num_workgroups: vec3<u32>) -> u32 {
+  let row = global_invocation_id.z * num_workgroups.x + global_invocation_id.x;
+  return row * (MANDELBROT_DISPATCH_SIZE_Y * MANDELBROT_WORKGROUP_SIZE_Y) + global_invocation_id.y;
+}
+
+fn pixel_size() -> f64 {
+  return f64(4.0) / (zoom * f64(min(uniforms.screen_size.x, uniforms.screen_size.y)));
+}
+
+fn pixel_coordinate(pixel: Pixel) -> Complex {
+  let x = f64(pixel.x + tile_offset.x) - f64(uniforms.screen_size.x) / f64(2.0);
+  let y = f64(pixel.y + tile_offset.y) - f64(uniforms.screen_size.y) / f64(2.0);
+  return Complex(x * pixel_size() + origin.x, y * pixel_size() + origin.y);
+}
+
+// The orbit trap only needs single precision.
+fn trap_distance(value: Complex) -> f32 {
+  let z = vec2<f32>(f32(value.real), f32(value.imaginary));
+  var distance = length(z);
+  switch trap_shape {
+    case 1u: {
+      distance = abs(z.y);
+    }
+    case 2u: {
+      distance = min(abs(z.x), abs(z.y));
+    }
+    default: {}
+  }
+  return distance;
+}
+
+// `log` only takes single precision, but the derivative can outgrow it.
+fn distance_estimate(z: Complex, dz: Complex) -> f32 {
+  let length_z = f32(sqrt(squared_length_complex(z)));
+  let length_dz = sqrt(squared_length_complex(dz));
+  let estimate = f64(length_z * log(length_z)) / (length_dz * pixel_size());
+  return select(0.0, max(f32(estimate), 0.0), length_dz > f64(0.0));
+}
+
+fn iterate(pixel: Pixel, starting_value: Complex, c: Complex, dc: f64) -> Pixel {
+  if pixel.iteration_count >= uniforms.max_iterations {
+    return pixel;
+  }
+
+  var result = pixel;
+
+  let escape_threshold = f64(uniforms.escape_radius) * f64(uniforms.escape_radius);
+
+  let escape_threshold_minus_squared_length_max_0 =
+    max(escape_threshold - squared_length_complex(starting_value), f64(0.0));
+  result.escaped =
+    1u - u32(sign(escape_threshold_minus_squared_length_max_0));
+
+  let escaped_last_iteration : u32 = pixel.escaped;
+  result.iteration_count = pixel.iteration_count + (1u - escaped_last_iteration);
+
+  let escaped_last_iteration : f64 = f64(escaped_last_iteration);
+  result.current_value =
+    add_complex(
+      scale_complex(escaped_last_iteration, starting_value),
+      scale_complex(f64(1.0) - escaped_last_iteration, add_complex(power_complex(starting_value, uniforms.power), c))
+    );
+
+  result.derivative =
+    add_complex(
+      scale_complex(escaped_last_iteration, pixel.derivative),
+      scale_complex(
+        f64(1.0) - escaped_last_iteration,
+        add_complex(
+          scale_complex(
+            f64(uniforms.power),
+            multiply_complex(power_complex(starting_value, uniforms.power - 1u), pixel.derivative)
+          ),
+          Complex(dc, f64(0.0))
+        )
+      )
+    );
+  result.distance_estimate = distance_estimate(starting_value, pixel.derivative);
+
+  result.min_trap_distance =
+    min(pixel.min_trap_distance, trap_distance(result.current_value));
+
+  return result;
+}
+
+fn keep_iterating(pixel: Pixel, i: u32) -> bool {
+  return i < iterations_per_frame && pixel.escaped == 0u && pixel.iteration_count < uniforms.max_iterations;
+}
+
+let INTERIOR = 2u;
+
+fn in_cardioid_or_bulb(c: Complex) -> bool {
+  let x = c.real - f64(0.25);
+  let y_squared = c.imaginary * c.imaginary;
+  let q = x * x + y_squared;
+  let in_cardioid = q * (q + x) <= f64(0.25) * y_squared;
+  let in_bulb = (c.real + f64(1.0)) * (c.real + f64(1.0)) + y_squared <= f64(0.0625);
+  return in_cardioid || in_bulb;
+}
+
+@compute @workgroup_size(1, 64, 1)
+fn mandelbrot(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let index = pixel_index(global_invocation_id, num_workgroups);
+  
+  var pixel = input[index];
+  let c = pixel_coordinate(pixel);
+
+  if uniforms.power == 2u && interior_colouring == 0u && pixel.iteration_count == 0u && in_cardioid_or_bulb(c) {
+    pixel.escaped = INTERIOR;
+    output[index] = pixel;
+    return;
+  }
+
+  for (var i = 0u; keep_iterating(pixel, i); i = i + 1u) {
+    pixel = iterate(pixel, pixel.current_value, c, f64(1.0));
+  }
+  output[index] = pixel;
+}
+
+@compute @workgroup_size(1, 64, 1)
+fn julia(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let index = pixel_index(global_invocation_id, num_workgroups);
+  
+  var pixel = input[index];
+
+  for (var i = 0u; keep_iterating(pixel, i); i = i + 1u) {
+    var starting_value : Complex = pixel.current_value;
+    if pixel.iteration_count == 0u {
+      starting_value = pixel_coordinate(pixel);
+      pixel.derivative = Complex(f64(1.0), f64(0.0));
+    }
+
+    pixel = iterate(pixel, starting_value, Complex(f64(julia_c.x), f64(julia_c.y)), f64(0.0));
+  }
+  output[index] = pixel;
+}
+
+@compute @workgroup_size(1, 64, 1)
+fn burning_ship(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let index = pixel_index(global_invocation_id, num_workgroups);
+  
+  var pixel = input[index];
+  let c = pixel_coordinate(pixel);
+
+  for (var i = 0u; keep_iterating(pixel, i); i = i + 1u) {
+    pixel = iterate(pixel, abs_complex(pixel.current_value), c, f64(1.0));
+  }
+  output[index] = pixel;
+}
\ No newline at end of file
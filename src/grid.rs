@@ -0,0 +1,56 @@
+//! The scale bar and gridlines drawn over the fractal by `render.wgsl#overlay_grid`.
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    pixel::{self, ComplexF64},
+    screen::{self, ViewRect},
+};
+
+/// Gridlines are never closer together than this, in pixels.
+pub const MIN_SPACING_PIXELS: f64 = 64.0;
+
+/**
+The distance between gridlines on the complex plane: the smallest power of ten that's at least
+[`MIN_SPACING_PIXELS`] wide at `zoom`. It's also the length of the scale bar.
+*/
+pub fn spacing(size: screen::Size, zoom: f64) -> f64 {
+    let min_spacing = MIN_SPACING_PIXELS * pixel::pixel_size(size, zoom);
+    10f64.powf(min_spacing.log10().ceil())
+}
+
+/**
+[`bytemuck`]-compatible layout of `render.wgsl#Grid`.
+
+Everything is in screen pixels, worked out here in `f64`, so the gridlines stay put at zooms
+where the shader's `f32` couldn't place them on the complex plane.
+*/
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, Default, PartialEq)]
+pub struct GridUniform {
+    /// Where a vertical and a horizontal gridline cross, from the top left of the screen.
+    pub offset: [f32; 2],
+    /// The distance between gridlines.
+    pub spacing: f32,
+    /// `1` to draw the grid and the scale bar, `0` to leave them out.
+    pub enabled: u32,
+}
+
+impl GridUniform {
+    /// The grid for a `size` view centred on `origin` at `zoom`.
+    pub fn new(origin: ComplexF64, zoom: f64, size: screen::Size, enabled: bool) -> Self {
+        let spacing = spacing(size, zoom);
+        let pixel_size = pixel::pixel_size(size, zoom);
+        let rect = ViewRect::new(origin, zoom, size);
+        // How far past the last gridline the top left corner is, so the next one is `spacing` minus that.
+        let offset = |min: f64| (spacing - min.rem_euclid(spacing)) / pixel_size;
+        GridUniform {
+            offset: [
+                offset(rect.min.real) as f32,
+                offset(rect.min.imaginary) as f32,
+            ],
+            spacing: (spacing / pixel_size) as f32,
+            enabled: enabled as u32,
+        }
+    }
+}
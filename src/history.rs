@@ -0,0 +1,79 @@
+//! Undo and redo for navigation.
+
+use std::collections::VecDeque;
+
+use crate::pixel::ComplexF64;
+
+/// How many views a [`History`] keeps by default before dropping the oldest.
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/**
+The views that have been navigated to, as `(origin, zoom)`, for undo and redo.
+
+[`History::push`] records each view once it has settled. [`History::undo`] and [`History::redo`]
+step through them without recording anything, and pushing after an undo drops the views that
+could have been redone.
+*/
+pub struct History {
+    views: VecDeque<(ComplexF64, f64)>,
+    /// The index of the view being shown in `views`.
+    current: usize,
+    capacity: usize,
+}
+
+impl History {
+    /// A history that starts at `view`.
+    pub fn new(view: (ComplexF64, f64)) -> Self {
+        Self {
+            views: VecDeque::from([view]),
+            current: 0,
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+
+    /// Keep at most `capacity` views, and at least one. Defaults to [`DEFAULT_CAPACITY`].
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        while self.views.len() > self.capacity {
+            self.views.pop_front();
+        }
+        self.current = self.current.min(self.views.len() - 1);
+        self
+    }
+
+    /// The view being shown.
+    pub fn current(&self) -> (ComplexF64, f64) {
+        self.views[self.current]
+    }
+
+    /// Record that `view` is now being shown. Nothing changes if it already was.
+    pub fn push(&mut self, view: (ComplexF64, f64)) {
+        if view == self.current() {
+            return;
+        }
+        self.views.truncate(self.current + 1);
+        self.views.push_back(view);
+        if self.views.len() > self.capacity {
+            self.views.pop_front();
+        }
+        self.current = self.views.len() - 1;
+    }
+
+    /// Step back to the previous view, if there is one.
+    pub fn undo(&mut self) -> Option<(ComplexF64, f64)> {
+        if self.current == 0 {
+            return None;
+        }
+        self.current -= 1;
+        Some(self.current())
+    }
+
+    /// Step forward to the view that was last undone, if there is one.
+    pub fn redo(&mut self) -> Option<(ComplexF64, f64)> {
+        if self.current + 1 >= self.views.len() {
+            return None;
+        }
+        self.current += 1;
+        Some(self.current())
+    }
+}
@@ -0,0 +1,156 @@
+//! An [`egui`] overlay showing where the view is and how far along it is.
+
+use std::time::Duration;
+
+use log::trace;
+
+use crate::{
+    command_buffer,
+    command_encoder::CommandEncoderExt,
+    pixel::{self, ComplexF64},
+};
+
+/**
+How many significant digits the origin is shown with at `zoom`: enough to tell pixels apart,
+which takes one more for every tenfold zoom, up to what an `f64` holds.
+*/
+fn origin_digits(zoom: f64) -> usize {
+    (zoom.log10().max(0.0).ceil() as usize + 6).min(17)
+}
+
+/// What the HUD displays for a frame.
+#[derive(Clone, Copy, Debug)]
+pub struct Stats {
+    pub origin: ComplexF64,
+    pub zoom: f64,
+    pub frame_time: Duration,
+    /// The number of pixels that will be iterated next frame.
+    pub unescaped: usize,
+    /// See [`crate::renderer::Renderer::convergence`].
+    pub convergence: f32,
+    /// The length of the scale bar, if it's shown. See [`crate::grid::spacing`].
+    pub grid_spacing: Option<f64>,
+}
+
+/// The egui context, its winit integration, and its wgpu renderer.
+pub struct Hud {
+    context: egui::Context,
+    state: egui_winit::State,
+    renderer: egui_wgpu::Renderer,
+}
+
+impl Hud {
+    /// Create a HUD that draws to textures with `format`.
+    pub fn new<T>(
+        event_loop: &winit::event_loop::EventLoopWindowTarget<T>,
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        Self {
+            context: egui::Context::default(),
+            state: egui_winit::State::new(event_loop),
+            renderer: egui_wgpu::Renderer::new(device, format, None, 1),
+        }
+    }
+
+    /// Pass a window event to egui. Returns `true` if egui used it, and it shouldn't control the view.
+    pub fn on_event(&mut self, event: &winit::event::WindowEvent) -> bool {
+        self.state.on_event(&self.context, event).consumed
+    }
+
+    /// Draw `stats` over the contents of `view`.
+    pub fn render(
+        &mut self,
+        window: &winit::window::Window,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view: &wgpu::TextureView,
+        stats: Stats,
+    ) {
+        let raw_input = self.state.take_egui_input(window);
+        let full_output = self.context.run(raw_input, |context| {
+            egui::Window::new("hud")
+                .title_bar(false)
+                .resizable(false)
+                .anchor(egui::Align2::LEFT_TOP, egui::vec2(8.0, 8.0))
+                .show(context, |ui| {
+                    ui.monospace(format!(
+                        "origin: {}",
+                        pixel::format_coord(stats.origin, origin_digits(stats.zoom))
+                    ));
+                    ui.monospace(format!("zoom: {:e}", stats.zoom));
+                    ui.monospace(format!(
+                        "frame time: {:.2} ms",
+                        stats.frame_time.as_secs_f64() * 1000.0
+                    ));
+                    ui.monospace(format!("unescaped: {}", stats.unescaped));
+                    ui.add(egui::ProgressBar::new(stats.convergence).show_percentage());
+                    if let Some(grid_spacing) = stats.grid_spacing {
+                        ui.monospace(format!("scale bar: {:e}", grid_spacing));
+                    }
+                });
+        });
+        self.state
+            .handle_platform_output(window, &self.context, full_output.platform_output);
+
+        let paint_jobs = self.context.tessellate(full_output.shapes);
+        let size = window.inner_size();
+        let screen_descriptor = egui_wgpu::renderer::ScreenDescriptor {
+            size_in_pixels: [size.width, size.height],
+            pixels_per_point: self.state.pixels_per_point(),
+        };
+
+        for (id, image_delta) in &full_output.textures_delta.set {
+            self.renderer
+                .update_texture(device, queue, *id, image_delta);
+        }
+
+        let mut update_command_buffers = Vec::new();
+        let render_command_buffer = command_buffer::create(
+            device,
+            &wgpu::CommandEncoderDescriptor::default(),
+            |command_encoder| {
+                update_command_buffers = self.renderer.update_buffers(
+                    device,
+                    queue,
+                    command_encoder,
+                    &paint_jobs,
+                    &screen_descriptor,
+                );
+
+                command_encoder.push_debug_group("hud-render-pass");
+                command_encoder.with_render_pass(
+                    &wgpu::RenderPassDescriptor {
+                        label: Some("hud-render-pass"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                // Draw over the fractal.
+                                load: wgpu::LoadOp::Load,
+                                store: true,
+                            },
+                        })],
+                        depth_stencil_attachment: None,
+                    },
+                    |render_pass| {
+                        self.renderer
+                            .render(render_pass, &paint_jobs, &screen_descriptor);
+                    },
+                );
+                command_encoder.pop_debug_group();
+            },
+        );
+
+        trace!("submitting hud render commands");
+        queue.submit(
+            update_command_buffers
+                .into_iter()
+                .chain(std::iter::once(render_command_buffer)),
+        );
+
+        for id in &full_output.textures_delta.free {
+            self.renderer.free_texture(id);
+        }
+    }
+}
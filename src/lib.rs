@@ -1,7 +1,20 @@
+pub mod bookmark;
 pub mod colour;
 pub mod command_buffer;
 pub mod command_encoder;
 pub mod compute;
+pub mod grid;
+pub mod history;
+#[cfg(feature = "hud")]
+pub mod hud;
+pub mod location;
 pub mod pixel;
+pub mod renderer;
 pub mod screen;
+pub mod screenshot;
+pub mod settings;
+pub mod shader;
 pub mod typed_buffer;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod video;
+pub mod viewer;
@@ -0,0 +1,209 @@
+//! Views of the complex plane that can be written down and returned to.
+
+use std::{fmt, num::ParseFloatError, str::FromStr};
+
+use crate::pixel::ComplexF64;
+
+/// The centre and zoom of a view, as written by the user (`re,im,zoom`).
+#[derive(Clone, Copy, Debug)]
+pub struct Location {
+    pub origin: ComplexF64,
+    pub zoom: f64,
+}
+
+/// Why a string couldn't be parsed as a [`Location`].
+#[derive(Debug)]
+pub enum ParseLocationError {
+    /// There weren't the expected number of comma-separated fields.
+    FieldCount {
+        expected: usize,
+        found: usize,
+    },
+    Float(ParseFloatError),
+    /// The zoom wasn't a finite number greater than zero.
+    Zoom(f64),
+    /// There's no [`SeedLocation`] with this name.
+    UnknownSeed(String),
+}
+
+impl fmt::Display for ParseLocationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseLocationError::FieldCount { expected, found } => write!(
+                f,
+                "expected {} comma-separated fields, found {}",
+                expected, found
+            ),
+            ParseLocationError::Float(err) => write!(f, "{}", err),
+            ParseLocationError::Zoom(zoom) => {
+                write!(f, "zoom must be greater than zero, found {}", zoom)
+            }
+            ParseLocationError::UnknownSeed(name) => {
+                write!(f, "no seed location named {:?}, expected one of ", name)?;
+                for (index, seed) in SEED_LOCATIONS.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", seed.name)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseLocationError {}
+
+impl From<ParseFloatError> for ParseLocationError {
+    fn from(err: ParseFloatError) -> Self {
+        ParseLocationError::Float(err)
+    }
+}
+
+/// Parse a zoom, which has to be a finite number greater than zero.
+pub fn parse_zoom(s: &str) -> Result<f64, ParseLocationError> {
+    let zoom: f64 = s.trim().parse()?;
+    if zoom.is_finite() && zoom > 0.0 {
+        Ok(zoom)
+    } else {
+        Err(ParseLocationError::Zoom(zoom))
+    }
+}
+
+/**
+Parse a point written as `re,im`, ignoring whitespace around each field, or as `re + imi` or
+`re - imi`, the way [`ComplexF64`]'s `Display` and [`crate::pixel::format_coord`] print it.
+*/
+pub fn parse_point(s: &str) -> Result<ComplexF64, ParseLocationError> {
+    if let Some(point) = parse_algebraic(s) {
+        return point;
+    }
+    let fields: Vec<&str> = s.split(',').map(str::trim).collect();
+    match fields[..] {
+        [real, imaginary] => Ok(ComplexF64 {
+            real: real.parse()?,
+            imaginary: imaginary.parse()?,
+        }),
+        _ => Err(ParseLocationError::FieldCount {
+            expected: 2,
+            found: fields.len(),
+        }),
+    }
+}
+
+/**
+Parse `re + imi` or `re - imi`, or `None` if `s` isn't written that way. The sign is the last `+`
+or `-` that isn't at the start or part of an exponent.
+*/
+fn parse_algebraic(s: &str) -> Option<Result<ComplexF64, ParseLocationError>> {
+    let s = s.trim().strip_suffix('i')?;
+    let (split, sign) = s.char_indices().rev().find(|&(index, c)| {
+        (c == '+' || c == '-')
+            && index > 0
+            && !s[..index].trim_end().ends_with(|c| c == 'e' || c == 'E')
+    })?;
+    let parse = || -> Result<ComplexF64, ParseLocationError> {
+        let imaginary: f64 = s[split + 1..].trim().parse()?;
+        Ok(ComplexF64 {
+            real: s[..split].trim().parse()?,
+            imaginary: if sign == '-' { -imaginary } else { imaginary },
+        })
+    };
+    Some(parse())
+}
+
+impl FromStr for Location {
+    type Err = ParseLocationError;
+
+    /// Parse `re,im,zoom`, ignoring whitespace around each field.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split(',').map(str::trim).collect();
+        let (real, imaginary, zoom) = match fields[..] {
+            [real, imaginary, zoom] => (real, imaginary, zoom),
+            _ => {
+                return Err(ParseLocationError::FieldCount {
+                    expected: 3,
+                    found: fields.len(),
+                })
+            }
+        };
+
+        let zoom = parse_zoom(zoom)?;
+
+        Ok(Location {
+            origin: ComplexF64 {
+                real: real.parse()?,
+                imaginary: imaginary.parse()?,
+            },
+            zoom,
+        })
+    }
+}
+
+/// A well-known view of the Mandelbrot set, for demos and testing. See [`SEED_LOCATIONS`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SeedLocation {
+    /// What `--seed-location` calls it.
+    pub name: &'static str,
+    pub origin: ComplexF64,
+    pub zoom: f64,
+    /// Enough iterations to bring out the detail at `zoom`.
+    pub max_iterations: u32,
+}
+
+/**
+The views that `N` and `P` cycle through, in order.
+
+These are [`SeedLocation`]s rather than `(Vec2, f32, u32)` tuples: an `f32` origin runs out of
+precision well before the deeper views, and [`ComplexF64`] and an `f64` zoom are what
+[`crate::renderer::Renderer::set_origin`] and [`crate::renderer::Renderer::set_zoom`] take.
+*/
+pub const SEED_LOCATIONS: &[SeedLocation] = &[
+    SeedLocation {
+        name: "seahorse-valley",
+        origin: ComplexF64 {
+            real: -0.743643887037151,
+            imaginary: 0.131825904205330,
+        },
+        zoom: 2000.0,
+        max_iterations: 1500,
+    },
+    SeedLocation {
+        name: "elephant-valley",
+        origin: ComplexF64 {
+            real: 0.285,
+            imaginary: 0.01,
+        },
+        zoom: 100.0,
+        max_iterations: 1000,
+    },
+    SeedLocation {
+        name: "triple-spiral",
+        origin: ComplexF64 {
+            real: -0.088,
+            imaginary: 0.654,
+        },
+        zoom: 200.0,
+        max_iterations: 1000,
+    },
+    // The period 3 copy of the set on the real axis.
+    SeedLocation {
+        name: "mini-mandelbrot",
+        origin: ComplexF64 {
+            real: -1.7548776662,
+            imaginary: 0.0,
+        },
+        zoom: 80.0,
+        max_iterations: 500,
+    },
+];
+
+/// Look up one of the [`SEED_LOCATIONS`] by name, ignoring case and surrounding whitespace.
+pub fn parse_seed_location(s: &str) -> Result<SeedLocation, ParseLocationError> {
+    let name = s.trim();
+    SEED_LOCATIONS
+        .iter()
+        .find(|seed| seed.name.eq_ignore_ascii_case(name))
+        .copied()
+        .ok_or_else(|| ParseLocationError::UnknownSeed(name.to_string()))
+}
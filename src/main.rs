@@ -1,736 +1,1015 @@
-use std::sync::{Arc, Condvar, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{cell::RefCell, rc::Rc};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use bytemuck::{Pod, Zeroable};
-use log::{debug, trace};
+use clap::{Parser, ValueEnum};
+use instant::Instant;
+use log::{debug, error, info, trace, warn};
+#[cfg(not(target_arch = "wasm32"))]
 use rayon::ThreadPoolBuilder;
 use winit::{
     event::{Event, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
-    window::WindowBuilder,
+    window::{Fullscreen, WindowBuilder},
 };
 
 use wgpu_mandelbrot::{
-    colour::{ColourRange, HistogramColouring},
-    command_buffer,
-    command_encoder::CommandEncoderExt,
+    colour::{self, PaletteName},
     compute,
-    pixel::{Complex, Pixel},
-    screen, typed_buffer,
+    location::{self, Location, SeedLocation},
+    pixel::ComplexF64,
+    renderer::Renderer,
+    screen,
+    settings::{self, Settings},
+    viewer::{Viewer, TITLE},
+};
+#[cfg(feature = "hud")]
+use wgpu_mandelbrot::{grid, hud};
+#[cfg(not(target_arch = "wasm32"))]
+use wgpu_mandelbrot::{
+    renderer::{self, FrameStats},
+    screenshot, shader, video,
 };
 
-fn create_pixels(size: screen::Size) -> Vec<Pixel> {
-    (0..size.height)
-        .flat_map(move |y| {
-            (0..size.width).map(move |x| Pixel {
-                x: x as u32,
-                y: y as u32,
-                current_value: Complex::ZERO,
-                escaped: 0,
-                iteration_count: 0,
-            })
+/// A realtime Mandelbrot renderer.
+#[derive(Parser)]
+struct Args {
+    /// The graphics API to render with. Defaults to any available one.
+    #[arg(long, value_enum)]
+    backend: Option<Backend>,
+
+    /// Which GPU to prefer when there's more than one.
+    #[arg(long, value_enum)]
+    power: Option<Power>,
+
+    /// How frames are presented. `fifo` waits for vsync, `mailbox` replaces frames that haven't
+    /// been shown yet, and `immediate` doesn't wait at all.
+    #[arg(long, value_enum, default_value_t = PresentMode::Fifo)]
+    present_mode: PresentMode,
+
+    /// How quickly zooming eases in, per second. Higher is snappier.
+    #[arg(long, default_value_t = 10.0)]
+    animation_speed: f64,
+
+    /// How much each notch of the scroll wheel zooms, e.g. 0.1 for 10%. Trackpads scroll the same
+    /// amount per 100 pixels.
+    #[arg(long, default_value_t = 0.1)]
+    zoom_sensitivity: f64,
+
+    /// Zoom out when scrolling up, and in when scrolling down.
+    #[arg(long)]
+    invert_zoom: bool,
+
+    /// Start with the settings in a TOML file. Flags given alongside it override its values.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// The colours to start with. `C` cycles through them. Defaults to green.
+    #[arg(long, value_enum)]
+    palette: Option<PaletteName>,
+
+    /// Start with a palette saved with `E`, instead of `--palette`.
+    #[arg(long, value_name = "FILE")]
+    palette_file: Option<PathBuf>,
+
+    /// How many times a second `A` cycles the palette.
+    #[arg(long, default_value_t = 0.05)]
+    cycle_speed: f32,
+
+    /// The point to start centred on, as `re,im` or `re + imi`. Defaults to `-0.74529,0.113075`.
+    #[arg(long, value_parser = location::parse_point)]
+    origin: Option<ComplexF64>,
+
+    /// A point to slowly drift the view toward, as `re,im` or `re + imi`, for a fly-through.
+    #[arg(long, value_name = "RE,IM", value_parser = location::parse_point)]
+    drift_to: Option<ComplexF64>,
+
+    /// How quickly `--drift-to` moves toward its point, per second. Higher is faster.
+    #[arg(long, default_value_t = 0.5)]
+    drift_speed: f64,
+
+    /// The zoom to start at. `1`, the default, fits `-2` to `2` across the window's shorter side.
+    #[arg(long, value_parser = location::parse_zoom)]
+    zoom: Option<f64>,
+
+    /// Start at one of the well-known views that `N` and `P` cycle through, with its zoom and
+    /// iteration count: `seahorse-valley`, `elephant-valley`, `triple-spiral` or `mini-mandelbrot`.
+    /// `--origin`, `--zoom` and `--max-iterations` override its values.
+    #[arg(long, value_name = "NAME", value_parser = location::parse_seed_location)]
+    seed_location: Option<SeedLocation>,
+
+    /// Iterate each pixel up to N times before colouring it as interior.
+    #[arg(long, value_name = "N")]
+    max_iterations: Option<u32>,
+
+    /// Raise the maximum iteration count by K each time the zoom doubles, starting from
+    /// `--max-iterations` at zoom 1.
+    #[arg(long, value_name = "K")]
+    auto_iterations: Option<f64>,
+
+    /// How far from the origin an orbit has to get to escape.
+    #[arg(long, value_name = "R")]
+    escape_radius: Option<f32>,
+
+    /// The window's initial size in physical pixels, as `WxH`.
+    #[arg(long, value_name = "WxH")]
+    size: Option<screen::Size>,
+
+    /// Start in borderless fullscreen. `F11` toggles it.
+    #[arg(long)]
+    fullscreen: bool,
+
+    /// Iterate N×N pixels for every one displayed, and average them. Uses N² times the memory.
+    #[arg(long, value_name = "N")]
+    ssaa: Option<u32>,
+
+    /// Draw with N samples per pixel: 1 (off) or 4. `--ssaa` is what anti-aliases the fractal.
+    #[arg(long, default_value_t = 1, value_name = "N")]
+    msaa: u32,
+
+    /// Render at most N frames per second, to keep the GPU from running flat out. 0 is uncapped.
+    #[arg(long, default_value_t = 0, value_name = "N")]
+    max_fps: u32,
+
+    /// Iterate each pixel up to N times per frame. Higher finishes sooner but makes frames slower.
+    #[arg(long, default_value_t = compute::DEFAULT_ITERATIONS_PER_FRAME, value_name = "N")]
+    iterations_per_frame: u32,
+
+    /// Iterate every pixel from scratch each frame instead of carrying on from the last one. For
+    /// checking the incremental path against, and sometimes faster for shallow views.
+    #[arg(long)]
+    no_incremental: bool,
+
+    /// Colour with N CPU threads. Defaults to the number of physical cores; 1 colours serially.
+    #[arg(long, value_name = "N")]
+    threads: Option<usize>,
+
+    /// How far the window's colours move toward new ones each frame, from 0.01 (eases in slowly)
+    /// to 1 (changes at once) as pixels escape. Defaults to 1.
+    #[arg(long, value_name = "ALPHA")]
+    colour_smoothing: Option<f32>,
+
+    /// Run N compute shader invocations per workgroup instead of a size chosen for the GPU.
+    #[arg(long, value_name = "N")]
+    workgroup_size: Option<u32>,
+
+    /// Anti-alias the set's boundary by iterating N jittered samples per pixel. Renders each view
+    /// in one pass instead of refining it; single precision Mandelbrot sets only.
+    #[arg(long, value_name = "N")]
+    samples_per_pixel: Option<u32>,
+
+    /// Render N frames without a window, print frame time statistics, and exit.
+    #[arg(long, value_name = "N")]
+    benchmark: Option<u32>,
+
+    /// The view rendered by `--benchmark`, as `re,im,zoom`.
+    #[arg(long, default_value = "-0.74529,0.113075,1")]
+    benchmark_location: Location,
+
+    /// Render a video zooming toward `--target` without a window, save it to PATH, and exit.
+    /// Uses ffmpeg if it's installed, and otherwise saves a PNG per frame.
+    #[arg(long, value_name = "PATH")]
+    record: Option<PathBuf>,
+
+    /// How many frames `--record` renders.
+    #[arg(long, default_value_t = 900, value_name = "N")]
+    frames: u32,
+
+    /// The point `--record` zooms toward, as `re,im`.
+    #[arg(long, default_value = "-0.74529,0.113075", value_parser = location::parse_point)]
+    target: ComplexF64,
+
+    /// How much `--record` multiplies the zoom by each frame. Higher makes a faster video.
+    #[arg(long, default_value_t = 1.02)]
+    zoom_factor: f64,
+
+    /// Render the view given by the other flags and `--config` until it's finished, save it to PATH
+    /// as a PNG, and exit. `--size` sets the image size.
+    #[arg(long, value_name = "PATH")]
+    snapshot: Option<PathBuf>,
+
+    /// Log wgpu validation errors instead of panicking. Debug builds also enable the graphics
+    /// API's validation layers.
+    #[arg(long)]
+    validation: bool,
+
+    /// Print the available adapters and exit.
+    #[arg(long)]
+    list_adapters: bool,
+
+    /// Read the shaders from DIR instead of the copies built in, and reload them when they change.
+    #[arg(long, value_name = "DIR")]
+    shader_dir: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Backend {
+    Vulkan,
+    Metal,
+    Dx12,
+    Gl,
+}
+
+impl Args {
+    /// The settings in `--config`, or the defaults, with any flags given in their place.
+    fn settings(&self) -> Result<Settings, Box<dyn std::error::Error>> {
+        let mut settings = match &self.config {
+            Some(path) => settings::load(path).map_err(|err| {
+                format!("couldn't load settings from {}: {}", path.display(), err)
+            })?,
+            None => Settings::default(),
+        };
+        if let Some(seed) = &self.seed_location {
+            settings.origin = seed.origin;
+            settings.zoom = seed.zoom;
+            settings.max_iterations = seed.max_iterations;
+        }
+        if let Some(origin) = self.origin {
+            settings.origin = origin;
+        }
+        if let Some(zoom) = self.zoom {
+            settings.zoom = zoom;
+        }
+        if let Some(palette) = self.palette {
+            settings.palette = palette;
+        }
+        if let Some(max_iterations) = self.max_iterations {
+            settings.max_iterations = max_iterations;
+        }
+        if let Some(escape_radius) = self.escape_radius {
+            settings.escape_radius = escape_radius;
+        }
+        if let Some(ssaa) = self.ssaa {
+            settings.ssaa = ssaa;
+        }
+        if self.no_incremental {
+            settings.incremental = false;
+        }
+        Ok(settings)
+    }
+
+    /// The scaling given by `--auto-iterations`, starting from `settings`' maximum iteration count.
+    fn auto_iterations(&self, settings: &Settings) -> Option<compute::AutoIterations> {
+        self.auto_iterations.map(|per_doubling| {
+            compute::AutoIterations::new()
+                .with_base(settings.max_iterations)
+                .with_per_doubling(per_doubling)
         })
-        .collect::<Vec<_>>()
+    }
+
+    /// The palette given by `--palette-file`, or else the one in `settings`.
+    fn initial_palette(
+        &self,
+        settings: &Settings,
+    ) -> Result<Box<dyn colour::Palette>, Box<dyn std::error::Error>> {
+        match &self.palette_file {
+            Some(path) => Ok(Box::new(colour::Custom::load(path).map_err(|err| {
+                format!("couldn't load palette from {}: {}", path.display(), err)
+            })?)),
+            None => Ok(settings.palette.palette()),
+        }
+    }
 }
 
-fn create_pixels_buffers(
-    device: &wgpu::Device,
-    size: screen::Size,
-) -> typed_buffer::DoubleBuffer<Pixel> {
-    let pixels = create_pixels(size);
-
-    typed_buffer::DoubleBuffer {
-        input: typed_buffer::Builder::from(pixels.as_slice())
-            .with_label("pixels_buffer_1")
-            .with_usage(wgpu::BufferUsages::STORAGE)
-            .with_usage(wgpu::BufferUsages::COPY_SRC)
-            .create(device),
-
-        output: typed_buffer::Builder::from(pixels.as_slice())
-            .with_label("pixels_buffer_2")
-            .with_usage(wgpu::BufferUsages::STORAGE)
-            .with_usage(wgpu::BufferUsages::COPY_SRC)
-            .create(device),
+impl From<Backend> for wgpu::Backends {
+    fn from(backend: Backend) -> Self {
+        match backend {
+            Backend::Vulkan => wgpu::Backends::VULKAN,
+            Backend::Metal => wgpu::Backends::METAL,
+            Backend::Dx12 => wgpu::Backends::DX12,
+            Backend::Gl => wgpu::Backends::GL,
+        }
     }
 }
 
-#[repr(C)]
-#[derive(Pod, Zeroable, Clone, Copy, Debug)]
-struct Vec2 {
-    x: f32,
-    y: f32,
+#[derive(Clone, Copy, ValueEnum)]
+enum Power {
+    Low,
+    High,
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum PresentMode {
+    Fifo,
+    Mailbox,
+    Immediate,
+}
+
+impl From<PresentMode> for wgpu::PresentMode {
+    fn from(present_mode: PresentMode) -> Self {
+        match present_mode {
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+        }
+    }
+}
+
+impl From<Power> for wgpu::PowerPreference {
+    fn from(power: Power) -> Self {
+        match power {
+            Power::Low => wgpu::PowerPreference::LowPower,
+            Power::High => wgpu::PowerPreference::HighPerformance,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
     env_logger::init();
 
+    let args = Args::parse();
+
+    let backends = args
+        .backend
+        .map_or(wgpu::Backends::all(), wgpu::Backends::from);
+    let instance = wgpu::Instance::new(backends);
+
+    if args.list_adapters {
+        for adapter in instance.enumerate_adapters(backends) {
+            let info = adapter.get_info();
+            println!("{} ({:?}, {:?})", info.name, info.backend, info.device_type);
+        }
+        return;
+    }
+
     ThreadPoolBuilder::new()
-        .num_threads(num_cpus::get_physical())
+        .num_threads(args.threads.unwrap_or_else(num_cpus::get_physical))
         .build_global()
         .unwrap();
 
-    let event_loop = EventLoop::new();
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
+    if args.benchmark.is_some() || args.record.is_some() || args.snapshot.is_some() {
+        let adapter =
+            match pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: args.power.map(Into::into).unwrap_or_default(),
+                force_fallback_adapter: false,
+                compatible_surface: None,
+            })) {
+                Some(adapter) => adapter,
+                None => {
+                    eprintln!("no GPU adapter found");
+                    std::process::exit(1);
+                }
+            };
+        if let Some(frames) = args.benchmark {
+            if let Err(err) = benchmark(&adapter, &args, frames) {
+                eprintln!("couldn't run the benchmark: {}", err);
+                std::process::exit(1);
+            }
+        }
+        if let Some(path) = &args.record {
+            if let Err(err) = record(&adapter, &args, path) {
+                eprintln!("couldn't record a video to {}: {}", path.display(), err);
+                std::process::exit(1);
+            }
+        }
+        if let Some(path) = &args.snapshot {
+            if let Err(err) = snapshot(&adapter, &args, path) {
+                eprintln!("couldn't save a snapshot to {}: {}", path.display(), err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    pollster::block_on(run(args, instance));
+}
+
+/**
+The browser entry point. Build with `wasm-bindgen --target web` and open `index.html`.
+
+Rayon has no threads to spread work over on the web, so it runs on the main thread.
+*/
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+    console_log::init_with_level(log::Level::Info).unwrap();
+
+    // There's no command line, so start with the defaults.
+    let args = Args::parse_from([TITLE]);
+    let instance = wgpu::Instance::new(wgpu::Backends::BROWSER_WEBGPU);
 
-    let instance = wgpu::Instance::new(wgpu::Backends::all());
+    wasm_bindgen_futures::spawn_local(run(args, instance));
+}
 
+/// Open a window and render into it until it's closed.
+async fn run(args: Args, instance: wgpu::Instance) {
+    // User events are the names of shaders that changed in `--shader-dir`.
+    let event_loop = EventLoop::<String>::with_user_event();
+    let window_builder = WindowBuilder::new().with_title(TITLE);
+    // The canvas would otherwise start at the browser's default of 300×150.
+    #[cfg(target_arch = "wasm32")]
+    let window_builder =
+        window_builder.with_inner_size(winit::dpi::LogicalSize::new(1280.0, 720.0));
+    let window_builder = match args.size {
+        Some(size) => {
+            window_builder.with_inner_size(winit::dpi::PhysicalSize::new(size.width, size.height))
+        }
+        None => window_builder,
+    };
+    let window_builder = if args.fullscreen {
+        window_builder.with_fullscreen(Some(Fullscreen::Borderless(None)))
+    } else {
+        window_builder
+    };
+    let window = window_builder.build(&event_loop).unwrap();
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        use winit::platform::web::WindowExtWebSys;
+
+        web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.body())
+            .and_then(|body| {
+                body.append_child(&web_sys::Element::from(window.canvas()))
+                    .ok()
+            })
+            .expect("couldn't add the canvas to the page");
+    }
+
+    // Everything from here on is in physical pixels, so a scale factor only changes the window's size.
+    info!("scale factor {}", window.scale_factor());
     let mut size = window.inner_size();
+    // Minimizing resizes the window to zero, which the surface can't be configured with.
+    let mut minimized = false;
     let surface = unsafe { instance.create_surface(&window) };
 
-    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
-        power_preference: Default::default(),
-        force_fallback_adapter: false,
-        compatible_surface: Some(&surface),
-    }))
-    .unwrap();
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: args.power.map(Into::into).unwrap_or_default(),
+            force_fallback_adapter: false,
+            compatible_surface: Some(&surface),
+        })
+        .await
+        .unwrap();
 
-    let (device, queue) = pollster::block_on(adapter.request_device(
-        &wgpu::DeviceDescriptor {
-            label: Some("device"),
-            features: wgpu::Features::empty(),
-            limits: wgpu::Limits::default(),
-        },
-        None,
-    ))
-    .unwrap();
+    let settings = args.settings().unwrap_or_else(|err| panic!("{}", err));
+
+    // Every surface supports `Fifo`.
+    let present_mode = wgpu::PresentMode::from(args.present_mode);
+    let present_mode = if surface
+        .get_supported_present_modes(&adapter)
+        .contains(&present_mode)
+    {
+        present_mode
+    } else {
+        warn!(
+            "present mode {:?} isn't supported; using {:?}",
+            present_mode,
+            wgpu::PresentMode::Fifo
+        );
+        wgpu::PresentMode::Fifo
+    };
+    info!("presenting with {:?}", present_mode);
+
+    let format = surface.get_supported_formats(&adapter)[0];
+    info!("surface format {:?}", format);
 
     let mut surface_configuration = wgpu::SurfaceConfiguration {
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-        format: surface.get_supported_formats(&adapter)[0],
+        format,
         width: size.width,
         height: size.height,
-        present_mode: wgpu::PresentMode::Fifo,
+        present_mode,
         alpha_mode: wgpu::CompositeAlphaMode::Auto,
     };
-    surface.configure(&device, &surface_configuration);
 
-    let compute_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("compute-shader"),
-        source: wgpu::ShaderSource::Wgsl(include_str!("compute.wgsl").into()),
-    });
-
-    let compute_bind_group_layout_1 =
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("compute-bind-group-layout-1"),
-            entries: &[
-                // compute.wgsl#screen_size
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                // compute.wgsl#zoom
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                // compute.wgsl#origin
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
-        });
-
-    let compute_bind_group_layout_2 =
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("compute-bind-group-layout-2"),
-            entries: &[
-                // compute.wgsl#input
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                // compute.wgsl#output
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
-        });
-
-    let compute_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("compute-pipeline-layout"),
-        bind_group_layouts: &[&compute_bind_group_layout_1, &compute_bind_group_layout_2],
-        push_constant_ranges: &[],
-    });
-
-    let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-        label: Some("compute-pipeline"),
-        layout: Some(&compute_pipeline_layout),
-        module: &compute_shader_module,
-        entry_point: "mandelbrot",
-    });
-
-    let render_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("render-shader"),
-        source: wgpu::ShaderSource::Wgsl(include_str!("render.wgsl").into()),
-    });
+    let mut renderer = Renderer::request(
+        &adapter,
+        surface_configuration.format,
+        screen::Size {
+            width: size.width as u32,
+            height: size.height as u32,
+        },
+        settings.origin,
+        settings.zoom,
+    )
+    .await
+    .unwrap_or_else(|err| panic!("{}", err));
+    if args.validation {
+        renderer.log_validation_errors();
+    }
+    surface.configure(&renderer.device, &surface_configuration);
 
-    let render_bind_group_layout_1 =
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("render-bind-group-layout"),
-            entries: &[
-                // render.wgsl#screen_size
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
-        });
-
-    let render_bind_group_layout_2 =
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("render-bind-group-layout-2"),
-            entries: &[
-                // render.wgsl#colour_ranges
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
-        });
-
-    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("render-pipeline-layout"),
-        bind_group_layouts: &[&render_bind_group_layout_1, &render_bind_group_layout_2],
-        push_constant_ranges: &[],
-    });
+    if let Err(err) = settings.apply(&mut renderer) {
+        error!("{}", err);
+    }
+    let palette = args
+        .initial_palette(&settings)
+        .unwrap_or_else(|err| panic!("{}", err));
+    renderer.set_palette(palette.as_ref());
+    renderer.set_msaa(args.msaa);
+    renderer.set_iterations_per_frame(args.iterations_per_frame);
+    renderer.set_auto_iterations(args.auto_iterations(&settings));
+    if let Some(alpha) = args.colour_smoothing {
+        renderer.set_colour_smoothing(alpha);
+    }
+    renderer.set_target(args.drift_to);
+    if let Some(workgroup_size) = args.workgroup_size {
+        if let Err(err) = renderer.set_workgroup_size(workgroup_size) {
+            error!("{}", err);
+        }
+    }
+    if let Some(samples_per_pixel) = args.samples_per_pixel {
+        if let Err(err) = renderer.set_samples_per_pixel(samples_per_pixel) {
+            error!("{}", err);
+        }
+    }
+    // Run with `RUST_LOG=wgpu_mandelbrot=trace` to see how quickly the unescaped pixels drain.
+    // Only then, since reading the GPU timings waits for each frame to finish rendering.
+    if log::log_enabled!(log::Level::Trace) {
+        renderer.set_frame_callback(|frame_stats| trace!("{:?}", frame_stats));
+    }
 
-    let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("render-pipeline"),
-        layout: Some(&render_pipeline_layout),
-        vertex: wgpu::VertexState {
-            module: &render_shader_module,
-            entry_point: "vertex_main",
-            buffers: &[],
-        },
-        primitive: wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::TriangleStrip,
-            strip_index_format: None,
-            front_face: wgpu::FrontFace::Ccw,
-            cull_mode: Some(wgpu::Face::Back),
-            unclipped_depth: false,
-            polygon_mode: wgpu::PolygonMode::Fill,
-            conservative: false,
-        },
-        depth_stencil: None,
-        multisample: wgpu::MultisampleState::default(),
-        fragment: Some(wgpu::FragmentState {
-            module: &render_shader_module,
-            entry_point: "fragment_main",
-            targets: &[Some(wgpu::ColorTargetState {
-                format: surface_configuration.format,
-                blend: Some(wgpu::BlendState::REPLACE),
-                write_mask: wgpu::ColorWrites::ALL,
-            })],
-        }),
-        multiview: None,
+    // Watching stops when this is dropped, which is never, since `event_loop.run` doesn't return.
+    #[cfg(not(target_arch = "wasm32"))]
+    let _shader_watcher = args.shader_dir.as_ref().map(|dir| {
+        if let Err(err) = renderer.set_shader_dir(Some(dir.clone())) {
+            error!("{}", err);
+        }
+        let proxy = event_loop.create_proxy();
+        shader::watch(dir, move |name| {
+            // Only fails once the event loop has exited.
+            let _ = proxy.send_event(name);
+        })
+        .unwrap_or_else(|err| panic!("couldn't watch {}: {}", dir.display(), err))
     });
 
-    let mut screen_size = screen::Size {
-        width: size.width as u32,
-        height: size.height as u32,
+    #[cfg(feature = "hud")]
+    let mut hud = hud::Hud::new(&event_loop, &renderer.device, surface_configuration.format);
+    #[cfg(feature = "hud")]
+    let mut last_frame = Instant::now();
+
+    let viewer = Viewer::new(renderer, settings.palette, palette)
+        .with_animation_speed(args.animation_speed)
+        .with_cycle_speed(args.cycle_speed)
+        .with_drift_speed(args.drift_speed)
+        .with_zoom_sensitivity(args.zoom_sensitivity)
+        .with_invert_zoom(args.invert_zoom);
+    let viewer = match &args.seed_location {
+        Some(seed) => viewer.with_seed_location(seed),
+        None => viewer,
     };
-    let screen_size_buffer = typed_buffer::var::Builder::new(screen_size)
-        .with_label("screen-size-buffer")
-        .with_usage(wgpu::BufferUsages::UNIFORM)
-        .create(&device);
-
-    let mut zoom: f32 = 1.0;
-    let zoom_buffer = typed_buffer::var::Builder::new(zoom)
-        .with_label("zoom-buffer")
-        .with_usage(wgpu::BufferUsages::UNIFORM)
-        .create(&device);
-
-    let mut origin: Vec2 = Vec2 {
-        x: -0.74529,
-        y: 0.113075,
+    let mut viewer = match args.size {
+        Some(size) => {
+            viewer.with_windowed_size(winit::dpi::PhysicalSize::new(size.width, size.height))
+        }
+        None => viewer,
     };
-    let origin_buffer = typed_buffer::var::Builder::new(origin)
-        .with_label("origin-buffer")
-        .with_usage(wgpu::BufferUsages::UNIFORM)
-        .create(&device);
-
-    let mut pixels_staging_buffer: typed_buffer::Buffer<Pixel> =
-        typed_buffer::Builder::new(screen_size.width as u64 * screen_size.height as u64)
-            .with_label("pixels_staging_buffer")
-            .with_usage(wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ)
-            .create(&device);
-
-    let mut pixels_buffers = create_pixels_buffers(&device, screen_size);
-
-    let mut compute_bind_group_1 = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("compute-bind-group-1"),
-        layout: &compute_bind_group_layout_1,
-        entries: &[
-            // compute.wgsl#screen_size
-            wgpu::BindGroupEntry {
-                binding: 0,
-                resource: screen_size_buffer.binding_resource(),
-            },
-            // compute.wgsl#zoom
-            wgpu::BindGroupEntry {
-                binding: 1,
-                resource: zoom_buffer.binding_resource(),
-            },
-            // compute.wgsl#origin
-            wgpu::BindGroupEntry {
-                binding: 2,
-                resource: origin_buffer.binding_resource(),
-            },
-        ],
-    });
 
-    let mut render_bind_group_1 = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("render-bind-group"),
-        layout: &render_pipeline.get_bind_group_layout(0),
-        entries: &[
-            // render.wgsl#screen_size
-            wgpu::BindGroupEntry {
-                binding: 0,
-                resource: screen_size_buffer.binding_resource(),
-            },
-        ],
-    });
+    // `--max-fps` waits this long after each redraw before requesting the next one.
+    let frame_interval =
+        (args.max_fps > 0).then(|| Duration::from_secs_f64(1.0 / args.max_fps as f64));
+    let mut last_redraw = Instant::now();
+    // Whether the last frame found the surface lost. If reconfiguring it didn't help, the device is.
+    let mut surface_lost = false;
 
-    let mut cursor_position = Vec2 { x: 0.0, y: 0.0 };
-    let mut zoom_changed = false;
-    let mut origin_changed = false;
+    event_loop.run(move |event, _target, control_flow| {
+        match event {
+            Event::MainEventsCleared => {
+                let animating = viewer.update();
+
+                /*
+                To present frames in realtime, poll and `request_redraw` once we've cleared all
+                events for the frame. Once nothing is moving and the image has converged, there's
+                nothing to draw until the next interaction, so wait for it instead.
+                */
+                let now = Instant::now();
+                let next_redraw = frame_interval.map(|interval| last_redraw + interval);
+                if viewer.is_idle(animating) {
+                    control_flow.set_wait();
+                } else if let Some(next_redraw) = next_redraw.filter(|&next| next > now) {
+                    control_flow.set_wait_until(next_redraw);
+                } else {
+                    control_flow.set_poll();
+                    window.request_redraw();
+                }
+            }
+            Event::WindowEvent { window_id, event } if window_id == window.id() => {
+                #[cfg(feature = "hud")]
+                if hud.on_event(&event) {
+                    return;
+                }
 
-    let mut colour_ranges_buffer: typed_buffer::Buffer<ColourRange> = typed_buffer::Builder::from(
-        std::iter::repeat(ColourRange::default())
-            .take((screen_size.width * screen_size.height) as usize)
-            .collect::<Vec<_>>()
-            .as_slice(),
-    )
-    .with_usage(wgpu::BufferUsages::STORAGE)
-    .create(&device);
+                match event {
+                    WindowEvent::CloseRequested => {
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    // The window's physical size changes with the scale factor, e.g. when it moves
+                    // to a monitor with a different DPI, and not every platform follows that with
+                    // `Resized`.
+                    WindowEvent::Resized(new_size)
+                    | WindowEvent::ScaleFactorChanged {
+                        new_inner_size: &mut new_size,
+                        ..
+                    } => {
+                        if new_size.width == 0 || new_size.height == 0 {
+                            debug!("minimized, keeping size {:?}", size);
+                            minimized = true;
+                            return;
+                        }
+                        minimized = false;
 
-    let mut colour_ranges: Vec<ColourRange> = std::iter::repeat(ColourRange::default())
-        .take((screen_size.width * screen_size.height) as usize)
-        .collect();
+                        debug!("resizing to {:?}", new_size);
+                        size = new_size;
+                        let screen_size = screen::Size {
+                            width: size.width as u32,
+                            height: size.height as u32,
+                        };
 
-    let mut histogram_colouring = HistogramColouring::new();
+                        surface_configuration.width = size.width;
+                        surface_configuration.height = size.height;
 
-    let mut all_pixels: Vec<Pixel> = create_pixels(screen_size);
-    let mut unescaped_pixels: Vec<Pixel> = create_pixels(screen_size);
-    let mut newly_escaped_pixels: Vec<Pixel> = Vec::new();
+                        surface.configure(&viewer.renderer.device, &surface_configuration);
 
-    let device = Arc::new(device);
+                        viewer.renderer.resize(screen_size);
 
-    event_loop.run(move |event, _, control_flow| {
-        // To present frames in realtime, *don't* set `control_flow` to `Wait`.
-        // control_flow.set_wait();
-        match event {
-            Event::MainEventsCleared => {
-                // And `request_redraw` once we've cleared all events for the frame.
-                window.request_redraw();
-            }
-            Event::WindowEvent { window_id, event } if window_id == window.id() => match event {
-                WindowEvent::CloseRequested => {
-                    *control_flow = ControlFlow::Exit;
-                }
-                WindowEvent::CursorMoved { position, .. } => {
-                    cursor_position.x = position.x as f32;
-                    cursor_position.y = position.y as f32;
-                }
-                WindowEvent::MouseInput {
-                    state: winit::event::ElementState::Pressed,
-                    button: winit::event::MouseButton::Left,
-                    ..
-                } => {
-                    debug!("mouse pressed at {:?}", cursor_position);
-
-                    /*
-                    when `zoom = 1.0`, we're viewing (-2, -2) to (2, 2).
-
-                    (0, 0) corresponds to (size.width / 2, size.height / 2)
-
-                    A click at (cursor_x, cursor_y) corresponds to (4 * cursor_x / size.width - 2, 4 * cursor_y / size.height - 2)
-                     */
-
-                    let zoom_inv = 2.0 / zoom;
-                    origin = Vec2 {
-                        x: origin.x
-                            + (2.0 * zoom_inv * cursor_position.x / (size.width as f32) - zoom_inv),
-                        y: origin.y
-                            + (2.0 * zoom_inv * cursor_position.y / (size.height as f32)
-                                - zoom_inv),
-                    };
-                    debug!("origin set to {:?}", origin);
-                    origin_changed = true;
-                    origin_buffer.write(&queue, origin);
-                }
-                WindowEvent::MouseWheel { delta, .. } => {
-                    zoom += zoom
-                        * 0.1
-                        * match delta {
-                            winit::event::MouseScrollDelta::LineDelta(_, delta) => delta,
-                            winit::event::MouseScrollDelta::PixelDelta(position) => (position.y / 1000.0 as f64) as f32,
-                        };
-                    zoom_changed = true;
-                    zoom_buffer.write(&queue, zoom);
+                        window.request_redraw();
+                    }
+                    event => viewer.handle_window_event(&window, event),
                 }
-                WindowEvent::Resized(new_size) => {
-                    debug!("resizing to {:?}", new_size);
-                    size = new_size;
-                    screen_size = screen::Size {
-                        width: size.width as u32,
-                        height: size.height as u32,
-                    };
-
-                    surface_configuration.width = size.width;
-                    surface_configuration.height = size.height;
-
-                    surface.configure(&device, &surface_configuration);
-
-                    colour_ranges.clear();
-                    colour_ranges.extend(
-                        std::iter::repeat(ColourRange::default())
-                            .take((screen_size.width * screen_size.height) as usize),
-                    );
-                    histogram_colouring.reset();
-
-                    screen_size_buffer.write(&queue, screen_size);
-
-                    pixels_staging_buffer = typed_buffer::Builder::new(
-                        screen_size.width as u64 * screen_size.height as u64,
-                    )
-                    .with_label("pixels_staging_buffer")
-                    .with_usage(wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ)
-                    .create(&device);
-
-                    std::mem::replace(
-                        &mut pixels_buffers,
-                        create_pixels_buffers(&device, screen_size),
-                    )
-                    .destroy();
-                    all_pixels = create_pixels(screen_size);
-                    unescaped_pixels = create_pixels(screen_size);
-
-                    std::mem::replace(
-                        &mut colour_ranges_buffer,
-                        typed_buffer::Builder::from(
-                            std::iter::repeat(ColourRange::default())
-                                .take((screen_size.width * screen_size.height) as usize)
-                                .collect::<Vec<_>>()
-                                .as_slice(),
-                        )
-                        .with_usage(wgpu::BufferUsages::STORAGE)
-                        .create(&device),
-                    )
-                    .destroy();
-
-                    compute_bind_group_1 = device.create_bind_group(&wgpu::BindGroupDescriptor {
-                        label: Some("compute-bind-group"),
-                        layout: &compute_bind_group_layout_1,
-                        entries: &[
-                            // compute.wgsl#screen_size
-                            wgpu::BindGroupEntry {
-                                binding: 0,
-                                resource: screen_size_buffer.binding_resource(),
-                            },
-                            // compute.wgsl#zoom
-                            wgpu::BindGroupEntry {
-                                binding: 1,
-                                resource: zoom_buffer.binding_resource(),
-                            },
-                            // compute.wgsl#origin
-                            wgpu::BindGroupEntry {
-                                binding: 2,
-                                resource: origin_buffer.binding_resource(),
-                            },
-                        ],
-                    });
-
-                    render_bind_group_1 = device.create_bind_group(&wgpu::BindGroupDescriptor {
-                        label: Some("render-bind-group"),
-                        layout: &render_pipeline.get_bind_group_layout(0),
-                        entries: &[
-                            // render.wgsl#screen_size
-                            wgpu::BindGroupEntry {
-                                binding: 0,
-                                resource: screen_size_buffer.binding_resource(),
-                            },
-                        ],
-                    });
-
+            }
+            Event::UserEvent(name) => match viewer.renderer.reload_shader(&name) {
+                Ok(true) => {
+                    info!("reloaded {}", name);
                     window.request_redraw();
                 }
-                _ => {}
+                Ok(false) => {}
+                // Keep drawing with the old shaders until the file is fixed.
+                Err(err) => error!("{}", err),
             },
             Event::RedrawRequested(window_id) if window_id == window.id() => {
-                debug_assert!(
-                    unescaped_pixels.len()
-                        <= screen_size.width as usize * screen_size.height as usize
-                );
-                if cfg!(debug_assertions) {
-                    for pixel in unescaped_pixels.iter() {
-                        debug_assert!(pixel.escaped < 2);
+                if minimized {
+                    return;
+                }
+                last_redraw = Instant::now();
+
+                #[cfg(feature = "hud")]
+                let overlay = |renderer: &Renderer, view: &wgpu::TextureView| {
+                    let now = Instant::now();
+                    hud.render(
+                        &window,
+                        &renderer.device,
+                        &renderer.queue,
+                        view,
+                        hud::Stats {
+                            origin: renderer.origin(),
+                            zoom: renderer.zoom(),
+                            frame_time: now.duration_since(last_frame),
+                            unescaped: renderer.pixels.unescaped_len(),
+                            convergence: renderer.convergence(),
+                            grid_spacing: renderer
+                                .grid
+                                .then(|| grid::spacing(renderer.display_size, renderer.zoom())),
+                        },
+                    );
+                    last_frame = now;
+                };
+                #[cfg(not(feature = "hud"))]
+                let overlay = |_: &Renderer, _: &wgpu::TextureView| {};
+
+                match viewer.render_frame(&surface, overlay) {
+                    Ok(()) => surface_lost = false,
+                    // The window moved to another monitor, or the GPU was reset.
+                    Err(err @ (wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost)) => {
+                        let lost = matches!(err, wgpu::SurfaceError::Lost);
+                        #[cfg(not(target_arch = "wasm32"))]
+                        if lost && surface_lost {
+                            match recover_device(&instance, &surface, &args, &mut viewer.renderer) {
+                                Ok(()) => {
+                                    surface_configuration.format = viewer.renderer.format;
+                                    #[cfg(feature = "hud")]
+                                    {
+                                        hud = hud::Hud::new(
+                                            _target,
+                                            &viewer.renderer.device,
+                                            surface_configuration.format,
+                                        );
+                                    }
+                                }
+                                // The old device is gone, so there's nothing left to render with.
+                                Err(err) => {
+                                    eprintln!("couldn't recover from losing the device: {}", err);
+                                    *control_flow = ControlFlow::ExitWithCode(1);
+                                    return;
+                                }
+                            }
+                        }
+                        surface_lost = lost;
+
+                        debug!("reconfiguring surface: {}", err);
+                        surface.configure(&viewer.renderer.device, &surface_configuration);
+                        window.request_redraw();
                     }
+                    Err(wgpu::SurfaceError::OutOfMemory) => {
+                        error!("out of memory, exiting");
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    Err(err @ wgpu::SurfaceError::Timeout) => warn!("skipping frame: {}", err),
                 }
+            }
+            _ => {}
+        }
+    });
+}
 
-                let reset_buffers = zoom_changed || origin_changed;
-                zoom_changed = false;
-                origin_changed = false;
+/**
+Request a new adapter for `surface` and recreate `renderer` on it with [`Renderer::recover`], after
+its device was lost. The surface has to be reconfigured with the new device afterwards, in
+`renderer.format`, which only changes if the new adapter can't present the old one. Fails with
+[`renderer::RendererError::NoAdapter`] or [`renderer::RendererError::RequestDevice`] if there's
+no new device to recover on.
+*/
+#[cfg(not(target_arch = "wasm32"))]
+fn recover_device(
+    instance: &wgpu::Instance,
+    surface: &wgpu::Surface,
+    args: &Args,
+    renderer: &mut Renderer,
+) -> Result<(), renderer::RendererError> {
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: args.power.map(Into::into).unwrap_or_default(),
+        force_fallback_adapter: false,
+        compatible_surface: Some(surface),
+    }))
+    .ok_or(renderer::RendererError::NoAdapter)?;
+    renderer.recover(&adapter)?;
 
-                if reset_buffers {
-                    colour_ranges.clear();
-                    colour_ranges.extend(
-                        std::iter::repeat(ColourRange::default())
-                            .take((screen_size.width * screen_size.height) as usize),
-                    );
-                    histogram_colouring.reset();
+    let formats = surface.get_supported_formats(&adapter);
+    if !formats.contains(&renderer.format) {
+        renderer.set_format(formats[0]);
+    }
+    if args.validation {
+        renderer.log_validation_errors();
+    }
+    Ok(())
+}
 
-                    let pixels = create_pixels(screen_size);
-                    pixels_buffers.input.write(&queue, &pixels);
-                    pixels_buffers.output.write(&queue, &pixels);
-                    all_pixels = pixels.clone();
-                    unescaped_pixels = pixels;
-                }
+/// The size of the view rendered by `--benchmark`.
+#[cfg(not(target_arch = "wasm32"))]
+const BENCHMARK_SIZE: screen::Size = screen::Size {
+    width: 1920,
+    height: 1080,
+};
 
-                let surface_texture = surface.get_current_texture().unwrap();
-                let surface_texture_view = surface_texture
-                    .texture
-                    .create_view(&wgpu::TextureViewDescriptor::default());
-
-                let compute_bind_group_2 = device.create_bind_group(&wgpu::BindGroupDescriptor {
-                    label: Some("compute-bind-group-2"),
-                    layout: &compute_bind_group_layout_2,
-                    entries: &[
-                        // compute.wgsl#input
-                        wgpu::BindGroupEntry {
-                            binding: 0,
-                            resource: pixels_buffers.input.binding_resource(0, None),
-                        },
-                        // compute.wgsl#output
-                        wgpu::BindGroupEntry {
-                            binding: 1,
-                            resource: pixels_buffers.output.binding_resource(0, None),
-                        },
-                    ],
-                });
-
-                let render_bind_group_2 = device.create_bind_group(&wgpu::BindGroupDescriptor {
-                    label: Some("render-bind-group-2"),
-                    layout: &render_pipeline.get_bind_group_layout(1),
-                    entries: &[
-                        // render.wgsl#colour_ranges
-                        wgpu::BindGroupEntry {
-                            binding: 0,
-                            resource: colour_ranges_buffer.binding_resource(0, None),
-                        },
-                    ],
-                });
-
-                let compute_command_buffer = command_buffer::create(
-                    &device,
-                    &wgpu::CommandEncoderDescriptor::default(),
-                    |command_encoder| {
-                        pixels_buffers.input.write(&queue, &unescaped_pixels);
-
-                        command_encoder.push_debug_group("compute-pass");
-                        command_encoder.with_compute_pass(
-                            &wgpu::ComputePassDescriptor {
-                                label: Some("compute-pass"),
-                            },
-                            |compute_pass| {
-                                compute_pass.set_pipeline(&compute_pipeline);
-
-                                compute_pass.set_bind_group(0, &compute_bind_group_1, &[]);
-                                compute_pass.set_bind_group(1, &compute_bind_group_2, &[]);
-
-                                compute_pass.insert_debug_marker("mandelbrot");
-
-                                let total_work = unescaped_pixels.len();
-
-                                let (x, y, z) = compute::mandelbrot_dispatch_size(total_work);
-
-                                compute_pass.dispatch_workgroups(x, y, z);
-                            },
-                        );
-                        command_encoder.pop_debug_group();
-
-                        typed_buffer::copy_buffer_to_buffer(
-                            command_encoder,
-                            &pixels_buffers.output,
-                            0,
-                            &pixels_staging_buffer,
-                            0,
-                            unescaped_pixels.len().try_into().unwrap(),
-                        );
-                    },
-                );
-
-                queue.submit([compute_command_buffer]);
-
-                let pixels_staging_buffer_slice = pixels_staging_buffer.slice(..);
-
-                {
-                    trace!("waiting for staging buffer");
-                    let mapped = Arc::new((Mutex::new(true), Condvar::new()));
-
-                    pixels_staging_buffer_slice.map_async(wgpu::MapMode::Read, {
-                        let mapped = mapped.clone();
-                        move |map_result| {
-                            debug!("map_async callback called");
-                            map_result.unwrap_or_else(|err| panic!("buffer async error: {}", err));
-                            let mut guard = mapped.0.lock().unwrap();
-                            *guard = false;
-                            mapped.1.notify_all();
-                        }
-                    });
+/**
+Render `frames` frames of `--benchmark-location` offscreen, and print how long they took. The
+other settings come from the command line and `--config`, as they do for `--record`.
+*/
+#[cfg(not(target_arch = "wasm32"))]
+fn benchmark(
+    adapter: &wgpu::Adapter,
+    args: &Args,
+    frames: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let location = args.benchmark_location;
+    let mut renderer = Renderer::new(
+        adapter,
+        renderer::HEADLESS_FORMAT,
+        BENCHMARK_SIZE,
+        location.origin,
+        location.zoom,
+    )?;
+    if args.validation {
+        renderer.log_validation_errors();
+    }
+    let settings = args.settings()?;
+    settings.apply(&mut renderer)?;
+    renderer.set_origin(location.origin);
+    renderer.set_zoom(location.zoom);
+    renderer.set_palette(args.initial_palette(&settings)?.as_ref());
+    renderer.set_msaa(args.msaa);
+    renderer.set_iterations_per_frame(args.iterations_per_frame);
+    renderer.set_auto_iterations(args.auto_iterations(&settings));
+    if let Some(workgroup_size) = args.workgroup_size {
+        renderer.set_workgroup_size(workgroup_size)?;
+    }
+    if let Some(samples_per_pixel) = args.samples_per_pixel {
+        renderer.set_samples_per_pixel(samples_per_pixel)?;
+    }
+    let texture = renderer.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("benchmark-texture"),
+        size: wgpu::Extent3d {
+            width: BENCHMARK_SIZE.width,
+            height: BENCHMARK_SIZE.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: renderer::HEADLESS_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    // Start the clock before collecting, so the first frame has a duration.
+    renderer.finish_frame();
+    let frame_stats: Rc<RefCell<Vec<FrameStats>>> = Rc::default();
+    {
+        let frame_stats = frame_stats.clone();
+        renderer.set_frame_callback(move |stats| frame_stats.borrow_mut().push(stats));
+    }
 
-                    {
-                        let device = device.clone();
-                        std::thread::spawn(move || while !device.poll(wgpu::Maintain::Poll) {});
-                    }
+    for _ in 0..frames {
+        renderer.compute();
+        renderer.render(&view);
+        renderer.finish_frame();
+    }
 
-                    debug!("waiting for condition");
-                    let _guard = mapped
-                        .1
-                        .wait_while(mapped.0.lock().unwrap(), |pending| *pending)
-                        .unwrap();
-                    debug!("staging buffer mapped");
-                }
+    let frame_stats = frame_stats.borrow();
 
-                {
-                    let pixels_staging_buffer_view: typed_buffer::View<Pixel> =
-                        pixels_staging_buffer_slice.get_mapped_range();
-
-                    let unescaped_pixels_len = unescaped_pixels.len();
-                    unescaped_pixels.clear();
-                    newly_escaped_pixels.clear();
-
-                    pixels_staging_buffer_view
-                        .iter()
-                        /*
-                        This caused a bug for me: even though I copy `unescaped_pixels.len()`
-                        worth of data into the staging buffer, the buffer is still the size
-                        of the screen.
-                        Without the `take`, I was iterating over every pixel in the buffer.
-                        Everything after `unescaped_pixels.len()` in the buffer is effectively
-                        garbage (leftover from previous runs), but I was including it in the
-                        `newly_escaped` array anyway.
-                        */
-                        .take(unescaped_pixels_len)
-                        .for_each(|pixel| {
-                            let pixel = *pixel;
-
-                            debug_assert!(pixel.x < screen_size.width);
-                            debug_assert!(pixel.y < screen_size.height);
-                            debug_assert!(pixel.escaped < 2);
-
-                            if pixel.escaped == 1 {
-                                all_pixels[pixel.y as usize * screen_size.width as usize
-                                    + pixel.x as usize] = pixel;
-                                newly_escaped_pixels.push(pixel);
-                            } else {
-                                unescaped_pixels.push(pixel);
-                            }
-                        });
-                }
+    // Each frame dispatches the pixels that were still unescaped after the frame before it. How
+    // many iterations a dispatch runs depends on `--iterations-per-frame`, `--auto-iterations`,
+    // `--samples-per-pixel` and when the pixel escapes, so dispatches are counted instead.
+    let mut dispatched = BENCHMARK_SIZE.width as u64 * BENCHMARK_SIZE.height as u64;
+    let mut total_dispatches = 0;
+    for stats in frame_stats.iter() {
+        total_dispatches += dispatched;
+        dispatched = stats.unescaped as u64;
+    }
 
-                pixels_staging_buffer.buffer().unmap();
-
-                histogram_colouring.update_colours(
-                    screen_size,
-                    &all_pixels,
-                    &newly_escaped_pixels,
-                    &mut colour_ranges,
-                );
-                debug_assert!(
-                    colour_ranges.len() == screen_size.width as usize * screen_size.height as usize,
-                    "colour_ranges.len() == {}, expected {}",
-                    colour_ranges.len(),
-                    screen_size.width * screen_size.height,
-                );
-
-                colour_ranges_buffer.write(&queue, &colour_ranges);
-
-                let render_command_buffer = command_buffer::create(
-                    &device,
-                    &wgpu::CommandEncoderDescriptor::default(),
-                    |command_encoder| {
-                        command_encoder.push_debug_group("render-pass");
-                        command_encoder.with_render_pass(
-                            &wgpu::RenderPassDescriptor {
-                                label: Some("render-pass"),
-                                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                                    view: &surface_texture_view,
-                                    resolve_target: None,
-                                    ops: wgpu::Operations {
-                                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                                            r: 0.5,
-                                            g: 0.5,
-                                            b: 0.0,
-                                            a: 1.0,
-                                        }),
-                                        store: true,
-                                    },
-                                })],
-                                depth_stencil_attachment: None,
-                            },
-                            |render_pass| {
-                                render_pass.set_pipeline(&render_pipeline);
-                                render_pass.set_bind_group(0, &render_bind_group_1, &[]);
-                                render_pass.set_bind_group(1, &render_bind_group_2, &[]);
-                                render_pass.draw(0..4, 0..1);
-                            },
-                        );
-                        command_encoder.pop_debug_group();
-                    },
-                );
-
-                trace!("submitting render commands");
-                queue.submit([render_command_buffer]);
-
-                surface_texture.present();
-
-                pixels_buffers.swap();
+    let mut frame_times: Vec<f64> = frame_stats
+        .iter()
+        .map(|stats| stats.frame_time.as_secs_f64() * 1000.0)
+        .collect();
+    frame_times.sort_by(|first, second| first.partial_cmp(second).unwrap());
+    let percentile =
+        |fraction: f64| frame_times[((frame_times.len() - 1) as f64 * fraction).round() as usize];
+
+    println!(
+        "{} frames of {}x{} at {},{},{}, workgroup size {}, {} iterations per frame, {} samples per pixel",
+        frame_times.len(),
+        BENCHMARK_SIZE.width,
+        BENCHMARK_SIZE.height,
+        location.origin.real,
+        location.origin.imaginary,
+        location.zoom,
+        renderer.workgroup_size,
+        renderer.iterations_per_frame,
+        renderer.samples_per_pixel
+    );
+    if frame_times.is_empty() {
+        return Ok(());
+    }
+    println!(
+        "frame time: mean {:.3} ms, median {:.3} ms, p99 {:.3} ms",
+        frame_times.iter().sum::<f64>() / frame_times.len() as f64,
+        percentile(0.5),
+        percentile(0.99)
+    );
+    if let Some(compute_times) = frame_stats
+        .iter()
+        .map(|stats| {
+            stats
+                .gpu_timings
+                .map(|timings| timings.compute.as_secs_f64())
+        })
+        .collect::<Option<Vec<f64>>>()
+    {
+        println!(
+            "gpu compute time: mean {:.3} ms",
+            compute_times.iter().sum::<f64>() * 1000.0 / compute_times.len() as f64
+        );
+    }
+    println!("pixel dispatches: {}", total_dispatches);
+    // Before colour ranges were uploaded by dirty range, every frame uploaded all of them.
+    let colour_bytes_written: u64 = frame_stats
+        .iter()
+        .map(|stats| stats.colour_bytes_written)
+        .sum();
+    let full_colour_bytes = frame_stats.len() as u64
+        * BENCHMARK_SIZE.width as u64
+        * BENCHMARK_SIZE.height as u64
+        * std::mem::size_of::<colour::ColourRange>() as u64;
+    println!(
+        "colour ranges uploaded: {:.1} MiB ({:.1}% of {:.1} MiB for full uploads)",
+        colour_bytes_written as f64 / (1024.0 * 1024.0),
+        colour_bytes_written as f64 * 100.0 / full_colour_bytes as f64,
+        full_colour_bytes as f64 / (1024.0 * 1024.0)
+    );
+    println!("unescaped after the last frame: {}", dispatched);
+    Ok(())
+}
+
+/// The size of the video rendered by `--record`.
+#[cfg(not(target_arch = "wasm32"))]
+const VIDEO_SIZE: screen::Size = screen::Size {
+    width: 1920,
+    height: 1080,
+};
+
+/// The frame rate of the video rendered by `--record`.
+#[cfg(not(target_arch = "wasm32"))]
+const VIDEO_FPS: u32 = 30;
+
+/**
+Render a video zooming toward `args.target` to `path`, one offscreen frame at a time.
+
+Fails if a frame can't be written or the video can't be finished, so a broken video is never
+reported as recorded.
+*/
+#[cfg(not(target_arch = "wasm32"))]
+fn record(
+    adapter: &wgpu::Adapter,
+    args: &Args,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut zoom = 1.0;
+    let mut renderer = Renderer::new(
+        adapter,
+        renderer::HEADLESS_FORMAT,
+        VIDEO_SIZE,
+        args.target,
+        zoom,
+    )?;
+    if args.validation {
+        renderer.log_validation_errors();
+    }
+    let settings = args.settings()?;
+    settings.apply(&mut renderer)?;
+    renderer.set_palette(args.initial_palette(&settings)?.as_ref());
+    renderer.set_msaa(args.msaa);
+    renderer.set_iterations_per_frame(args.iterations_per_frame);
+    renderer.set_auto_iterations(args.auto_iterations(&settings));
+    if let Some(workgroup_size) = args.workgroup_size {
+        renderer.set_workgroup_size(workgroup_size)?;
+    }
+    if let Some(samples_per_pixel) = args.samples_per_pixel {
+        renderer.set_samples_per_pixel(samples_per_pixel)?;
+    }
+
+    let mut sink = video::FrameSink::new(path, VIDEO_SIZE, VIDEO_FPS)?;
+    for frame in 0..args.frames {
+        let rgba = renderer.render_to_vec(args.target, zoom);
+        if let Err(err) = sink.write(&rgba) {
+            // Wait for ffmpeg to exit, but report why the frame couldn't be written rather than
+            // how ffmpeg exited.
+            if let Err(finish_err) = sink.finish() {
+                warn!("{}", finish_err);
             }
-            _ => {}
+            return Err(format!("couldn't write frame {}: {}", frame, err).into());
         }
-    });
+        info!(
+            "recorded frame {}/{} at zoom {}",
+            frame + 1,
+            args.frames,
+            zoom
+        );
+        zoom *= args.zoom_factor;
+    }
+
+    sink.finish()?;
+    info!("recorded {} frames to {:?}", args.frames, path);
+    Ok(())
+}
+
+/// The size of the image saved by `--snapshot` without `--size`.
+#[cfg(not(target_arch = "wasm32"))]
+const SNAPSHOT_SIZE: screen::Size = screen::Size {
+    width: 1920,
+    height: 1080,
+};
+
+/**
+Render the view given by `args` offscreen until every pixel has escaped or reached the maximum
+number of iterations, and save it to `path` as a PNG.
+*/
+#[cfg(not(target_arch = "wasm32"))]
+fn snapshot(
+    adapter: &wgpu::Adapter,
+    args: &Args,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let settings = args.settings()?;
+    let size = args.size.unwrap_or(SNAPSHOT_SIZE);
+    let mut renderer = Renderer::new(
+        adapter,
+        renderer::HEADLESS_FORMAT,
+        size,
+        settings.origin,
+        settings.zoom,
+    )?;
+    if args.validation {
+        renderer.log_validation_errors();
+    }
+    settings.apply(&mut renderer)?;
+    renderer.set_palette(args.initial_palette(&settings)?.as_ref());
+    renderer.set_msaa(args.msaa);
+    renderer.set_iterations_per_frame(args.iterations_per_frame);
+    renderer.set_auto_iterations(args.auto_iterations(&settings));
+    if let Some(workgroup_size) = args.workgroup_size {
+        renderer.set_workgroup_size(workgroup_size)?;
+    }
+    if let Some(samples_per_pixel) = args.samples_per_pixel {
+        renderer.set_samples_per_pixel(samples_per_pixel)?;
+    }
+
+    let rgba = renderer.render_to_vec(renderer.origin(), renderer.zoom());
+    screenshot::save(path, size, &rgba)?;
+    info!("saved a snapshot to {}", path.display());
+    Ok(())
 }
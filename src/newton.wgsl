@@ -0,0 +1,74 @@
+/*
+[Newton's method](https://en.wikipedia.org/wiki/Newton_fractal) for `p(z) = z^3 - 1`, iterating
+`z - p(z) / p'(z)` from each pixel's coordinate.
+
+Every starting point but a few converges to one of the three cube roots of unity. Instead of
+escaping, a pixel stops once it's within `NEWTON_TOLERANCE` of a root, and `Pixel.root_index`
+records which. `escape_radius`, `power` and the orbit trap don't apply.
+
+Appended to `compute.wgsl`, whose bindings, `Pixel` and complex arithmetic it shares. See
+`compute.rs#Precision::shader_source`.
+*/
+
+// How close, squared, `z` has to get to a root to have converged to it.
+let NEWTON_TOLERANCE = 1e-6;
+
+// `Pixel.escaped` once the pixel has converged, which is coloured like an escaped pixel.
+let CONVERGED = 1u;
+
+fn divide_complex(first: Complex, second: Complex) -> Complex {
+  let denominator = squared_length_complex(second);
+  let real = first.real * second.real + first.imaginary * second.imaginary;
+  let imaginary = first.imaginary * second.real - first.real * second.imaginary;
+  return Complex(real / denominator, imaginary / denominator);
+}
+
+// `z - (z^3 - 1) / 3z^2`. `z = 0` gives NaN, which never converges.
+fn newton_step(z: Complex) -> Complex {
+  let z_squared = multiply_complex(z, z);
+  let p = add_complex(multiply_complex(z_squared, z), Complex(-1.0, 0.0));
+  let dp = scale_complex(3.0, z_squared);
+  return add_complex(z, scale_complex(-1.0, divide_complex(p, dp)));
+}
+
+// `nearest_root` for a `z` that isn't near any root yet.
+let NO_ROOT = 3u;
+
+// The index of the root of `z^3 - 1` that `z` has converged to: `1`, then `e^(2πi/3)`, then
+// `e^(-2πi/3)`. `NO_ROOT` if it hasn't.
+fn nearest_root(z: Complex) -> u32 {
+  var roots = array<Complex, 3>(Complex(1.0, 0.0), Complex(-0.5, 0.8660254), Complex(-0.5, -0.8660254));
+  for (var i = 0u; i < 3u; i = i + 1u) {
+    if squared_length_complex(add_complex(z, scale_complex(-1.0, roots[i]))) < NEWTON_TOLERANCE {
+      return i;
+    }
+  }
+  return NO_ROOT;
+}
+
+@compute @workgroup_size(1, 64, 1)
+fn newton(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let index = pixel_index(global_invocation_id, num_workgroups);
+
+  var pixel = input[index];
+
+  for (var i = 0u; keep_iterating(pixel, i); i = i + 1u) {
+    // Like `julia`, each pixel's coordinate is its starting value.
+    if pixel.iteration_count == 0u {
+      pixel.current_value = pixel_coordinate(pixel);
+    }
+
+    pixel.current_value = newton_step(pixel.current_value);
+    pixel.iteration_count = pixel.iteration_count + 1u;
+
+    let root_index = nearest_root(pixel.current_value);
+    if root_index != NO_ROOT {
+      pixel.escaped = CONVERGED;
+      pixel.root_index = root_index;
+    }
+  }
+  output[index] = pixel;
+}
@@ -0,0 +1,95 @@
+/*
+[Perturbation](https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Perturbation_theory_and_series_approximation)
+for `mandelbrot`, past the zoom where `f32` can tell pixels apart.
+
+`reference_orbit` holds `Z_n`, the orbit of one point computed at high precision on the CPU (see
+`compute/orbit.rs`). A pixel whose coordinate is `dc` away from that point has `z_n = Z_n + d_n`,
+and only `d_n` is iterated here:
+
+  d_(n+1) = 2 Z_n d_n + d_n^2 + dc
+
+Both `d_n` and `dc` are tiny, and `f32` keeps them at full relative precision, where `z_n` itself
+would have rounded them away.
+
+When `|z_n| < |d_n|`, or the reference has run out because it escaped, the pixel's delta would
+lose precision against the reference. It rebases instead, as suggested by Zhuoran on Fractal
+Forums: `d_n` becomes `z_n` and iterates from `Z_0 = 0` again, which avoids the glitches without
+a second reference.
+
+Appended to `compute.wgsl` for `compute.rs#Precision::Perturbed`. Only iterates `z^2 + c`, and
+doesn't check for the cardioid and bulb, which `f32` can't place precisely at these zooms.
+*/
+
+@group(0) @binding(8) var<storage, read> reference_orbit : array<Complex>;
+
+// How far `uniforms.origin` has moved from the reference orbit's point since it was computed, e.g. while
+// zooming is animated.
+@group(0) @binding(9) var<uniform> reference_offset : vec2<f32>;
+
+// `dc`: how far the point that `pixel` covers is from the reference orbit's.
+fn pixel_delta(pixel: Pixel) -> Complex {
+  let x = f32(pixel.x + tile_offset.x) - f32(uniforms.screen_size.x) / 2.0;
+  let y = f32(pixel.y + tile_offset.y) - f32(uniforms.screen_size.y) / 2.0;
+  return Complex(x * pixel_size() + reference_offset.x, y * pixel_size() + reference_offset.y);
+}
+
+// `iterate` for `z^2 + c`, in terms of `pixel.delta`.
+fn iterate_perturbed(pixel: Pixel, dc: Complex) -> Pixel {
+  if pixel.iteration_count >= uniforms.max_iterations {
+    return pixel;
+  }
+
+  var result = pixel;
+  let z = add_complex(reference_orbit[pixel.reference_index], pixel.delta);
+
+  if squared_length_complex(z) >= uniforms.escape_radius * uniforms.escape_radius {
+    result.escaped = 1u;
+  }
+  result.iteration_count = pixel.iteration_count + 1u;
+
+  // `2 Z_n d_n + d_n^2 + dc`.
+  var delta = add_complex(
+    multiply_complex(
+      add_complex(scale_complex(2.0, reference_orbit[pixel.reference_index]), pixel.delta),
+      pixel.delta
+    ),
+    dc
+  );
+  var reference_index = pixel.reference_index + 1u;
+  let current_value = add_complex(reference_orbit[reference_index], delta);
+
+  // Rebase before the reference runs out, or once it's further from `z` than zero is.
+  if reference_index + 1u >= arrayLength(&reference_orbit)
+    || squared_length_complex(current_value) < squared_length_complex(delta) {
+    delta = current_value;
+    reference_index = 0u;
+  }
+
+  result.current_value = current_value;
+  result.delta = delta;
+  result.reference_index = reference_index;
+
+  result.derivative =
+    add_complex(scale_complex(2.0, multiply_complex(z, pixel.derivative)), Complex(1.0, 0.0));
+  result.distance_estimate = distance_estimate(z, pixel.derivative);
+
+  result.min_trap_distance = min(pixel.min_trap_distance, trap_distance(current_value));
+
+  return result;
+}
+
+@compute @workgroup_size(1, 64, 1)
+fn mandelbrot_perturbed(
+  @builtin(global_invocation_id) global_invocation_id: vec3<u32>,
+  @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+  let index = pixel_index(global_invocation_id, num_workgroups);
+
+  var pixel = input[index];
+  let dc = pixel_delta(pixel);
+
+  for (var i = 0u; keep_iterating(pixel, i); i = i + 1u) {
+    pixel = iterate_perturbed(pixel, dc);
+  }
+  output[index] = pixel;
+}
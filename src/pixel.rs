@@ -1,10 +1,18 @@
 //! Pixel data.
 
+use std::{
+    fmt,
+    ops::{Add, Mul, Sub},
+};
+
 use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+
+use crate::screen::{Size, Vec2, ViewRect};
 
 /// [`bytemuck`]-compatible complex numbers.
 #[repr(C)]
-#[derive(Pod, Zeroable, Clone, Copy, Debug)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Complex {
     pub real: f32,
     pub imaginary: f32,
@@ -15,15 +23,482 @@ impl Complex {
         real: 0.0,
         imaginary: 0.0,
     };
+
+    /// The squared length, which avoids the square root in [`Complex::abs`].
+    pub fn norm_sqr(self) -> f32 {
+        self.real * self.real + self.imaginary * self.imaginary
+    }
+
+    /// The length.
+    pub fn abs(self) -> f32 {
+        self.norm_sqr().sqrt()
+    }
+}
+
+/// The same arithmetic as `compute.wgsl`, for working with pixels on the CPU.
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Complex {
+            real: self.real + other.real,
+            imaginary: self.imaginary + other.imaginary,
+        }
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Complex {
+            real: self.real - other.real,
+            imaginary: self.imaginary - other.imaginary,
+        }
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Complex {
+            real: self.real * other.real - self.imaginary * other.imaginary,
+            imaginary: self.real * other.imaginary + self.imaginary * other.real,
+        }
+    }
+}
+
+impl From<ComplexF64> for Complex {
+    fn from(value: ComplexF64) -> Self {
+        Complex {
+            real: value.real as f32,
+            imaginary: value.imaginary as f32,
+        }
+    }
+}
+
+/**
+Prints `re + imi`, e.g. `-0.74529 + 0.113075i`, which
+[`location::parse_point`](crate::location::parse_point) reads back.
+*/
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_complex(
+            f,
+            self.real,
+            self.imaginary.abs(),
+            self.imaginary.is_sign_negative(),
+        )
+    }
+}
+
+/**
+Write `real + imaginary_abs i`, or `-` for a `negative` imaginary part, passing the precision on
+so that `{:.3}` rounds both parts.
+*/
+fn write_complex(
+    f: &mut fmt::Formatter<'_>,
+    real: impl fmt::Display,
+    imaginary_abs: impl fmt::Display,
+    negative: bool,
+) -> fmt::Result {
+    let sign = if negative { '-' } else { '+' };
+    match f.precision() {
+        Some(precision) => write!(
+            f,
+            "{:.*} {} {:.*}i",
+            precision, real, sign, precision, imaginary_abs
+        ),
+        None => write!(f, "{} {} {}i", real, sign, imaginary_abs),
+    }
+}
+
+/**
+The width and height of one pixel on the complex plane, in a `size` view at `zoom`.
+
+Both axes use the same scale, so the set isn't stretched on screens that aren't square. It's
+based on the shorter side, which spans `4.0 / zoom`.
+*/
+pub fn pixel_size(size: Size, zoom: f64) -> f64 {
+    4.0 / (zoom * size.width.min(size.height) as f64)
+}
+
+/// [`bytemuck`]-compatible double-precision complex numbers.
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ComplexF64 {
+    pub real: f64,
+    pub imaginary: f64,
+}
+
+impl ComplexF64 {
+    pub const ZERO: Self = ComplexF64 {
+        real: 0.0,
+        imaginary: 0.0,
+    };
+
+    /**
+    The point under `position` on the screen.
+
+    When `zoom = 1.0`, the screen's shorter side spans `origin - 2` to `origin + 2`, and the
+    longer side spans further at the same [`pixel_size`]. The centre of the screen is `origin`.
+    */
+    pub fn from_screen(position: Vec2, size: Size, origin: ComplexF64, zoom: f64) -> Self {
+        ViewRect::new(origin, zoom, size).point_at(position, size)
+    }
+
+    /// The inverse of [`ComplexF64::from_screen`]: where `self` is on the screen, in pixels.
+    pub fn to_screen(self, size: Size, origin: ComplexF64, zoom: f64) -> (f64, f64) {
+        ViewRect::new(origin, zoom, size).position_of(self, size)
+    }
+}
+
+/// Prints `re + imi`, like [`Complex`]'s `Display`.
+impl fmt::Display for ComplexF64 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_complex(
+            f,
+            self.real,
+            self.imaginary.abs(),
+            self.imaginary.is_sign_negative(),
+        )
+    }
 }
 
+/**
+`value` as `re + imi`, with each part rounded to `digits` significant digits rather than a fixed
+number of decimal places, so it stays readable near the origin and precise far from it. Deep
+zooms need 15 or more to pin down the view; `f64` has about 17.
+
+Like [`ComplexF64`]'s `Display`, it can be parsed back by
+[`location::parse_point`](crate::location::parse_point).
+*/
+pub fn format_coord(value: ComplexF64, digits: usize) -> String {
+    let decimals = |part: f64| {
+        let digits = digits.max(1) as i32;
+        if part == 0.0 || !part.is_finite() {
+            return (digits - 1) as usize;
+        }
+        (digits - 1 - part.abs().log10().floor() as i32).max(0) as usize
+    };
+    let sign = if value.imaginary.is_sign_negative() {
+        '-'
+    } else {
+        '+'
+    };
+    format!(
+        "{:.*} {} {:.*}i",
+        decimals(value.real),
+        value.real,
+        sign,
+        decimals(value.imaginary),
+        value.imaginary.abs()
+    )
+}
+
+impl From<Complex> for ComplexF64 {
+    fn from(value: Complex) -> Self {
+        ComplexF64 {
+            real: value.real as f64,
+            imaginary: value.imaginary as f64,
+        }
+    }
+}
+
+/**
+[`IteratedPixel::escaped`] for a pixel that hasn't escaped yet. Pixels that reach the maximum
+iteration count stay `ITERATING`, so they can carry on if it's raised.
+*/
+pub const ITERATING: u32 = 0;
+
+/// [`IteratedPixel::escaped`] for a pixel whose orbit has passed the escape radius.
+pub const ESCAPED: u32 = 1;
+
+/// [`IteratedPixel::escaped`] for a point that `compute.wgsl#in_cardioid_or_bulb` found inside the set.
+pub const INTERIOR: u32 = 2;
+
 /// Pixel data for rendering fractals.
 #[repr(C)]
 #[derive(Pod, Zeroable, Clone, Copy, Debug)]
 pub struct Pixel {
     pub x: u32,
     pub y: u32,
+    /// [`ITERATING`], [`ESCAPED`], or [`INTERIOR`].
     pub escaped: u32,
     pub current_value: Complex,
     pub iteration_count: u32,
+    /// The closest `current_value` has come to `compute.wgsl#trap_shape`.
+    pub min_trap_distance: f32,
+    /// The derivative of `current_value` with respect to the pixel's coordinate.
+    pub derivative: Complex,
+    /// How far the pixel is from the set, in pixels. See `compute.wgsl#distance_estimate`.
+    pub distance_estimate: f32,
+    /// Which root [`crate::compute::FractalKind::Newton`] converged to. See `newton.wgsl#nearest_root`.
+    pub root_index: u32,
+    /**
+    How far `current_value` is from the reference orbit, for
+    [`crate::compute::Precision::Perturbed`]. See `perturbation.wgsl`.
+    */
+    pub delta: Complex,
+    /// Which value of the reference orbit `delta` is relative to.
+    pub reference_index: u32,
+}
+
+/**
+Pixel data for rendering fractals at double precision.
+
+`current_value` and `derivative` come first so that the struct has no padding, which [`Pod`]
+requires.
+*/
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug)]
+pub struct PixelF64 {
+    pub current_value: ComplexF64,
+    pub derivative: ComplexF64,
+    pub x: u32,
+    pub y: u32,
+    pub escaped: u32,
+    pub iteration_count: u32,
+    pub min_trap_distance: f32,
+    pub distance_estimate: f32,
+}
+
+/// Pixel data that's iterated by a compute shader: [`Pixel`] or [`PixelF64`].
+pub trait IteratedPixel: Pod + Send + Sync {
+    /// A pixel at `(x, y)` that hasn't been iterated yet.
+    fn new(x: u32, y: u32) -> Self;
+
+    fn x(&self) -> u32;
+
+    fn y(&self) -> u32;
+
+    /**
+    [`ITERATING`], [`ESCAPED`], or [`INTERIOR`].
+
+    Every pixel starts [`ITERATING`], and the compute shader moves it to [`ESCAPED`] or
+    [`INTERIOR`] at most once. After that it's never dispatched again.
+    */
+    fn escaped(&self) -> u32;
+
+    /// Whether the pixel is still [`ITERATING`], including after reaching the maximum iteration count.
+    fn is_iterating(&self) -> bool {
+        self.escaped() == ITERATING
+    }
+
+    /// Whether the pixel has [`ESCAPED`].
+    fn is_escaped(&self) -> bool {
+        self.escaped() == ESCAPED
+    }
+
+    /// Whether the pixel was found to be [`INTERIOR`] without iterating it.
+    fn is_interior(&self) -> bool {
+        self.escaped() == INTERIOR
+    }
+
+    fn iteration_count(&self) -> u32;
+
+    /// The pixel's value after its last iteration, at single precision.
+    fn current_value(&self) -> Complex;
+
+    /// The closest the pixel's orbit has come to the orbit trap.
+    fn min_trap_distance(&self) -> f32;
+
+    /// How far the pixel is from the set, in pixels. Only meaningful once it has escaped.
+    fn distance_estimate(&self) -> f32;
+
+    /// Which root the pixel converged to, for [`crate::compute::FractalKind::Newton`].
+    fn root_index(&self) -> u32;
+}
+
+impl IteratedPixel for Pixel {
+    fn new(x: u32, y: u32) -> Self {
+        Pixel {
+            x,
+            y,
+            current_value: Complex::ZERO,
+            escaped: ITERATING,
+            iteration_count: 0,
+            min_trap_distance: f32::MAX,
+            derivative: Complex::ZERO,
+            distance_estimate: 0.0,
+            root_index: 0,
+            delta: Complex::ZERO,
+            reference_index: 0,
+        }
+    }
+
+    fn x(&self) -> u32 {
+        self.x
+    }
+
+    fn y(&self) -> u32 {
+        self.y
+    }
+
+    fn escaped(&self) -> u32 {
+        self.escaped
+    }
+
+    fn iteration_count(&self) -> u32 {
+        self.iteration_count
+    }
+
+    fn current_value(&self) -> Complex {
+        self.current_value
+    }
+
+    fn min_trap_distance(&self) -> f32 {
+        self.min_trap_distance
+    }
+
+    fn distance_estimate(&self) -> f32 {
+        self.distance_estimate
+    }
+
+    fn root_index(&self) -> u32 {
+        self.root_index
+    }
+}
+
+impl IteratedPixel for PixelF64 {
+    fn new(x: u32, y: u32) -> Self {
+        PixelF64 {
+            x,
+            y,
+            current_value: ComplexF64::ZERO,
+            escaped: ITERATING,
+            iteration_count: 0,
+            min_trap_distance: f32::MAX,
+            derivative: ComplexF64::ZERO,
+            distance_estimate: 0.0,
+        }
+    }
+
+    fn x(&self) -> u32 {
+        self.x
+    }
+
+    fn y(&self) -> u32 {
+        self.y
+    }
+
+    fn escaped(&self) -> u32 {
+        self.escaped
+    }
+
+    fn iteration_count(&self) -> u32 {
+        self.iteration_count
+    }
+
+    fn current_value(&self) -> Complex {
+        self.current_value.into()
+    }
+
+    fn min_trap_distance(&self) -> f32 {
+        self.min_trap_distance
+    }
+
+    fn distance_estimate(&self) -> f32 {
+        self.distance_estimate
+    }
+
+    /// Always `0`: [`crate::compute::FractalKind::Newton`] only runs at single precision.
+    fn root_index(&self) -> u32 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn complex(real: f32, imaginary: f32) -> Complex {
+        Complex { real, imaginary }
+    }
+
+    #[test]
+    fn add_and_sub() {
+        assert_eq!(complex(1.0, 2.0) + complex(3.0, -5.0), complex(4.0, -3.0));
+        assert_eq!(complex(1.0, 2.0) - complex(3.0, -5.0), complex(-2.0, 7.0));
+        assert_eq!(complex(1.5, -0.5) - complex(1.5, -0.5), Complex::ZERO);
+    }
+
+    #[test]
+    fn mul() {
+        let i = complex(0.0, 1.0);
+        assert_eq!(i * i, complex(-1.0, 0.0));
+        assert_eq!(complex(1.0, 2.0) * complex(3.0, 4.0), complex(-5.0, 10.0));
+        assert_eq!(complex(2.0, -3.0) * Complex::ZERO, Complex::ZERO);
+    }
+
+    #[test]
+    fn norm_sqr_and_abs() {
+        assert_eq!(complex(3.0, -4.0).norm_sqr(), 25.0);
+        assert_eq!(complex(3.0, -4.0).abs(), 5.0);
+        assert_eq!(Complex::ZERO.norm_sqr(), 0.0);
+    }
+
+    #[test]
+    fn screen_round_trip() {
+        let size = Size {
+            width: 800,
+            height: 600,
+        };
+        let origin = ComplexF64 {
+            real: -0.74529,
+            imaginary: 0.113075,
+        };
+        let zoom = 37.5;
+        let positions = [
+            (0.0, 0.0),
+            (800.0, 0.0),
+            (0.0, 600.0),
+            (800.0, 600.0),
+            (400.0, 300.0),
+        ];
+        for (x, y) in positions {
+            let point = ComplexF64::from_screen(Vec2 { x, y }, size, origin, zoom);
+            let (round_x, round_y) = point.to_screen(size, origin, zoom);
+            assert!(
+                (round_x - x as f64).abs() < 1e-6 && (round_y - y as f64).abs() < 1e-6,
+                "({}, {}) came back as ({}, {})",
+                x,
+                y,
+                round_x,
+                round_y
+            );
+        }
+
+        let centre = ComplexF64::from_screen(Vec2 { x: 400.0, y: 300.0 }, size, origin, zoom);
+        assert!((centre.real - origin.real).abs() < 1e-12);
+        assert!((centre.imaginary - origin.imaginary).abs() < 1e-12);
+    }
+
+    /// A circle stays circular at 16:9: a step of one pixel covers as much of the plane either way.
+    #[test]
+    fn square_pixels_at_16_by_9() {
+        let size = Size {
+            width: 1920,
+            height: 1080,
+        };
+        let origin = ComplexF64 {
+            real: 0.25,
+            imaginary: -0.5,
+        };
+        let zoom = 3.0;
+        let at = |x, y| ComplexF64::from_screen(Vec2 { x, y }, size, origin, zoom);
+
+        let start = at(960.0, 540.0);
+        let step_x = at(961.0, 540.0).real - start.real;
+        let step_y = at(960.0, 541.0).imaginary - start.imaginary;
+        assert!((step_x - step_y).abs() < 1e-12, "{} != {}", step_x, step_y);
+        assert!((step_x - pixel_size(size, zoom)).abs() < 1e-12);
+
+        // The shorter side spans `4.0 / zoom`, and the longer side proportionally more.
+        let corner = at(0.0, 0.0);
+        let far_corner = at(1920.0, 1080.0);
+        assert!((far_corner.imaginary - corner.imaginary - 4.0 / zoom).abs() < 1e-12);
+        assert!((far_corner.real - corner.real - 4.0 / zoom * 1920.0 / 1080.0).abs() < 1e-12);
+    }
 }
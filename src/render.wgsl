@@ -10,38 +10,242 @@ fn vertex_main(@builtin(vertex_index) index : u32) -> @builtin(position) vec4<f3
   return vec4<f32>(vertices[index], 0.0, 1.0);
 }
 
-struct ColourRange{escaped : u32, value : f32}
+struct ColourRange{escaped : u32, position : f32}
 
-@group(0) @binding(0) var<uniform> screen_size : vec2<u32>;
+// See `colour.rs#PaletteUniform`. The array's length is `colour.rs#PALETTE_STOPS`.
+struct Palette{colours : array<vec4<f32>, 256>}
+
+// The same as `compute.wgsl#Uniforms`, bound to the same buffer. `origin` and `zoom` are unused.
+struct Uniforms{
+  screen_size : vec2<u32>,
+  origin : vec2<f32>,
+  zoom : f32,
+  escape_radius : f32,
+  max_iterations : u32,
+  power : u32,
+}
+
+@group(0) @binding(0) var<uniform> uniforms : Uniforms;
+@group(0) @binding(1) var<uniform> palette : Palette;
+
+// Each fragment averages an `ssaa` by `ssaa` block of `colour_ranges`, which is
+// `uniforms.screen_size` pixels.
+@group(0) @binding(2) var<uniform> ssaa : u32;
+
+// Shifts where every pixel samples the palette, for animating it. From 0.0 to 1.0, wrapping around.
+@group(0) @binding(4) var<uniform> palette_offset : f32;
+
+// See `colour.rs#PostProcessing`.
+struct PostProcessing{gamma : f32, brightness : f32, contrast : f32}
+
+@group(0) @binding(3) var<uniform> post_processing : PostProcessing;
+
+// See `grid.rs#GridUniform`. In screen pixels, like `@builtin(position)`.
+struct Grid{offset : vec2<f32>, spacing : f32, enabled : u32}
+
+@group(0) @binding(5) var<uniform> grid : Grid;
+
+// `1u` to dither the output with `dither_colour`, `0u` not to.
+@group(0) @binding(6) var<uniform> dither : u32;
+
+// See `screen.rs#SelectionUniform`. In screen pixels, like `@builtin(position)`.
+struct Selection{min : vec2<f32>, max : vec2<f32>}
+
+@group(0) @binding(7) var<uniform> selection : Selection;
 
 @group(1) @binding(0) var<storage, read> colour_ranges : array<ColourRange>;
+
+// See `compute.wgsl#Pixel`.
+struct Complex{real : f32, imaginary : f32}
+struct Pixel{
+  x : u32,
+  y : u32,
+  escaped : u32,
+  current_value : Complex,
+  iteration_count : u32,
+  min_trap_distance : f32,
+  derivative : Complex,
+  distance_estimate : f32,
+  root_index : u32,
+  delta : Complex,
+  reference_index : u32,
+}
+
+// Every pixel in screen order, straight from the compute pass. Only read by `fragment_gpu_smooth`.
+@group(1) @binding(1) var<storage, read> pixels : array<Pixel>;
   
 let GAMMA = vec3<f32>(2.2, 2.2, 2.2);
-let EXPONENT = vec3<f32>(2.0, 2.0, 2.0);
+let EXPONENT = 2.0;
+
+let PALETTE_STOPS = 256u;
+
+// Sample the palette at `position` (from 0.0 to 1.0), interpolating between the two nearest stops.
+fn sample_palette(position : f32) -> vec3<f32> {
+  let scaled = clamp(position, 0.0, 1.0) * f32(PALETTE_STOPS - 1u);
+  let lower = min(u32(floor(scaled)), PALETTE_STOPS - 2u);
+  let fraction = scaled - f32(lower);
+
+  return pow(mix(palette.colours[lower].rgb, palette.colours[lower + 1u].rgb, vec3<f32>(fraction)), GAMMA);
+}
+
+/*
+Shift `position` by `palette_offset`, running back down the palette after reaching the end so there's
+no seam where it wraps. Moving `palette_offset` from 0.0 to 1.0 goes up and back down once, and 0.0
+leaves `position` as it is.
+*/
+fn cycle_position(position : f32) -> f32 {
+  return 1.0 - abs(fract((position + 2.0 * palette_offset) / 2.0) * 2.0 - 1.0);
+}
+
+// The "jet" scale from dark blue at 0.0 through cyan, green and yellow to dark red at 1.0, for
+// `colour.rs#HeatmapColouring`.
+fn heatmap_colour(position : f32) -> vec3<f32> {
+  let t = 4.0 * clamp(position, 0.0, 1.0);
+  let rgb = vec3<f32>(1.5, 1.5, 1.5) - abs(vec3<f32>(t - 3.0, t - 2.0, t - 1.0));
+  return pow(clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0)), GAMMA);
+}
 
 fn compute_colour(colour_range : ColourRange) -> vec4<f32> {
   let unescaped = pow(vec3<f32>(0.0, 0.0, 0.0), GAMMA);
-  let initial_colour = pow(vec3<f32>(15.0 / 255.0, 66.0 / 255.0, 7.0 / 255.0), GAMMA);
-  let final_colour = pow(vec3<f32>(1.0, 1.0, 1.0), GAMMA);
-  
-  let scale = vec3<f32>(colour_range.value, colour_range.value, colour_range.value);
   
   if colour_range.escaped == 1u {
-    return vec4<f32>(
-      initial_colour + (final_colour - initial_colour) * pow(scale, EXPONENT),
-      1.0
-    );
+    return vec4<f32>(sample_palette(cycle_position(pow(colour_range.position, EXPONENT))), 1.0);
+  } else if colour_range.escaped == 2u {
+    // Interior pixels shaded by `colour.rs#InteriorColouring`.
+    return vec4<f32>(sample_palette(cycle_position(colour_range.position)), 1.0);
+  } else if colour_range.escaped == 3u {
+    // `colour.rs#HEATMAP`.
+    return vec4<f32>(heatmap_colour(colour_range.position), 1.0);
   } else {
     return vec4<f32>(unescaped, 1.0);
   }
 }
 
+/*
+Colour `pixel` by its continuous escape count, like `colour.rs#smooth_fraction`. The count is scaled
+logarithmically so that a range of views look alright without a histogram.
+*/
+fn smooth_colour_range(pixel : Pixel) -> ColourRange {
+  if pixel.escaped != 1u {
+    return ColourRange(0u, 0.0);
+  }
+
+  let z = vec2<f32>(pixel.current_value.real, pixel.current_value.imaginary);
+  let radius = max(length(z), uniforms.escape_radius);
+  let nu = log2(log2(radius) / log2(uniforms.escape_radius)) / log2(f32(uniforms.power));
+  let count = f32(pixel.iteration_count) + clamp(1.0 - nu, 0.0, 1.0);
+  return ColourRange(1u, clamp(log(count + 1.0) / log(f32(uniforms.max_iterations) + 1.0), 0.0, 1.0));
+}
+
+// Adjust a linear `colour` by `post_processing`, in sRGB space like the palette's colours.
+fn post_process(colour : vec3<f32>) -> vec3<f32> {
+  let srgb = pow(colour, vec3<f32>(1.0, 1.0, 1.0) / GAMMA);
+  let adjusted = (srgb - 0.5) * post_processing.contrast + 0.5 + post_processing.brightness;
+  let corrected = pow(clamp(adjusted, vec3<f32>(0.0, 0.0, 0.0), vec3<f32>(1.0, 1.0, 1.0)), vec3<f32>(1.0 / post_processing.gamma));
+  return pow(corrected, GAMMA);
+}
+
+let GRID_OPACITY = 0.2;
+let SCALE_BAR_MARGIN = 16.0;
+let SCALE_BAR_THICKNESS = 4.0;
+
+/*
+Draw faint gridlines every `grid.spacing` pixels over `colour`, and a scale bar one grid spacing
+long in the bottom left corner. `grid.rs#spacing` says how long that is on the complex plane.
+*/
+fn overlay_grid(position : vec2<f32>, colour : vec3<f32>) -> vec3<f32> {
+  if grid.enabled == 0u {
+    return colour;
+  }
+
+  let white = vec3<f32>(1.0, 1.0, 1.0);
+  let bar_bottom = f32(uniforms.screen_size.y / ssaa) - SCALE_BAR_MARGIN;
+  if position.x >= SCALE_BAR_MARGIN && position.x < SCALE_BAR_MARGIN + grid.spacing
+    && position.y >= bar_bottom - SCALE_BAR_THICKNESS && position.y < bar_bottom {
+    return white;
+  }
+
+  // Distance to the nearest gridline along each axis.
+  let phase = (position - grid.offset) / grid.spacing;
+  let distance = abs(fract(phase + 0.5) - 0.5) * grid.spacing;
+  if min(distance.x, distance.y) < 0.5 {
+    return mix(colour, white, GRID_OPACITY);
+  }
+  return colour;
+}
+
+let SELECTION_OPACITY = 0.15;
+
+// Outline the `selection` rectangle and lighten what's inside it, if there is one.
+fn overlay_selection(position : vec2<f32>, colour : vec3<f32>) -> vec3<f32> {
+  if any(selection.max <= selection.min)
+    || any(position < selection.min) || any(position >= selection.max) {
+    return colour;
+  }
+
+  let white = vec3<f32>(1.0, 1.0, 1.0);
+  let inset = min(position - selection.min, selection.max - position);
+  if min(inset.x, inset.y) < 1.0 {
+    return white;
+  }
+  return mix(colour, white, SELECTION_OPACITY);
+}
+
+/*
+Nudge `colour` up or down by less than one 8-bit step, following a 4×4 Bayer matrix across the
+screen. Gradients too shallow for the surface's 8 bits per channel then come out as a fine pattern
+instead of bands. The pattern is fixed to the pixel grid, so it doesn't shimmer between frames.
+*/
+fn dither_colour(position : vec2<f32>, colour : vec3<f32>) -> vec3<f32> {
+  if dither == 0u {
+    return colour;
+  }
+
+  var bayer = array<f32, 16>(
+    0.0, 8.0, 2.0, 10.0,
+    12.0, 4.0, 14.0, 6.0,
+    3.0, 11.0, 1.0, 9.0,
+    15.0, 7.0, 13.0, 5.0
+  );
+  let threshold = (bayer[(u32(position.y) % 4u) * 4u + u32(position.x) % 4u] + 0.5) / 16.0 - 0.5;
+
+  // The surface quantizes in sRGB space, so that's where the steps are even.
+  let srgb = pow(colour, vec3<f32>(1.0, 1.0, 1.0) / GAMMA) + threshold / 255.0;
+  return pow(max(srgb, vec3<f32>(0.0, 0.0, 0.0)), GAMMA);
+}
+
 // builtins are documented here: https://www.w3.org/TR/WGSL/#builtin-values
 @fragment
 fn fragment_main(@builtin(position) position : vec4<f32>) -> @location(0) vec4<f32> {
   // TODO: why is position not coming throught as NDC?
-  let x = u32(position.x);
-  let y = u32(position.y);
+  let x = u32(position.x) * ssaa;
+  let y = u32(position.y) * ssaa;
 
-  return compute_colour(colour_ranges[y * screen_size.x + x]);
-}
\ No newline at end of file
+  // Average after `compute_colour`'s gamma, so edges blend in linear space.
+  var colour = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+  for (var j = 0u; j < ssaa; j = j + 1u) {
+    for (var i = 0u; i < ssaa; i = i + 1u) {
+      colour = colour + compute_colour(colour_ranges[(y + j) * uniforms.screen_size.x + x + i]);
+    }
+  }
+  colour = colour / f32(ssaa * ssaa);
+  let overlaid = overlay_selection(position.xy, overlay_grid(position.xy, post_process(colour.rgb)));
+  return vec4<f32>(dither_colour(position.xy, overlaid), colour.a);
+}
+
+// `fragment_main` for `colour.rs#ColouringMode::GpuSmooth`, colouring `pixels` instead of reading `colour_ranges`.
+@fragment
+fn fragment_gpu_smooth(@builtin(position) position : vec4<f32>) -> @location(0) vec4<f32> {
+  let x = u32(position.x) * ssaa;
+  let y = u32(position.y) * ssaa;
+
+  var colour = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+  for (var j = 0u; j < ssaa; j = j + 1u) {
+    for (var i = 0u; i < ssaa; i = i + 1u) {
+      colour = colour + compute_colour(smooth_colour_range(pixels[(y + j) * uniforms.screen_size.x + x + i]));
+    }
+  }
+  colour = colour / f32(ssaa * ssaa);
+  let overlaid = overlay_selection(position.xy, overlay_grid(position.xy, post_process(colour.rgb)));
+  return vec4<f32>(dither_colour(position.xy, overlaid), colour.a);
+}
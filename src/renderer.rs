@@ -0,0 +1,3905 @@
+//! GPU state for rendering fractals.
+
+use std::{
+    fmt,
+    mem::size_of,
+    ops::Range,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use instant::Instant;
+use log::{debug, error, info, trace, warn};
+
+use crate::{
+    colour::{
+        self, ColourRange, DistanceColouring, EscapeTimeColouring, HeatmapColouring,
+        HistogramColouring, OrbitTrapColouring, PaletteUniform, PostProcessing, RootColouring,
+    },
+    command_buffer,
+    command_encoder::{CommandEncoderExt, PassTimestamps},
+    compute::{
+        self,
+        orbit::{self, ReferenceOrbit},
+    },
+    grid::{self, GridUniform},
+    pixel::{
+        self, Complex, ComplexF64, IteratedPixel, Pixel, PixelF64, ESCAPED, INTERIOR, ITERATING,
+    },
+    screen::{self, SelectionUniform},
+    shader, typed_buffer,
+};
+
+/// The first of the compute pass's timestamps in [`Timestamps::query_set`].
+const COMPUTE_TIMESTAMPS: u32 = 0;
+/// The first of the render pass's timestamps in [`Timestamps::query_set`].
+const RENDER_TIMESTAMPS: u32 = 2;
+const TIMESTAMP_COUNT: u32 = 4;
+
+/// See [`HistogramColouring::with_min_sample_fraction`].
+const HISTOGRAM_MIN_SAMPLE_FRACTION: f32 = 0.01;
+
+/// See [`HistogramColouring::with_temporal_smoothing`].
+const HISTOGRAM_TEMPORAL_SMOOTHING: f32 = 0.5;
+
+/// How close, in pixels, [`Renderer::drift`] has to get to the target to stop.
+pub const DRIFT_SETTLED_PIXELS: f64 = 0.5;
+
+/// The texture format used by [`Renderer::new_headless`].
+pub const HEADLESS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+pub fn create_pixels<P: IteratedPixel>(size: screen::Size) -> Vec<P> {
+    (0..size.height)
+        .flat_map(move |y| (0..size.width).map(move |x| P::new(x, y)))
+        .collect::<Vec<_>>()
+}
+
+/**
+Both halves of the pixels buffer, holding [`create_pixels`].
+
+The pixels are written straight into the buffers while they're mapped at creation, without
+collecting them into a `Vec` or staging them first.
+*/
+pub fn create_pixels_buffers<P: IteratedPixel>(
+    device: &wgpu::Device,
+    size: screen::Size,
+) -> typed_buffer::DoubleBuffer<P> {
+    let create = |label| {
+        let buffer = typed_buffer::Builder::new(size.width as u64 * size.height as u64)
+            .with_label(label)
+            .with_usage(wgpu::BufferUsages::STORAGE)
+            .with_usage(wgpu::BufferUsages::COPY_SRC)
+            .with_mapped_at_creation(true)
+            .create(device);
+        for (index, pixel) in buffer
+            .slice(..)
+            .get_mapped_range_mut()
+            .iter_mut()
+            .enumerate()
+        {
+            let index = index as u32;
+            *pixel = P::new(index % size.width, index / size.width);
+        }
+        buffer.unmap();
+        buffer
+    };
+
+    typed_buffer::DoubleBuffer::new(create("pixels_buffer_1"), create("pixels_buffer_2"))
+}
+
+/// Why a [`Renderer`] couldn't be created or reconfigured.
+#[derive(Debug)]
+pub enum RendererError {
+    /**
+    A shader didn't compile, or the compute shader has no entry point for the fractal kind. Holds
+    wgpu's message, which says where.
+    */
+    ShaderCompile(String),
+    /// A shader couldn't be read from [`Renderer::shader_dir`].
+    ShaderRead(shader::ShaderReadError),
+    /// The adapter couldn't provide a device, e.g. because it was lost as well.
+    RequestDevice(wgpu::RequestDeviceError),
+    /// There's no GPU adapter to create a device on, e.g. on a server without a usable GPU.
+    NoAdapter,
+}
+
+impl fmt::Display for RendererError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RendererError::ShaderCompile(message) => {
+                write!(f, "couldn't compile the shader: {}", message)
+            }
+            RendererError::ShaderRead(error) => write!(f, "{}", error),
+            RendererError::RequestDevice(error) => {
+                write!(f, "couldn't get a device from the adapter: {}", error)
+            }
+            RendererError::NoAdapter => write!(f, "no GPU adapter found"),
+        }
+    }
+}
+
+impl std::error::Error for RendererError {}
+
+impl From<shader::ShaderReadError> for RendererError {
+    fn from(error: shader::ShaderReadError) -> Self {
+        RendererError::ShaderRead(error)
+    }
+}
+
+impl From<wgpu::RequestDeviceError> for RendererError {
+    fn from(error: wgpu::RequestDeviceError) -> Self {
+        RendererError::RequestDevice(error)
+    }
+}
+
+/**
+Run `create` in a validation error scope, so that a broken shader or a missing entry point comes
+back as [`RendererError::ShaderCompile`], instead of panicking in wgpu's default error handler.
+
+The web can't wait for the scope's result, so there the error is logged when it arrives, and this
+returns `Ok`.
+*/
+fn validate<T>(device: &wgpu::Device, create: impl FnOnce() -> T) -> Result<T, RendererError> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let value = create();
+    let error = device.pop_error_scope();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(error) = pollster::block_on(error) {
+        return Err(RendererError::ShaderCompile(error.to_string()));
+    }
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Some(error) = error.await {
+            error!("{}", RendererError::ShaderCompile(error.to_string()));
+        }
+    });
+
+    Ok(value)
+}
+
+pub fn create_compute_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    module: &wgpu::ShaderModule,
+    precision: compute::Precision,
+    fractal_kind: compute::FractalKind,
+    samples_per_pixel: u32,
+) -> wgpu::ComputePipeline {
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("compute-pipeline"),
+        layout: Some(layout),
+        module,
+        entry_point: precision.entry_point(fractal_kind, samples_per_pixel),
+    })
+}
+
+/**
+`compute_f64.wgsl` reads its `f64` `zoom` and `origin` from `zoom_f64_buffer` and
+`origin_f64_buffer`. The other shaders read the `f32` ones in `uniforms_buffer` and leave those
+bindings unused.
+*/
+#[allow(clippy::too_many_arguments)]
+fn create_compute_bind_group_1(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    uniforms_buffer: &typed_buffer::var::Var<compute::Uniforms>,
+    zoom_f64_buffer: &typed_buffer::var::Var<f64>,
+    origin_f64_buffer: &typed_buffer::var::Var<ComplexF64>,
+    julia_c_buffer: &typed_buffer::var::Var<Complex>,
+    tile_offset_buffer: &typed_buffer::var::Var<[u32; 2]>,
+    trap_shape_buffer: &typed_buffer::var::Var<u32>,
+    iterations_per_frame_buffer: &typed_buffer::var::Var<u32>,
+    interior_colouring_buffer: &typed_buffer::var::Var<u32>,
+    reference_orbit_buffer: &typed_buffer::Buffer<Complex>,
+    reference_offset_buffer: &typed_buffer::var::Var<Complex>,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("compute-bind-group-1"),
+        layout,
+        entries: &[
+            // compute.wgsl#uniforms
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniforms_buffer.binding_resource(),
+            },
+            // compute_f64.wgsl#zoom
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: zoom_f64_buffer.binding_resource(),
+            },
+            // compute_f64.wgsl#origin
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: origin_f64_buffer.binding_resource(),
+            },
+            // compute.wgsl#julia_c
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: julia_c_buffer.binding_resource(),
+            },
+            // compute.wgsl#tile_offset
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: tile_offset_buffer.binding_resource(),
+            },
+            // compute.wgsl#trap_shape
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: trap_shape_buffer.binding_resource(),
+            },
+            // compute.wgsl#iterations_per_frame
+            wgpu::BindGroupEntry {
+                binding: 6,
+                resource: iterations_per_frame_buffer.binding_resource(),
+            },
+            // compute.wgsl#interior_colouring
+            wgpu::BindGroupEntry {
+                binding: 7,
+                resource: interior_colouring_buffer.binding_resource(),
+            },
+            // perturbation.wgsl#reference_orbit
+            wgpu::BindGroupEntry {
+                binding: 8,
+                resource: reference_orbit_buffer.binding_resource(0, None),
+            },
+            // perturbation.wgsl#reference_offset
+            wgpu::BindGroupEntry {
+                binding: 9,
+                resource: reference_offset_buffer.binding_resource(),
+            },
+        ],
+    })
+}
+
+/**
+A pipeline drawing a full-screen quad with `render.wgsl#vertex_main` and `fragment_entry_point`,
+to targets with `sample_count` samples per pixel.
+*/
+pub fn create_render_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    module: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    fragment_entry_point: &str,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("render-pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module,
+            entry_point: "vertex_main",
+            buffers: &[],
+        },
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleStrip,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..wgpu::MultisampleState::default()
+        },
+        fragment: Some(wgpu::FragmentState {
+            module,
+            entry_point: fragment_entry_point,
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+    })
+}
+
+/// The multisampled texture that [`Renderer::render`] draws to and resolves from. See [`Renderer::set_msaa`].
+fn create_msaa_texture_view(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    size: screen::Size,
+    sample_count: u32,
+) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("msaa-texture"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+pub fn create_render_bind_group_1(
+    device: &wgpu::Device,
+    render_pipeline: &wgpu::RenderPipeline,
+    uniforms_buffer: &typed_buffer::var::Var<compute::Uniforms>,
+    palette_buffer: &typed_buffer::var::Var<PaletteUniform>,
+    ssaa_buffer: &typed_buffer::var::Var<u32>,
+    post_processing_buffer: &typed_buffer::var::Var<PostProcessing>,
+    palette_offset_buffer: &typed_buffer::var::Var<f32>,
+    grid_buffer: &typed_buffer::var::Var<GridUniform>,
+    dither_buffer: &typed_buffer::var::Var<u32>,
+    selection_buffer: &typed_buffer::var::Var<SelectionUniform>,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("render-bind-group"),
+        layout: &render_pipeline.get_bind_group_layout(0),
+        entries: &[
+            // render.wgsl#uniforms
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniforms_buffer.binding_resource(),
+            },
+            // render.wgsl#palette
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: palette_buffer.binding_resource(),
+            },
+            // render.wgsl#ssaa
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: ssaa_buffer.binding_resource(),
+            },
+            // render.wgsl#post_processing
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: post_processing_buffer.binding_resource(),
+            },
+            // render.wgsl#palette_offset
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: palette_offset_buffer.binding_resource(),
+            },
+            // render.wgsl#grid
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: grid_buffer.binding_resource(),
+            },
+            // render.wgsl#dither
+            wgpu::BindGroupEntry {
+                binding: 6,
+                resource: dither_buffer.binding_resource(),
+            },
+            // render.wgsl#selection
+            wgpu::BindGroupEntry {
+                binding: 7,
+                resource: selection_buffer.binding_resource(),
+            },
+        ],
+    })
+}
+
+pub fn create_colour_ranges_buffer(
+    device: &wgpu::Device,
+    size: screen::Size,
+) -> typed_buffer::Buffer<ColourRange> {
+    typed_buffer::Builder::from(
+        std::iter::repeat(ColourRange::default())
+            .take((size.width * size.height) as usize)
+            .collect::<Vec<_>>()
+            .as_slice(),
+    )
+    .with_usage(wgpu::BufferUsages::STORAGE)
+    .create(device)
+}
+
+pub fn create_pixels_staging_buffer<P: IteratedPixel>(
+    device: &wgpu::Device,
+    size: screen::Size,
+) -> typed_buffer::Buffer<P> {
+    typed_buffer::Builder::new(size.width as u64 * size.height as u64)
+        .with_label("pixels_staging_buffer")
+        .with_usage(wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ)
+        .create(device)
+}
+
+fn create_compute_shader_module(device: &wgpu::Device, source: String) -> wgpu::ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("compute-shader"),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    })
+}
+
+fn create_render_shader_module(device: &wgpu::Device, source: &str) -> wgpu::ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("render-shader"),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    })
+}
+
+/// The pixels being iterated, on the CPU and the GPU.
+pub struct Pixels<P> {
+    pub staging_buffer: typed_buffer::Buffer<P>,
+    pub buffers: typed_buffer::DoubleBuffer<P>,
+    pub all: Vec<P>,
+    pub unescaped: Vec<P>,
+    pub newly_escaped: Vec<P>,
+    /// Pixels that reached the maximum iteration count in the last compute pass. Like escaped
+    /// pixels, they're moved to `all` and never dispatched again.
+    pub newly_interior: Vec<P>,
+    /// Set once the staging buffer of the last submitted compute pass is mapped. See [`Pixels::compute`].
+    pending: Option<Arc<AtomicBool>>,
+}
+
+impl<P: IteratedPixel> Pixels<P> {
+    pub fn new(device: &wgpu::Device, size: screen::Size) -> Self {
+        Self {
+            staging_buffer: create_pixels_staging_buffer(device, size),
+            buffers: create_pixels_buffers(device, size),
+            all: create_pixels(size),
+            unescaped: create_pixels(size),
+            newly_escaped: Vec::new(),
+            newly_interior: Vec::new(),
+            pending: None,
+        }
+    }
+
+    /// Discard all iterations.
+    pub fn reset(&mut self, queue: &wgpu::Queue, size: screen::Size) {
+        self.cancel_pending();
+        let pixels = create_pixels(size);
+        self.buffers.write(queue, &pixels);
+        self.reset_vecs(pixels);
+    }
+
+    /// Start `all` and `unescaped` over from `pixels`, which the buffers already hold.
+    fn reset_vecs(&mut self, pixels: Vec<P>) {
+        self.newly_escaped.clear();
+        self.newly_interior.clear();
+        self.all = pixels.clone();
+        self.unescaped = pixels;
+    }
+
+    /**
+    Resize the buffers for a new screen size, discarding all iterations.
+
+    The pixels buffers are recreated with [`create_pixels_buffers`] rather than resized and then
+    written, which saves staging two full-screen copies of the pixels.
+    */
+    pub fn resize(&mut self, device: &wgpu::Device, size: screen::Size) {
+        let len = size.width as u64 * size.height as u64;
+        self.cancel_pending();
+        self.staging_buffer.resize(device, len, None);
+        std::mem::replace(&mut self.buffers, create_pixels_buffers(device, size)).destroy();
+        self.reset_vecs(create_pixels(size));
+    }
+
+    /// Whether every unescaped pixel has reached `max_iterations`, and no results are still to be read.
+    pub fn is_finished(&self, max_iterations: u32) -> bool {
+        self.pending.is_none()
+            && self
+                .unescaped
+                .iter()
+                .all(|pixel| pixel.iteration_count() >= max_iterations)
+    }
+
+    /// Dispatch the pixels that reached the maximum iteration count again, after raising it.
+    pub fn resume_interior(&mut self) {
+        self.cancel_pending();
+        // Pixels that are still unescaped are left in `all` as they started, with no iterations.
+        let interior = self
+            .all
+            .iter()
+            .filter(|pixel| pixel.is_iterating() && pixel.iteration_count() > 0);
+        self.unescaped.extend(interior);
+    }
+
+    /// Stop waiting for the staging buffer, so the last compute pass's results are never read.
+    fn cancel_pending(&mut self) {
+        if self.pending.take().is_some() {
+            self.staging_buffer.unmap();
+        }
+    }
+
+    /**
+    Run one compute pass over the unescaped pixels, and sort out the newly escaped ones.
+
+    Native code blocks until the results have been copied back. On the web, where that isn't
+    possible, the browser maps the staging buffer in the background. Until it has, this returns
+    without any newly escaped pixels, and the next pass isn't submitted.
+    */
+    #[allow(clippy::too_many_arguments)]
+    fn compute(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        compute_pipeline: &wgpu::ComputePipeline,
+        compute_bind_group_1: &wgpu::BindGroup,
+        compute_bind_group_layout_2: &wgpu::BindGroupLayout,
+        workgroup_size: u32,
+        screen_size: screen::Size,
+        max_iterations: u32,
+        timestamps: Option<PassTimestamps>,
+    ) {
+        self.newly_escaped.clear();
+        self.newly_interior.clear();
+
+        if self.pending.is_none() {
+            self.submit(
+                device,
+                queue,
+                compute_pipeline,
+                compute_bind_group_1,
+                compute_bind_group_layout_2,
+                workgroup_size,
+                screen_size,
+                timestamps,
+            );
+        }
+
+        trace!("waiting for staging buffer");
+        device.poll(if cfg!(target_arch = "wasm32") {
+            wgpu::Maintain::Poll
+        } else {
+            wgpu::Maintain::Wait
+        });
+
+        let mapped = self
+            .pending
+            .as_ref()
+            .map_or(false, |mapped| mapped.load(Ordering::Acquire));
+        if mapped {
+            self.pending = None;
+            self.receive(screen_size, max_iterations);
+        }
+    }
+
+    /// Dispatch the compute pass and copy its results to the staging buffer, which is then mapped.
+    #[allow(clippy::too_many_arguments)]
+    fn submit(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        compute_pipeline: &wgpu::ComputePipeline,
+        compute_bind_group_1: &wgpu::BindGroup,
+        compute_bind_group_layout_2: &wgpu::BindGroupLayout,
+        workgroup_size: u32,
+        screen_size: screen::Size,
+        timestamps: Option<PassTimestamps>,
+    ) {
+        debug_assert!(
+            self.unescaped.len() <= screen_size.width as usize * screen_size.height as usize
+        );
+        if cfg!(debug_assertions) {
+            for pixel in self.unescaped.iter() {
+                debug_assert!(pixel.is_iterating());
+            }
+        }
+
+        let compute_command_buffer = command_buffer::create(
+            device,
+            &wgpu::CommandEncoderDescriptor::default(),
+            |command_encoder| {
+                self.buffers.current().write(queue, &self.unescaped);
+
+                self.encode_compute_pass(
+                    command_encoder,
+                    device,
+                    compute_pipeline,
+                    compute_bind_group_1,
+                    compute_bind_group_layout_2,
+                    workgroup_size,
+                    self.unescaped.len(),
+                    timestamps,
+                );
+
+                typed_buffer::copy_buffer_to_buffer(
+                    command_encoder,
+                    self.buffers.next(),
+                    0,
+                    &self.staging_buffer,
+                    0,
+                    self.unescaped.len() as u64,
+                )
+                .expect("the unescaped pixels fit in both buffers, which are screen-sized");
+            },
+        );
+
+        queue.submit([compute_command_buffer]);
+
+        let mapped = Arc::new(AtomicBool::new(false));
+        {
+            let mapped = mapped.clone();
+            self.staging_buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |map_result| match map_result {
+                    Ok(()) => mapped.store(true, Ordering::Release),
+                    // Also reached when `Pixels::reset` cancels the mapping.
+                    Err(err) => debug!("staging buffer not mapped: {}", err),
+                });
+        }
+        self.pending = Some(mapped);
+    }
+
+    /**
+    Run one compute pass over every pixel, leaving them on the GPU in screen order for
+    [`colour::ColouringMode::GpuSmooth`]. Nothing is copied back, so [`Pixels::unescaped`] and
+    [`Pixels::all`] don't change.
+
+    The buffers must hold every pixel, as they do after [`Pixels::reset`]. Pixels that have
+    finished are passed through unchanged.
+    */
+    #[allow(clippy::too_many_arguments)]
+    fn iterate_on_gpu(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        compute_pipeline: &wgpu::ComputePipeline,
+        compute_bind_group_1: &wgpu::BindGroup,
+        compute_bind_group_layout_2: &wgpu::BindGroupLayout,
+        workgroup_size: u32,
+        timestamps: Option<PassTimestamps>,
+    ) {
+        debug_assert!(self.pending.is_none());
+
+        let compute_command_buffer = command_buffer::create(
+            device,
+            &wgpu::CommandEncoderDescriptor::default(),
+            |command_encoder| {
+                self.encode_compute_pass(
+                    command_encoder,
+                    device,
+                    compute_pipeline,
+                    compute_bind_group_1,
+                    compute_bind_group_layout_2,
+                    workgroup_size,
+                    self.all.len(),
+                    timestamps,
+                );
+            },
+        );
+        queue.submit([compute_command_buffer]);
+
+        self.buffers.swap();
+    }
+
+    /**
+    Copy every pixel back after [`Pixels::iterate_on_gpu`], blocking until they've arrived, and
+    sort them like [`Pixels::receive`]. Since [`Pixels::reset`], every pixel counts as unescaped,
+    so all of them are read.
+    */
+    #[cfg(not(target_arch = "wasm32"))]
+    fn receive_all(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        screen_size: screen::Size,
+        max_iterations: u32,
+    ) {
+        debug_assert!(self.pending.is_none());
+        debug_assert_eq!(self.unescaped.len(), self.all.len());
+
+        let copy_command_buffer = command_buffer::create(
+            device,
+            &wgpu::CommandEncoderDescriptor::default(),
+            |command_encoder| {
+                typed_buffer::copy_buffer_to_buffer(
+                    command_encoder,
+                    self.buffers.current(),
+                    0,
+                    &self.staging_buffer,
+                    0,
+                    self.all.len() as u64,
+                )
+                .expect("the staging buffer holds every pixel");
+            },
+        );
+        queue.submit([copy_command_buffer]);
+
+        // `receive` reads the mapped range itself.
+        drop(self.staging_buffer.map_read_blocking(device));
+        self.receive(screen_size, max_iterations);
+    }
+
+    /// Record a compute pass over the first `total_work` pixels of the input buffer.
+    #[allow(clippy::too_many_arguments)]
+    fn encode_compute_pass(
+        &self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        compute_pipeline: &wgpu::ComputePipeline,
+        compute_bind_group_1: &wgpu::BindGroup,
+        compute_bind_group_layout_2: &wgpu::BindGroupLayout,
+        workgroup_size: u32,
+        total_work: usize,
+        timestamps: Option<PassTimestamps>,
+    ) {
+        let compute_bind_group_2 = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("compute-bind-group-2"),
+            layout: compute_bind_group_layout_2,
+            entries: &[
+                // compute.wgsl#input
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.buffers.current().binding_resource(0, None),
+                },
+                // compute.wgsl#output
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.buffers.next().binding_resource(0, None),
+                },
+            ],
+        });
+
+        command_encoder.push_debug_group("compute-pass");
+        command_encoder.with_timestamps(timestamps, |command_encoder| {
+            command_encoder.with_compute_pass(
+                &wgpu::ComputePassDescriptor {
+                    label: Some("compute-pass"),
+                },
+                |compute_pass| {
+                    compute_pass.set_pipeline(compute_pipeline);
+
+                    compute_pass.set_bind_group(0, compute_bind_group_1, &[]);
+                    compute_pass.set_bind_group(1, &compute_bind_group_2, &[]);
+
+                    compute_pass.insert_debug_marker("mandelbrot");
+
+                    let (x, y, z) = compute::mandelbrot_dispatch_size(
+                        total_work,
+                        workgroup_size,
+                        device.limits().max_compute_workgroups_per_dimension,
+                    );
+
+                    compute_pass.dispatch_workgroups(x, y, z);
+                },
+            );
+        });
+        command_encoder.pop_debug_group();
+    }
+
+    /// Sort the mapped staging buffer into the unescaped, newly escaped, and newly interior pixels.
+    fn receive(&mut self, screen_size: screen::Size, max_iterations: u32) {
+        {
+            let staging_buffer_view = self.staging_buffer.slice(..).get_mapped_range();
+            debug!("staging buffer mapped");
+
+            let unescaped_len = self.unescaped.len();
+            debug_assert!(unescaped_len as u64 <= self.staging_buffer.len());
+            self.unescaped.clear();
+            self.newly_escaped.clear();
+
+            /*
+            This caused a bug for me: even though I copy `unescaped_pixels.len()`
+            worth of data into the staging buffer, the buffer is still the size
+            of the screen.
+            Everything after `unescaped_pixels.len()` in the buffer is effectively
+            garbage (leftover from previous runs), so only the slice before it is read.
+            */
+            staging_buffer_view.as_slice()[..unescaped_len]
+                .iter()
+                .for_each(|pixel| {
+                    let pixel = *pixel;
+
+                    debug_assert!(pixel.x() < screen_size.width);
+                    debug_assert!(pixel.y() < screen_size.height);
+                    debug_assert!(pixel.escaped() <= INTERIOR);
+
+                    let index =
+                        pixel.y() as usize * screen_size.width as usize + pixel.x() as usize;
+                    match pixel.escaped() {
+                        ITERATING if pixel.iteration_count() >= max_iterations => {
+                            self.all[index] = pixel;
+                            self.newly_interior.push(pixel);
+                        }
+                        ITERATING => self.unescaped.push(pixel),
+                        ESCAPED => {
+                            self.all[index] = pixel;
+                            self.newly_escaped.push(pixel);
+                        }
+                        // Never dispatched again, and left uncoloured like the unescaped pixels.
+                        _ => self.all[index] = pixel,
+                    }
+                });
+        }
+
+        self.staging_buffer.unmap();
+
+        self.buffers.swap();
+    }
+
+    pub fn destroy(self) {
+        self.staging_buffer.destroy();
+        self.buffers.destroy();
+    }
+}
+
+/// [`Pixels`] at either [`compute::Precision`].
+pub enum PrecisionPixels {
+    Single(Pixels<Pixel>),
+    Double(Pixels<PixelF64>),
+}
+
+impl PrecisionPixels {
+    pub fn new(device: &wgpu::Device, precision: compute::Precision, size: screen::Size) -> Self {
+        match precision {
+            compute::Precision::Single | compute::Precision::Perturbed => {
+                PrecisionPixels::Single(Pixels::new(device, size))
+            }
+            compute::Precision::Double => PrecisionPixels::Double(Pixels::new(device, size)),
+        }
+    }
+
+    pub fn reset(&mut self, queue: &wgpu::Queue, size: screen::Size) {
+        match self {
+            PrecisionPixels::Single(pixels) => pixels.reset(queue, size),
+            PrecisionPixels::Double(pixels) => pixels.reset(queue, size),
+        }
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, size: screen::Size) {
+        match self {
+            PrecisionPixels::Single(pixels) => pixels.resize(device, size),
+            PrecisionPixels::Double(pixels) => pixels.resize(device, size),
+        }
+    }
+
+    /// See [`HistogramColouring::record_escaped`].
+    pub fn record_escaped(&self, histogram_colouring: &mut HistogramColouring) {
+        match self {
+            PrecisionPixels::Single(pixels) => {
+                histogram_colouring.record_escaped(&pixels.newly_escaped)
+            }
+            PrecisionPixels::Double(pixels) => {
+                histogram_colouring.record_escaped(&pixels.newly_escaped)
+            }
+        }
+    }
+
+    /// See [`HistogramColouring::colour`].
+    pub fn colour(
+        &self,
+        histogram_colouring: &HistogramColouring,
+        colour_ranges: &mut [ColourRange],
+    ) -> Option<Range<usize>> {
+        match self {
+            PrecisionPixels::Single(pixels) => {
+                histogram_colouring.colour(&pixels.all, colour_ranges)
+            }
+            PrecisionPixels::Double(pixels) => {
+                histogram_colouring.colour(&pixels.all, colour_ranges)
+            }
+        }
+    }
+
+    /// See [`OrbitTrapColouring::colour`].
+    pub fn colour_orbit_traps(
+        &self,
+        orbit_trap_colouring: &OrbitTrapColouring,
+        colour_ranges: &mut [ColourRange],
+    ) -> Option<Range<usize>> {
+        match self {
+            PrecisionPixels::Single(pixels) => {
+                orbit_trap_colouring.colour(&pixels.all, colour_ranges)
+            }
+            PrecisionPixels::Double(pixels) => {
+                orbit_trap_colouring.colour(&pixels.all, colour_ranges)
+            }
+        }
+    }
+
+    /// See [`DistanceColouring::colour`].
+    pub fn colour_distances(
+        &self,
+        distance_colouring: &DistanceColouring,
+        colour_ranges: &mut [ColourRange],
+    ) -> Option<Range<usize>> {
+        match self {
+            PrecisionPixels::Single(pixels) => {
+                distance_colouring.colour(&pixels.all, colour_ranges)
+            }
+            PrecisionPixels::Double(pixels) => {
+                distance_colouring.colour(&pixels.all, colour_ranges)
+            }
+        }
+    }
+
+    /// See [`EscapeTimeColouring::colour`].
+    pub fn colour_escape_times(
+        &self,
+        max_iterations: u32,
+        colour_ranges: &mut [ColourRange],
+    ) -> Option<Range<usize>> {
+        match self {
+            PrecisionPixels::Single(pixels) => {
+                EscapeTimeColouring.colour(&pixels.all, max_iterations, colour_ranges)
+            }
+            PrecisionPixels::Double(pixels) => {
+                EscapeTimeColouring.colour(&pixels.all, max_iterations, colour_ranges)
+            }
+        }
+    }
+
+    /// See [`HeatmapColouring::colour`].
+    pub fn colour_heatmap(
+        &self,
+        max_iterations: u32,
+        colour_ranges: &mut [ColourRange],
+    ) -> Option<Range<usize>> {
+        match self {
+            PrecisionPixels::Single(pixels) => {
+                HeatmapColouring.colour(&pixels.all, max_iterations, colour_ranges)
+            }
+            PrecisionPixels::Double(pixels) => {
+                HeatmapColouring.colour(&pixels.all, max_iterations, colour_ranges)
+            }
+        }
+    }
+
+    /// See [`RootColouring::colour`].
+    pub fn colour_roots(
+        &self,
+        root_colouring: &RootColouring,
+        colour_ranges: &mut [ColourRange],
+    ) -> Option<Range<usize>> {
+        match self {
+            PrecisionPixels::Single(pixels) => root_colouring.colour(&pixels.all, colour_ranges),
+            PrecisionPixels::Double(pixels) => root_colouring.colour(&pixels.all, colour_ranges),
+        }
+    }
+
+    /// See [`colour::InteriorColouring::colour`].
+    pub fn colour_interior(
+        &self,
+        interior_colouring: colour::InteriorColouring,
+        max_iterations: u32,
+        colour_ranges: &mut [ColourRange],
+    ) -> Option<Range<usize>> {
+        match self {
+            PrecisionPixels::Single(pixels) => {
+                interior_colouring.colour(&pixels.all, max_iterations, colour_ranges)
+            }
+            PrecisionPixels::Double(pixels) => {
+                interior_colouring.colour(&pixels.all, max_iterations, colour_ranges)
+            }
+        }
+    }
+
+    /// The buffer holding the latest compute pass's results, for [`colour::ColouringMode::GpuSmooth`].
+    pub fn binding_resource(&self) -> wgpu::BindingResource {
+        match self {
+            PrecisionPixels::Single(pixels) => pixels.buffers.current().binding_resource(0, None),
+            PrecisionPixels::Double(pixels) => pixels.buffers.current().binding_resource(0, None),
+        }
+    }
+
+    /// See [`Pixels::resume_interior`].
+    pub fn resume_interior(&mut self) {
+        match self {
+            PrecisionPixels::Single(pixels) => pixels.resume_interior(),
+            PrecisionPixels::Double(pixels) => pixels.resume_interior(),
+        }
+    }
+
+    /// The number of pixels that reached the maximum iteration count in the last compute pass.
+    pub fn newly_interior_len(&self) -> usize {
+        match self {
+            PrecisionPixels::Single(pixels) => pixels.newly_interior.len(),
+            PrecisionPixels::Double(pixels) => pixels.newly_interior.len(),
+        }
+    }
+
+    /// See [`Pixels::receive_all`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn receive_all(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        screen_size: screen::Size,
+        max_iterations: u32,
+    ) {
+        match self {
+            PrecisionPixels::Single(pixels) => {
+                pixels.receive_all(device, queue, screen_size, max_iterations)
+            }
+            PrecisionPixels::Double(pixels) => {
+                pixels.receive_all(device, queue, screen_size, max_iterations)
+            }
+        }
+    }
+
+    /// The number of pixels that escaped in the last compute pass.
+    pub fn newly_escaped_len(&self) -> usize {
+        match self {
+            PrecisionPixels::Single(pixels) => pixels.newly_escaped.len(),
+            PrecisionPixels::Double(pixels) => pixels.newly_escaped.len(),
+        }
+    }
+
+    /// See [`Pixels::is_finished`].
+    pub fn is_finished(&self, max_iterations: u32) -> bool {
+        match self {
+            PrecisionPixels::Single(pixels) => pixels.is_finished(max_iterations),
+            PrecisionPixels::Double(pixels) => pixels.is_finished(max_iterations),
+        }
+    }
+
+    /// The number of pixels that haven't escaped yet.
+    pub fn unescaped_len(&self) -> usize {
+        match self {
+            PrecisionPixels::Single(pixels) => pixels.unescaped.len(),
+            PrecisionPixels::Double(pixels) => pixels.unescaped.len(),
+        }
+    }
+
+    pub fn destroy(self) {
+        match self {
+            PrecisionPixels::Single(pixels) => pixels.destroy(),
+            PrecisionPixels::Double(pixels) => pixels.destroy(),
+        }
+    }
+}
+
+/// GPU time spent in each pass of a frame. See [`Renderer::last_frame_timings`].
+#[derive(Clone, Copy, Debug)]
+pub struct FrameTimings {
+    pub compute: Duration,
+    pub render: Duration,
+}
+
+/// What [`Renderer::finish_frame`] passes to the callback set with [`Renderer::set_frame_callback`].
+#[derive(Clone, Copy, Debug)]
+pub struct FrameStats {
+    /// Wall-clock time since the previous [`Renderer::finish_frame`], or zero for the first frame.
+    pub frame_time: Duration,
+    /// `None` when the device doesn't support [`wgpu::Features::TIMESTAMP_QUERY`].
+    pub gpu_timings: Option<FrameTimings>,
+    /// The number of pixels that haven't escaped yet.
+    pub unescaped: usize,
+    /// Bytes of [`Renderer::colour_ranges`] uploaded to the GPU during the frame.
+    pub colour_bytes_written: u64,
+}
+
+/// Timestamp queries written around the compute and render passes.
+pub struct Timestamps {
+    pub query_set: wgpu::QuerySet,
+    /// Where `query_set` is resolved to at the end of [`Renderer::render`].
+    pub resolve_buffer: typed_buffer::Buffer<u64>,
+    pub readback_buffer: typed_buffer::Buffer<u64>,
+    /// Nanoseconds per timestamp tick.
+    pub period: f32,
+}
+
+impl Timestamps {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("timestamps"),
+                ty: wgpu::QueryType::Timestamp,
+                count: TIMESTAMP_COUNT,
+            }),
+            resolve_buffer: typed_buffer::Builder::new(TIMESTAMP_COUNT as u64)
+                .with_label("timestamps-resolve-buffer")
+                .with_usage(wgpu::BufferUsages::QUERY_RESOLVE)
+                .with_usage(wgpu::BufferUsages::COPY_SRC)
+                .create(device),
+            readback_buffer: typed_buffer::Builder::new(TIMESTAMP_COUNT as u64)
+                .with_label("timestamps-readback-buffer")
+                .with_usage(wgpu::BufferUsages::MAP_READ)
+                .create(device),
+            period: queue.get_timestamp_period(),
+        }
+    }
+
+    fn pass(&self, begin_index: u32) -> PassTimestamps {
+        PassTimestamps {
+            query_set: &self.query_set,
+            begin_index,
+        }
+    }
+
+    /// Resolve all of the timestamps, and copy them to `readback_buffer`.
+    fn resolve(&self, command_encoder: &mut wgpu::CommandEncoder) {
+        command_encoder.resolve_query_set(
+            &self.query_set,
+            0..TIMESTAMP_COUNT,
+            self.resolve_buffer.buffer(),
+            0,
+        );
+        typed_buffer::copy_buffer_to_buffer(
+            command_encoder,
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            TIMESTAMP_COUNT as u64,
+        )
+        .expect("the resolve and readback buffers both hold TIMESTAMP_COUNT timestamps");
+    }
+
+    fn read(&self, device: &wgpu::Device) -> FrameTimings {
+        let timings = {
+            let ticks = self.readback_buffer.map_read_blocking(device);
+            let duration = |begin_index: usize| {
+                let elapsed = ticks[begin_index + 1].wrapping_sub(ticks[begin_index]);
+                Duration::from_nanos((elapsed as f64 * self.period as f64) as u64)
+            };
+            FrameTimings {
+                compute: duration(COMPUTE_TIMESTAMPS as usize),
+                render: duration(RENDER_TIMESTAMPS as usize),
+            }
+        };
+
+        self.readback_buffer.unmap();
+        timings
+    }
+}
+
+/**
+The device, pipelines, and buffers used to render a fractal.
+
+Pixels are iterated incrementally: each call to [`Renderer::compute`] runs one
+compute pass over the pixels that haven't escaped yet, and [`Renderer::render`]
+draws the colours computed so far.
+*/
+pub struct Renderer {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+
+    pub compute_shader_module: wgpu::ShaderModule,
+    pub compute_bind_group_layout_1: wgpu::BindGroupLayout,
+    pub compute_bind_group_layout_2: wgpu::BindGroupLayout,
+    pub compute_pipeline_layout: wgpu::PipelineLayout,
+    pub compute_pipeline: wgpu::ComputePipeline,
+    pub render_shader_module: wgpu::ShaderModule,
+    /// Where the shaders are read from, if not embedded. See [`Renderer::set_shader_dir`].
+    pub shader_dir: Option<PathBuf>,
+    pub render_pipeline_layout: wgpu::PipelineLayout,
+    pub render_pipeline: wgpu::RenderPipeline,
+    /// Draws [`colour::ColouringMode::GpuSmooth`] straight from the pixels.
+    pub gpu_smooth_render_pipeline: wgpu::RenderPipeline,
+    /// The format of the textures targeted by `render_pipeline`.
+    pub format: wgpu::TextureFormat,
+
+    pub fractal_kind: compute::FractalKind,
+    pub precision: compute::Precision,
+    /// See [`Renderer::set_workgroup_size`].
+    pub workgroup_size: u32,
+    /// See [`Renderer::set_samples_per_pixel`].
+    pub samples_per_pixel: u32,
+    /// The size that pixels are iterated at: `display_size` scaled by `ssaa`.
+    pub screen_size: screen::Size,
+    /// The size of the textures rendered to.
+    pub display_size: screen::Size,
+    /// See [`Renderer::set_ssaa`].
+    pub ssaa: u32,
+    /// See [`Renderer::set_post_processing`].
+    pub post_processing: PostProcessing,
+    /// See [`Renderer::set_palette_offset`].
+    pub palette_offset: f32,
+    /// See [`Renderer::set_msaa`].
+    pub msaa: u32,
+    /// See [`Renderer::set_grid`].
+    pub grid: bool,
+    /// See [`Renderer::set_dither`].
+    pub dither: bool,
+    /// See [`Renderer::set_heatmap`].
+    pub heatmap: bool,
+    pub escape_radius: f32,
+    pub max_iterations: u32,
+    /// See [`Renderer::set_auto_iterations`].
+    pub auto_iterations: Option<compute::AutoIterations>,
+    pub power: u32,
+    pub trap_shape: compute::TrapShape,
+    /// See [`Renderer::set_iterations_per_frame`].
+    pub iterations_per_frame: u32,
+    /// See [`Renderer::set_incremental`].
+    pub incremental: bool,
+
+    /// What's in `uniforms_buffer`, uploaded by `Renderer::write_uniforms`.
+    pub uniforms: compute::Uniforms,
+    /// Bound by both the compute and render shaders.
+    pub uniforms_buffer: typed_buffer::var::Var<compute::Uniforms>,
+    pub zoom_f64_buffer: typed_buffer::var::Var<f64>,
+    pub origin_f64_buffer: typed_buffer::var::Var<ComplexF64>,
+    pub julia_c_buffer: typed_buffer::var::Var<Complex>,
+    pub tile_offset_buffer: typed_buffer::var::Var<[u32; 2]>,
+    pub trap_shape_buffer: typed_buffer::var::Var<u32>,
+    pub iterations_per_frame_buffer: typed_buffer::var::Var<u32>,
+    pub interior_colouring_buffer: typed_buffer::var::Var<u32>,
+    /// The orbit that [`compute::Precision::Perturbed`] iterates relative to.
+    pub reference_orbit_buffer: typed_buffer::Buffer<Complex>,
+    /// `origin` minus the reference orbit's centre.
+    pub reference_offset_buffer: typed_buffer::var::Var<Complex>,
+    pub palette_buffer: typed_buffer::var::Var<PaletteUniform>,
+    /// What was last written to `palette_buffer`, so [`Renderer::recover`] can write it again.
+    palette: PaletteUniform,
+    pub ssaa_buffer: typed_buffer::var::Var<u32>,
+    pub post_processing_buffer: typed_buffer::var::Var<PostProcessing>,
+    pub palette_offset_buffer: typed_buffer::var::Var<f32>,
+    pub grid_buffer: typed_buffer::var::Var<GridUniform>,
+    pub dither_buffer: typed_buffer::var::Var<u32>,
+    pub selection_buffer: typed_buffer::var::Var<SelectionUniform>,
+
+    pub pixels: PrecisionPixels,
+    pub colour_ranges_buffer: typed_buffer::Buffer<ColourRange>,
+
+    pub compute_bind_group_1: wgpu::BindGroup,
+    pub render_bind_group_1: wgpu::BindGroup,
+
+    pub colour_ranges: Vec<ColourRange>,
+    /// See [`Renderer::set_colour_smoothing`].
+    pub colour_smoothing: f32,
+    /**
+    What's been uploaded of [`Renderer::colour_ranges`] while [`Renderer::colour_smoothing`]
+    eases toward it. Empty when that's `1.0`.
+    */
+    shown_colour_ranges: Vec<ColourRange>,
+    /// The range of `shown_colour_ranges` that hasn't caught up with `colour_ranges` yet.
+    unsettled_colours: Option<Range<usize>>,
+    pub colouring_mode: colour::ColouringMode,
+    /// See [`Renderer::set_interior_colouring`].
+    pub interior_colouring: colour::InteriorColouring,
+    pub histogram_colouring: HistogramColouring,
+    pub orbit_trap_colouring: OrbitTrapColouring,
+    pub distance_colouring: DistanceColouring,
+    pub root_colouring: RootColouring,
+
+    /// `None` when the device doesn't support [`wgpu::Features::TIMESTAMP_QUERY`].
+    pub timestamps: Option<Timestamps>,
+
+    frame_callback: Option<Box<dyn FnMut(FrameStats)>>,
+    /// When [`Renderer::finish_frame`] was last called.
+    last_frame: Option<Instant>,
+    /// See [`Renderer::origin`].
+    origin: ComplexF64,
+    /// See [`Renderer::zoom`].
+    zoom: f64,
+    /// See [`Renderer::set_target`].
+    target: Option<ComplexF64>,
+    /// The point that `reference_orbit_buffer` was iterated from.
+    reference_centre: ComplexF64,
+    /// The origin and zoom the renderer was created with. See [`Renderer::go_home`].
+    home: (ComplexF64, f64),
+    /// Whether [`Renderer::compute`] should [`Renderer::reset`] first, after the view has changed.
+    reset_pending: bool,
+    /// The histogram colours haven't caught up with the escaped pixels, so recolour them next
+    /// [`Renderer::compute`] even if nothing escapes.
+    colours_pending: bool,
+    /// Bytes of `colour_ranges` uploaded since [`Renderer::finish_frame`] was last called.
+    colour_bytes_written: u64,
+    /**
+    Iterations run since the last [`Renderer::reset`] in [`colour::ColouringMode::GpuSmooth`],
+    which never reads the pixels back to see which have finished.
+    */
+    gpu_iterations: u32,
+    /// Whether `format` can be multisampled and resolved on this adapter.
+    msaa_supported: bool,
+    /// Drawn to and resolved into the target when [`Renderer::msaa`] is more than `1`.
+    msaa_texture_view: Option<wgpu::TextureView>,
+    /// See [`Renderer::adapter_info`].
+    adapter_info: wgpu::AdapterInfo,
+}
+
+impl Renderer {
+    /**
+    Create a renderer on `adapter` whose render pass targets textures with `format`, blocking
+    until the device is ready. See [`Renderer::request`].
+    */
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(
+        adapter: &wgpu::Adapter,
+        format: wgpu::TextureFormat,
+        screen_size: screen::Size,
+        origin: ComplexF64,
+        zoom: f64,
+    ) -> Result<Self, RendererError> {
+        pollster::block_on(Self::request(adapter, format, screen_size, origin, zoom))
+    }
+
+    /**
+    Create a renderer on `adapter` whose render pass targets textures with `format`.
+
+    The view starts at `origin` with `zoom`, at [`compute::Precision::Single`]. Requesting the
+    device can't block on the web, so this is `async`. Fails if the device can't be requested or
+    the compute shader doesn't compile; see [`RendererError`].
+    */
+    pub async fn request(
+        adapter: &wgpu::Adapter,
+        format: wgpu::TextureFormat,
+        screen_size: screen::Size,
+        origin: ComplexF64,
+        zoom: f64,
+    ) -> Result<Self, RendererError> {
+        let adapter_info = adapter.get_info();
+        info!(
+            "using {} ({:?}, {:?}, vendor {:#06x}, device {:#06x})",
+            adapter_info.name,
+            adapter_info.backend,
+            adapter_info.device_type,
+            adapter_info.vendor,
+            adapter_info.device
+        );
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("device"),
+                    // Enabled when available, for `compute::Precision::Double` and `Renderer::last_frame_timings`.
+                    features: adapter.features()
+                        & (wgpu::Features::SHADER_FLOAT64 | wgpu::Features::TIMESTAMP_QUERY),
+                    // The adapter's workgroup limits, so `compute::workgroup_size` can use them.
+                    limits: wgpu::Limits {
+                        max_compute_workgroup_size_y: adapter.limits().max_compute_workgroup_size_y,
+                        max_compute_invocations_per_workgroup: adapter
+                            .limits()
+                            .max_compute_invocations_per_workgroup,
+                        ..wgpu::Limits::default()
+                    },
+                },
+                None,
+            )
+            .await?;
+
+        let precision = compute::Precision::Single;
+        let workgroup_size = compute::workgroup_size(None, &device.limits());
+
+        let compute_bind_group_layout_1 =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("compute-bind-group-layout-1"),
+                entries: &[
+                    // compute.wgsl#uniforms
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // compute_f64.wgsl#zoom
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // compute_f64.wgsl#origin
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // compute.wgsl#julia_c
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // compute.wgsl#tile_offset
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // compute.wgsl#trap_shape
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // compute.wgsl#iterations_per_frame
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 6,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // compute.wgsl#interior_colouring
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 7,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // perturbation.wgsl#reference_orbit
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 8,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // perturbation.wgsl#reference_offset
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 9,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let compute_bind_group_layout_2 =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("compute-bind-group-layout-2"),
+                entries: &[
+                    // compute.wgsl#input
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // compute.wgsl#output
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let compute_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("compute-pipeline-layout"),
+                bind_group_layouts: &[&compute_bind_group_layout_1, &compute_bind_group_layout_2],
+                push_constant_ranges: &[],
+            });
+
+        let fractal_kind = compute::FractalKind::Mandelbrot;
+        let samples_per_pixel = 1;
+        let compute_shader_source =
+            precision.shader_source(workgroup_size, samples_per_pixel, None)?;
+        let (compute_shader_module, compute_pipeline) = validate(&device, || {
+            let module = create_compute_shader_module(&device, compute_shader_source);
+            let pipeline = create_compute_pipeline(
+                &device,
+                &compute_pipeline_layout,
+                &module,
+                precision,
+                fractal_kind,
+                samples_per_pixel,
+            );
+            (module, pipeline)
+        })?;
+
+        let render_shader_module =
+            create_render_shader_module(&device, &shader::read(shader::RENDER, None)?);
+
+        let render_bind_group_layout_1 =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("render-bind-group-layout"),
+                entries: &[
+                    // render.wgsl#uniforms
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // render.wgsl#palette
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // render.wgsl#ssaa
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // render.wgsl#post_processing
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // render.wgsl#palette_offset
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // render.wgsl#grid
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // render.wgsl#dither
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 6,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // render.wgsl#selection
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 7,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let render_bind_group_layout_2 =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("render-bind-group-layout-2"),
+                entries: &[
+                    // render.wgsl#colour_ranges
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // render.wgsl#pixels
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("render-pipeline-layout"),
+                bind_group_layouts: &[&render_bind_group_layout_1, &render_bind_group_layout_2],
+                push_constant_ranges: &[],
+            });
+
+        let render_pipeline = create_render_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &render_shader_module,
+            format,
+            "fragment_main",
+            1,
+        );
+        let gpu_smooth_render_pipeline = create_render_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &render_shader_module,
+            format,
+            "fragment_gpu_smooth",
+            1,
+        );
+        let format_features = adapter.get_texture_format_features(format).flags;
+        let msaa_supported = format_features.contains(
+            wgpu::TextureFormatFeatureFlags::MULTISAMPLE
+                | wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE,
+        );
+
+        let escape_radius = compute::DEFAULT_ESCAPE_RADIUS;
+        let max_iterations = compute::DEFAULT_MAX_ITERATIONS;
+        let power = compute::DEFAULT_POWER;
+        let uniforms = compute::Uniforms {
+            screen_size,
+            origin: origin.into(),
+            zoom: zoom as f32,
+            escape_radius,
+            max_iterations,
+            power,
+        };
+        let uniforms_buffer = typed_buffer::var::Builder::new(uniforms)
+            .with_label("uniforms-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(&device);
+
+        let zoom_f64_buffer = typed_buffer::var::Builder::new(zoom)
+            .with_label("zoom-f64-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(&device);
+
+        let origin_f64_buffer = typed_buffer::var::Builder::new(origin)
+            .with_label("origin-f64-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(&device);
+
+        let julia_c_buffer = typed_buffer::var::Builder::new(Complex::ZERO)
+            .with_label("julia-c-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(&device);
+
+        let tile_offset_buffer = typed_buffer::var::Builder::new([0, 0])
+            .with_label("tile-offset-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(&device);
+
+        let trap_shape = compute::TrapShape::Point;
+        let trap_shape_buffer = typed_buffer::var::Builder::new(trap_shape.index())
+            .with_label("trap-shape-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(&device);
+
+        let iterations_per_frame = compute::DEFAULT_ITERATIONS_PER_FRAME;
+        let iterations_per_frame_buffer = typed_buffer::var::Builder::new(iterations_per_frame)
+            .with_label("iterations-per-frame-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(&device);
+
+        let interior_colouring = colour::InteriorColouring::Flat;
+        let interior_colouring_buffer = typed_buffer::var::Builder::new(interior_colouring.index())
+            .with_label("interior-colouring-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(&device);
+
+        // Filled in by `Renderer::update_reference_orbit`, at `compute::Precision::Perturbed`.
+        let reference_orbit_buffer = typed_buffer::Builder::from([Complex::ZERO; 2].as_slice())
+            .with_label("reference-orbit-buffer")
+            .with_usage(wgpu::BufferUsages::STORAGE)
+            .create(&device);
+
+        let reference_offset_buffer = typed_buffer::var::Builder::new(Complex::ZERO)
+            .with_label("reference-offset-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(&device);
+
+        let pixels = PrecisionPixels::new(&device, precision, screen_size);
+        let colour_ranges_buffer = create_colour_ranges_buffer(&device, screen_size);
+
+        let compute_bind_group_1 = create_compute_bind_group_1(
+            &device,
+            &compute_bind_group_layout_1,
+            &uniforms_buffer,
+            &zoom_f64_buffer,
+            &origin_f64_buffer,
+            &julia_c_buffer,
+            &tile_offset_buffer,
+            &trap_shape_buffer,
+            &iterations_per_frame_buffer,
+            &interior_colouring_buffer,
+            &reference_orbit_buffer,
+            &reference_offset_buffer,
+        );
+        let palette = PaletteUniform::new(&colour::Green);
+        let palette_buffer = typed_buffer::var::Builder::new(palette)
+            .with_label("palette-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(&device);
+
+        let ssaa = 1;
+        let ssaa_buffer = typed_buffer::var::Builder::new(ssaa)
+            .with_label("ssaa-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(&device);
+
+        let post_processing = PostProcessing::default();
+        let post_processing_buffer = typed_buffer::var::Builder::new(post_processing)
+            .with_label("post-processing-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(&device);
+
+        let palette_offset = 0.0;
+        let palette_offset_buffer = typed_buffer::var::Builder::new(palette_offset)
+            .with_label("palette-offset-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(&device);
+
+        let grid = false;
+        let grid_buffer =
+            typed_buffer::var::Builder::new(GridUniform::new(origin, zoom, screen_size, grid))
+                .with_label("grid-buffer")
+                .with_usage(wgpu::BufferUsages::UNIFORM)
+                .create(&device);
+
+        let dither = false;
+        let dither_buffer = typed_buffer::var::Builder::new(dither as u32)
+            .with_label("dither-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(&device);
+
+        let selection_buffer = typed_buffer::var::Builder::new(SelectionUniform::NONE)
+            .with_label("selection-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(&device);
+
+        let render_bind_group_1 = create_render_bind_group_1(
+            &device,
+            &render_pipeline,
+            &uniforms_buffer,
+            &palette_buffer,
+            &ssaa_buffer,
+            &post_processing_buffer,
+            &palette_offset_buffer,
+            &grid_buffer,
+            &dither_buffer,
+            &selection_buffer,
+        );
+
+        let timestamps = if device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            Some(Timestamps::new(&device, &queue))
+        } else {
+            None
+        };
+
+        let colour_ranges: Vec<ColourRange> = std::iter::repeat(ColourRange::default())
+            .take((screen_size.width * screen_size.height) as usize)
+            .collect();
+
+        Ok(Self {
+            device,
+            queue,
+            compute_shader_module,
+            compute_bind_group_layout_1,
+            compute_bind_group_layout_2,
+            compute_pipeline_layout,
+            compute_pipeline,
+            render_shader_module,
+            shader_dir: None,
+            render_pipeline_layout,
+            render_pipeline,
+            gpu_smooth_render_pipeline,
+            format,
+            fractal_kind,
+            precision,
+            workgroup_size,
+            samples_per_pixel,
+            screen_size,
+            display_size: screen_size,
+            ssaa,
+            post_processing,
+            palette_offset,
+            escape_radius,
+            max_iterations,
+            auto_iterations: None,
+            power,
+            trap_shape,
+            iterations_per_frame,
+            incremental: true,
+            uniforms,
+            uniforms_buffer,
+            zoom_f64_buffer,
+            origin_f64_buffer,
+            julia_c_buffer,
+            tile_offset_buffer,
+            trap_shape_buffer,
+            iterations_per_frame_buffer,
+            interior_colouring_buffer,
+            reference_orbit_buffer,
+            reference_offset_buffer,
+            palette_buffer,
+            palette,
+            ssaa_buffer,
+            post_processing_buffer,
+            palette_offset_buffer,
+            grid_buffer,
+            dither_buffer,
+            selection_buffer,
+            pixels,
+            colour_ranges_buffer,
+            compute_bind_group_1,
+            render_bind_group_1,
+            colour_ranges,
+            colour_smoothing: 1.0,
+            shown_colour_ranges: Vec::new(),
+            unsettled_colours: None,
+            colouring_mode: colour::ColouringMode::Histogram,
+            interior_colouring,
+            histogram_colouring: HistogramColouring::new()
+                .with_smoothing(true)
+                .with_min_sample_fraction(HISTOGRAM_MIN_SAMPLE_FRACTION)
+                .with_temporal_smoothing(HISTOGRAM_TEMPORAL_SMOOTHING),
+            orbit_trap_colouring: OrbitTrapColouring::new(),
+            distance_colouring: DistanceColouring::new(),
+            root_colouring: RootColouring::new(),
+            timestamps,
+            frame_callback: None,
+            last_frame: None,
+            origin,
+            zoom,
+            target: None,
+            reference_centre: origin,
+            home: (origin, zoom),
+            reset_pending: false,
+            colours_pending: false,
+            colour_bytes_written: 0,
+            gpu_iterations: 0,
+            msaa: 1,
+            grid,
+            dither,
+            heatmap: false,
+            msaa_supported,
+            msaa_texture_view: None,
+            adapter_info,
+        })
+    }
+
+    /**
+    Create a renderer that isn't attached to a window, on the default adapter. Use
+    [`Renderer::render_to_vec`] to get images out of it.
+
+    Fails with [`RendererError::NoAdapter`] where there's no GPU to render on.
+    */
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new_headless(size: screen::Size) -> Result<Self, RendererError> {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
+
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: Default::default(),
+            force_fallback_adapter: false,
+            compatible_surface: None,
+        }))
+        .ok_or(RendererError::NoAdapter)?;
+
+        Self::new(&adapter, HEADLESS_FORMAT, size, ComplexF64::ZERO, 1.0)
+    }
+
+    /// Create `compute_bind_group_1`, e.g. after the pixel buffers have been resized.
+    pub fn create_compute_bind_group_1(&self) -> wgpu::BindGroup {
+        create_compute_bind_group_1(
+            &self.device,
+            &self.compute_bind_group_layout_1,
+            &self.uniforms_buffer,
+            &self.zoom_f64_buffer,
+            &self.origin_f64_buffer,
+            &self.julia_c_buffer,
+            &self.tile_offset_buffer,
+            &self.trap_shape_buffer,
+            &self.iterations_per_frame_buffer,
+            &self.interior_colouring_buffer,
+            &self.reference_orbit_buffer,
+            &self.reference_offset_buffer,
+        )
+    }
+
+    /**
+    Log wgpu's validation errors and keep going, instead of panicking on the first one. Errors
+    caught by [`validate`], like shaders that don't compile, are returned as before.
+
+    Every object is labelled, so the messages say which buffer or pass is at fault. wgpu 0.14 only
+    enables the graphics API's own validation layers in debug builds, so build without
+    `--release` to get their messages too.
+    */
+    pub fn log_validation_errors(&self) {
+        self.device
+            .on_uncaptured_error(|error| error!("wgpu validation error: {}", error));
+    }
+
+    /**
+    Start over on a new device from `adapter`, after this one was lost, e.g. to a driver reset.
+    Everything on the GPU is recreated, and the view, the palette and the other settings are
+    carried over, so only the iterations so far are lost.
+
+    wgpu 0.14 has no callback for a lost device, so it's up to the caller to notice, e.g. from a
+    surface that stays [`wgpu::SurfaceError::Lost`] after it's reconfigured. Fails if the new
+    renderer can't be created. Settings that can't be restored, like
+    [`compute::Precision::Double`] on an adapter without it, are logged and left at their defaults.
+    [`Renderer::log_validation_errors`] has to be called again.
+    */
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn recover(&mut self, adapter: &wgpu::Adapter) -> Result<(), RendererError> {
+        warn!("the device was lost; recreating the renderer");
+        let mut renderer = Self::new(
+            adapter,
+            self.format,
+            self.display_size,
+            self.origin,
+            self.zoom,
+        )?;
+
+        // Each recompiles the compute shader on top of the last.
+        let results = [
+            renderer.set_shader_dir(self.shader_dir.clone()),
+            renderer.set_precision(self.precision),
+            renderer.set_workgroup_size(self.workgroup_size),
+            renderer.set_samples_per_pixel(self.samples_per_pixel),
+            renderer.set_fractal_kind(self.fractal_kind),
+        ];
+        for err in results.into_iter().filter_map(Result::err) {
+            warn!(
+                "couldn't restore a setting after losing the device: {}",
+                err
+            );
+        }
+
+        renderer.home = self.home;
+        renderer.palette = self.palette;
+        renderer
+            .palette_buffer
+            .write(&renderer.queue, renderer.palette);
+        renderer.set_palette_offset(self.palette_offset);
+        renderer.set_post_processing(self.post_processing);
+        renderer.set_ssaa(self.ssaa);
+        renderer.set_msaa(self.msaa);
+        renderer.set_grid(self.grid);
+        renderer.set_dither(self.dither);
+        renderer.set_escape_radius(self.escape_radius);
+        renderer.set_power(self.power);
+        renderer.set_trap_shape(self.trap_shape);
+        renderer.set_iterations_per_frame(self.iterations_per_frame);
+        renderer.set_incremental(self.incremental);
+        renderer.set_max_iterations(self.max_iterations);
+        renderer.set_auto_iterations(self.auto_iterations);
+        renderer.set_target(self.target);
+        renderer.set_colouring_mode(self.colouring_mode);
+        renderer.set_colour_smoothing(self.colour_smoothing);
+        renderer.set_interior_colouring(self.interior_colouring);
+        if self.heatmap {
+            renderer.set_heatmap(true);
+        }
+        std::mem::swap(
+            &mut renderer.histogram_colouring,
+            &mut self.histogram_colouring,
+        );
+        std::mem::swap(
+            &mut renderer.orbit_trap_colouring,
+            &mut self.orbit_trap_colouring,
+        );
+        std::mem::swap(
+            &mut renderer.distance_colouring,
+            &mut self.distance_colouring,
+        );
+        std::mem::swap(&mut renderer.root_colouring, &mut self.root_colouring);
+        renderer.frame_callback = self.frame_callback.take();
+
+        renderer.reset();
+        *self = renderer;
+        Ok(())
+    }
+
+    /// The GPU this renderer runs on, e.g. for bug reports.
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.adapter_info
+    }
+
+    /// The centre of the view.
+    pub fn origin(&self) -> ComplexF64 {
+        self.origin
+    }
+
+    /// How far the view is zoomed in. At `1.0` it spans `origin - 2` to `origin + 2` on both axes.
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    /// Move the centre of the view, discarding the iterations so far on the next [`Renderer::compute`].
+    pub fn set_origin(&mut self, origin: ComplexF64) {
+        if origin != self.origin {
+            self.write_origin(origin);
+            self.reset_pending = true;
+        }
+    }
+
+    /// Zoom the view, discarding the iterations so far on the next [`Renderer::compute`].
+    pub fn set_zoom(&mut self, zoom: f64) {
+        if zoom != self.zoom {
+            self.write_zoom(zoom);
+            self.reset_pending = true;
+            self.apply_auto_iterations();
+        }
+    }
+
+    /// The origin and zoom the renderer was created with.
+    pub fn home(&self) -> (ComplexF64, f64) {
+        self.home
+    }
+
+    /**
+    Return to [`Renderer::home`] at [`compute::DEFAULT_MAX_ITERATIONS`], or
+    [`Renderer::auto_iterations`]' count for it, and stop drifting. The
+    iterations so far are discarded on the next [`Renderer::compute`], even if the view was
+    already there.
+    */
+    pub fn go_home(&mut self) {
+        let (origin, zoom) = self.home;
+        self.target = None;
+        self.set_origin(origin);
+        self.set_zoom(zoom);
+        if self.auto_iterations.is_some() {
+            self.apply_auto_iterations();
+        } else {
+            self.set_max_iterations(compute::DEFAULT_MAX_ITERATIONS);
+        }
+        self.reset_pending = true;
+    }
+
+    /// Where [`Renderer::drift`] is moving the view, if anywhere.
+    pub fn target(&self) -> Option<ComplexF64> {
+        self.target
+    }
+
+    /// Start drifting the view toward `target` with [`Renderer::drift`], or stop with `None`.
+    pub fn set_target(&mut self, target: Option<ComplexF64>) {
+        self.target = target;
+    }
+
+    /**
+    Move the centre of the view `fraction` of the way toward the target from
+    [`Renderer::set_target`], without resetting, like a smooth zoom.
+
+    Once the origin is within [`DRIFT_SETTLED_PIXELS`] of the target it snaps to it, resets once,
+    and stops drifting, so it doesn't keep moving by amounts too small to see. Returns whether the
+    origin moved.
+    */
+    pub fn drift(&mut self, fraction: f64) -> bool {
+        let target = match self.target {
+            Some(target) => target,
+            None => return false,
+        };
+
+        let offset = ComplexF64 {
+            real: target.real - self.origin.real,
+            imaginary: target.imaginary - self.origin.imaginary,
+        };
+        let settled = DRIFT_SETTLED_PIXELS * pixel::pixel_size(self.display_size, self.zoom);
+        if offset.real.hypot(offset.imaginary) < settled {
+            let moved = target != self.origin;
+            self.set_origin(target);
+            self.target = None;
+            return moved;
+        }
+
+        self.write_origin(ComplexF64 {
+            real: self.origin.real + offset.real * fraction,
+            imaginary: self.origin.imaginary + offset.imaginary * fraction,
+        });
+        true
+    }
+
+    /// Upload `uniforms` after changing any of its fields.
+    fn write_uniforms(&self) {
+        self.uniforms_buffer.write(&self.queue, self.uniforms);
+    }
+
+    /**
+    Move the centre of the view at both precisions, but keep iterating the pixels from where they
+    were. For animations, which [`Renderer::set_origin`] once they've settled.
+    */
+    pub fn write_origin(&mut self, origin: ComplexF64) {
+        self.origin = origin;
+        self.uniforms.origin = origin.into();
+        self.write_uniforms();
+        self.origin_f64_buffer.write(&self.queue, origin);
+        self.reference_offset_buffer.write(
+            &self.queue,
+            Complex::from(ComplexF64 {
+                real: origin.real - self.reference_centre.real,
+                imaginary: origin.imaginary - self.reference_centre.imaginary,
+            }),
+        );
+        self.write_grid();
+    }
+
+    /// Like [`Renderer::write_origin`], for the zoom.
+    pub fn write_zoom(&mut self, zoom: f64) {
+        self.zoom = zoom;
+        self.uniforms.zoom = zoom as f32;
+        self.write_uniforms();
+        self.zoom_f64_buffer.write(&self.queue, zoom);
+        self.write_grid();
+    }
+
+    /**
+    Draw gridlines at round coordinates and a scale bar over the fractal, or stop. Nothing is
+    recomputed. The spacing follows the zoom; see [`grid::spacing`].
+    */
+    pub fn set_grid(&mut self, grid: bool) {
+        self.grid = grid;
+        self.write_grid();
+    }
+
+    /**
+    Dither the rendered colours to hide banding in smooth gradients, or stop. Off by default, so
+    colours come out unchanged. Nothing is recomputed.
+    */
+    pub fn set_dither(&mut self, dither: bool) {
+        self.dither = dither;
+        self.dither_buffer.write(&self.queue, dither as u32);
+    }
+
+    /**
+    Colour pixels by how many iterations they took, with [`HeatmapColouring`], instead of with
+    the colouring mode, or stop. Nothing is recomputed. Has no effect in
+    [`colour::ColouringMode::GpuSmooth`], which doesn't use `colour_ranges`.
+    */
+    pub fn set_heatmap(&mut self, heatmap: bool) {
+        if self.colouring_mode == colour::ColouringMode::GpuSmooth {
+            warn!("the heatmap isn't drawn with GPU colouring");
+        }
+        self.heatmap = heatmap;
+        // Interior pixels aren't recoloured when the interior is flat, so start from scratch.
+        self.colour_ranges.fill(ColourRange::default());
+        self.colour_pixels();
+        self.colour_interior();
+        self.write_colour_ranges(Some(0..self.colour_ranges.len()));
+    }
+
+    /**
+    Outline the rectangle from `min` to `max`, in screen pixels, e.g. while it's being dragged
+    out to zoom into, or `None` to stop. Nothing is recomputed.
+    */
+    pub fn set_selection(&mut self, selection: Option<(screen::Vec2, screen::Vec2)>) {
+        let uniform = selection.map_or(SelectionUniform::NONE, |(min, max)| SelectionUniform {
+            min,
+            max,
+        });
+        self.selection_buffer.write(&self.queue, uniform);
+    }
+
+    /// Update `grid_buffer` for the current view.
+    fn write_grid(&self) {
+        self.grid_buffer.write(
+            &self.queue,
+            GridUniform::new(self.origin, self.zoom, self.display_size, self.grid),
+        );
+    }
+
+    /**
+    Iterate at a different precision, discarding the iterations so far.
+
+    Falls back to [`compute::Precision::Single`] when the device doesn't support
+    [`wgpu::Features::SHADER_FLOAT64`]. Nothing changes if the shader doesn't compile.
+    */
+    pub fn set_precision(&mut self, precision: compute::Precision) -> Result<(), RendererError> {
+        let precision = if precision == compute::Precision::Double
+            && !self
+                .device
+                .features()
+                .contains(wgpu::Features::SHADER_FLOAT64)
+        {
+            warn!("SHADER_FLOAT64 is not supported by this device; using single precision");
+            compute::Precision::Single
+        } else {
+            precision
+        };
+
+        if precision == self.precision {
+            return Ok(());
+        }
+        let (compute_shader_module, compute_pipeline) = self.create_compute_shader(
+            precision,
+            self.workgroup_size,
+            self.samples_per_pixel,
+            self.fractal_kind,
+        )?;
+        debug!("precision set to {:?}", precision);
+
+        if precision == compute::Precision::Double
+            && self.colouring_mode == colour::ColouringMode::GpuSmooth
+        {
+            warn!("GPU colouring needs single precision; using histogram colouring");
+            self.colouring_mode = colour::ColouringMode::Histogram;
+        }
+
+        self.precision = precision;
+        self.compute_shader_module = compute_shader_module;
+        self.compute_pipeline = compute_pipeline;
+        self.compute_bind_group_1 = self.create_compute_bind_group_1();
+        std::mem::replace(
+            &mut self.pixels,
+            PrecisionPixels::new(&self.device, precision, self.screen_size),
+        )
+        .destroy();
+        self.reset();
+        Ok(())
+    }
+
+    /// Compile the compute shader and create its pipeline. See [`validate`].
+    fn create_compute_shader(
+        &self,
+        precision: compute::Precision,
+        workgroup_size: u32,
+        samples_per_pixel: u32,
+        fractal_kind: compute::FractalKind,
+    ) -> Result<(wgpu::ShaderModule, wgpu::ComputePipeline), RendererError> {
+        let source = precision.shader_source(
+            workgroup_size,
+            samples_per_pixel,
+            self.shader_dir.as_deref(),
+        )?;
+        validate(&self.device, || {
+            let module = create_compute_shader_module(&self.device, source);
+            let pipeline = create_compute_pipeline(
+                &self.device,
+                &self.compute_pipeline_layout,
+                &module,
+                precision,
+                fractal_kind,
+                samples_per_pixel,
+            );
+            (module, pipeline)
+        })
+    }
+
+    /// Compile the render shader and create its pipelines. See [`validate`].
+    fn create_render_shader(
+        &self,
+    ) -> Result<
+        (
+            wgpu::ShaderModule,
+            wgpu::RenderPipeline,
+            wgpu::RenderPipeline,
+        ),
+        RendererError,
+    > {
+        let source = shader::read(shader::RENDER, self.shader_dir.as_deref())?;
+        validate(&self.device, || {
+            let module = create_render_shader_module(&self.device, &source);
+            let create = |entry_point| {
+                create_render_pipeline(
+                    &self.device,
+                    &self.render_pipeline_layout,
+                    &module,
+                    self.format,
+                    entry_point,
+                    self.msaa,
+                )
+            };
+            let render_pipeline = create("fragment_main");
+            let gpu_smooth_render_pipeline = create("fragment_gpu_smooth");
+            (module, render_pipeline, gpu_smooth_render_pipeline)
+        })
+    }
+
+    /**
+    Read the shaders from `shader_dir` instead of the copies embedded in the binary, or go back to
+    those with `None`, and recompile them. Nothing changes if they can't be read or don't compile.
+    */
+    pub fn set_shader_dir(&mut self, shader_dir: Option<PathBuf>) -> Result<(), RendererError> {
+        let previous = std::mem::replace(&mut self.shader_dir, shader_dir);
+        let shaders = self
+            .create_compute_shader(
+                self.precision,
+                self.workgroup_size,
+                self.samples_per_pixel,
+                self.fractal_kind,
+            )
+            .and_then(|compute| Ok((compute, self.create_render_shader()?)));
+        let ((compute_shader_module, compute_pipeline), render) = match shaders {
+            Ok(shaders) => shaders,
+            Err(error) => {
+                self.shader_dir = previous;
+                return Err(error);
+            }
+        };
+        debug!("shader directory set to {:?}", self.shader_dir);
+
+        self.compute_shader_module = compute_shader_module;
+        self.compute_pipeline = compute_pipeline;
+        (
+            self.render_shader_module,
+            self.render_pipeline,
+            self.gpu_smooth_render_pipeline,
+        ) = render;
+        self.reset();
+        Ok(())
+    }
+
+    /**
+    Recompile the shaders made from the file `name`, after it's changed in
+    [`Renderer::shader_dir`]. Returns whether the current shaders use it at all.
+
+    Iterations so far are discarded when the compute shader changes. The old shaders are kept if
+    the new ones can't be read or don't compile, so a typo doesn't stop the fractal from drawing.
+    */
+    pub fn reload_shader(&mut self, name: &str) -> Result<bool, RendererError> {
+        if name == shader::RENDER {
+            (
+                self.render_shader_module,
+                self.render_pipeline,
+                self.gpu_smooth_render_pipeline,
+            ) = self.create_render_shader()?;
+        } else if self.precision.shader_files().contains(&name) {
+            (self.compute_shader_module, self.compute_pipeline) = self.create_compute_shader(
+                self.precision,
+                self.workgroup_size,
+                self.samples_per_pixel,
+                self.fractal_kind,
+            )?;
+            self.reset();
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /**
+    Compile the compute shader for `workgroup_size` invocations per workgroup, clamped to the
+    device's limits. See [`compute::workgroup_size`].
+
+    Pixels keep their progress: each pass indexes them with the size it was dispatched with.
+    Nothing changes if the shader doesn't compile.
+    */
+    pub fn set_workgroup_size(&mut self, workgroup_size: u32) -> Result<(), RendererError> {
+        let workgroup_size = compute::workgroup_size(Some(workgroup_size), &self.device.limits());
+        if workgroup_size == self.workgroup_size {
+            return Ok(());
+        }
+        let (compute_shader_module, compute_pipeline) = self.create_compute_shader(
+            self.precision,
+            workgroup_size,
+            self.samples_per_pixel,
+            self.fractal_kind,
+        )?;
+        debug!("workgroup size set to {}", workgroup_size);
+
+        self.workgroup_size = workgroup_size;
+        self.compute_shader_module = compute_shader_module;
+        self.compute_pipeline = compute_pipeline;
+        Ok(())
+    }
+
+    /**
+    Anti-alias the boundary of the set by iterating `samples_per_pixel` points, jittered across
+    each pixel, instead of its centre. See `compute.wgsl#mandelbrot_multisampled`. `1` turns it
+    off. The iterations so far are discarded, and nothing changes if the shader doesn't compile.
+
+    Unlike [`Renderer::set_ssaa`], no more pixels are stored: each one holds the samples' average
+    escape count. The catch is that every sample runs to [`Renderer::max_iterations`] in the
+    pass that first dispatches its pixel, so the image no longer refines over several frames, as
+    with [`Renderer::set_incremental`]`(false)`, and passes take `samples_per_pixel` times as
+    long. Only [`compute::Precision::Single`]'s [`compute::FractalKind::Mandelbrot`] is
+    multisampled; anything else iterates one sample per pixel until it's switched back.
+    */
+    pub fn set_samples_per_pixel(&mut self, samples_per_pixel: u32) -> Result<(), RendererError> {
+        let samples_per_pixel = samples_per_pixel.max(1);
+        if samples_per_pixel == self.samples_per_pixel {
+            return Ok(());
+        }
+        if samples_per_pixel > 1 && !self.precision.supports_multisampling(self.fractal_kind) {
+            warn!("only single precision Mandelbrot sets are multisampled; using one sample per pixel");
+        }
+        let (compute_shader_module, compute_pipeline) = self.create_compute_shader(
+            self.precision,
+            self.workgroup_size,
+            samples_per_pixel,
+            self.fractal_kind,
+        )?;
+        debug!("samples per pixel set to {}", samples_per_pixel);
+
+        self.samples_per_pixel = samples_per_pixel;
+        self.compute_shader_module = compute_shader_module;
+        self.compute_pipeline = compute_pipeline;
+        self.reset_pending = true;
+        Ok(())
+    }
+
+    /**
+    Iterate a different fractal. Call [`Renderer::reset`] to discard the current one.
+
+    Nothing changes if the compute shader has no entry point for `fractal_kind`, like
+    [`compute::FractalKind::Newton`] at double precision.
+    */
+    pub fn set_fractal_kind(
+        &mut self,
+        fractal_kind: compute::FractalKind,
+    ) -> Result<(), RendererError> {
+        let compute_pipeline = validate(&self.device, || {
+            create_compute_pipeline(
+                &self.device,
+                &self.compute_pipeline_layout,
+                &self.compute_shader_module,
+                self.precision,
+                fractal_kind,
+                self.samples_per_pixel,
+            )
+        })?;
+        if let compute::FractalKind::Julia { c } | compute::FractalKind::Split { c } = fractal_kind
+        {
+            self.julia_c_buffer.write(&self.queue, c);
+        }
+        self.fractal_kind = fractal_kind;
+        self.compute_pipeline = compute_pipeline;
+        Ok(())
+    }
+
+    /**
+    Move the `c` of [`compute::FractalKind::Julia`] or [`compute::FractalKind::Split`] without
+    recreating the compute pipeline, so it can follow the cursor. The iterations so far are
+    discarded, including the other half's in [`compute::FractalKind::Split`].
+
+    Does nothing for the other fractals.
+    */
+    pub fn set_julia_c(&mut self, c: Complex) {
+        if let compute::FractalKind::Julia { c: current }
+        | compute::FractalKind::Split { c: current } = &mut self.fractal_kind
+        {
+            if *current != c {
+                *current = c;
+                self.julia_c_buffer.write(&self.queue, c);
+                self.reset_pending = true;
+            }
+        }
+    }
+
+    /**
+    Set the distance from zero at which a pixel is considered escaped.
+
+    Larger radii give smoother colouring. Iterations so far are discarded when it changes.
+    */
+    pub fn set_escape_radius(&mut self, escape_radius: f32) {
+        if escape_radius != self.escape_radius {
+            self.escape_radius = escape_radius;
+            self.uniforms.escape_radius = escape_radius;
+            self.write_uniforms();
+            self.histogram_colouring.set_escape_radius(escape_radius);
+            self.reset();
+        }
+    }
+
+    /**
+    Set the number of iterations after which a pixel is no longer iterated.
+
+    Raising it lets the unescaped pixels carry on from where they stopped, including those
+    that had reached the old maximum, without a reset. The newly reachable detail fades in over
+    the next frames as those pixels escape: they're added to the histogram as usual, and
+    [`HistogramColouring::with_temporal_smoothing`] eases the other pixels toward their shifted
+    colours.
+
+    Lowering it discards the iterations so far, since some pixels may already have escaped past
+    it, and their colours would be invalid.
+    */
+    pub fn set_max_iterations(&mut self, max_iterations: u32) {
+        if max_iterations != self.max_iterations {
+            let lowered = max_iterations < self.max_iterations;
+            self.max_iterations = max_iterations;
+            self.uniforms.max_iterations = max_iterations;
+            self.write_uniforms();
+            if lowered {
+                self.reset();
+            } else {
+                // The same centre gives the same orbit, only longer.
+                if self.is_perturbed() {
+                    self.update_reference_orbit();
+                }
+                self.pixels.resume_interior();
+                // Escape times are relative to the maximum, so every escaped pixel moves.
+                self.colours_pending |=
+                    self.heatmap || self.colouring_mode == colour::ColouringMode::EscapeTime;
+            }
+        }
+    }
+
+    /**
+    Set [`Renderer::max_iterations`] from the zoom with `auto_iterations` from now on, each time
+    [`Renderer::set_zoom`] settles on a new zoom, or stop with `None`. Setting the maximum by hand
+    only lasts until then.
+
+    Animated zooms through [`Renderer::write_zoom`] leave it alone, so that zooming out doesn't
+    reset the pixels every frame.
+    */
+    pub fn set_auto_iterations(&mut self, auto_iterations: Option<compute::AutoIterations>) {
+        self.auto_iterations = auto_iterations;
+        self.apply_auto_iterations();
+    }
+
+    /// Set [`Renderer::max_iterations`] for the current zoom, if [`Renderer::auto_iterations`] is on.
+    fn apply_auto_iterations(&mut self) {
+        if let Some(auto_iterations) = self.auto_iterations {
+            self.set_max_iterations(auto_iterations.max_iterations(self.zoom));
+        }
+    }
+
+    /**
+    Iterate `z^power + c` instead of `z^2 + c`, discarding the iterations so far.
+
+    `power` is clamped to `2..=`[`compute::MAX_POWER`].
+    */
+    pub fn set_power(&mut self, power: u32) {
+        let power = power.clamp(2, compute::MAX_POWER);
+        if power != self.power {
+            self.power = power;
+            self.uniforms.power = power;
+            self.write_uniforms();
+            self.histogram_colouring.set_power(power);
+            self.reset();
+        }
+    }
+
+    /**
+    Colour escaped pixels with a different algorithm.
+
+    Every pixel tracks what each mode needs as it's iterated, so this recolours
+    the pixels that have already escaped without iterating them again. The exception is switching
+    to or from [`colour::ColouringMode::GpuSmooth`], which keeps the pixels in a different order
+    on the GPU, and discards the iterations so far.
+
+    [`colour::ColouringMode::GpuSmooth`] falls back to histogram colouring at double precision.
+    */
+    pub fn set_colouring_mode(&mut self, colouring_mode: colour::ColouringMode) {
+        let gpu_smooth = colour::ColouringMode::GpuSmooth;
+        let colouring_mode =
+            if colouring_mode == gpu_smooth && self.precision == compute::Precision::Double {
+                warn!("GPU colouring needs single precision; using histogram colouring");
+                colour::ColouringMode::Histogram
+            } else {
+                colouring_mode
+            };
+
+        let layout_changed = (colouring_mode == gpu_smooth) != (self.colouring_mode == gpu_smooth);
+        self.colouring_mode = colouring_mode;
+        if layout_changed {
+            self.reset();
+        } else {
+            let dirty = self.colour_pixels();
+            self.write_colour_ranges(dirty);
+        }
+    }
+
+    /**
+    Colour pixels that never escape with `interior_colouring`.
+
+    Anything but [`colour::InteriorColouring::Flat`] iterates the main cardioid and period-2
+    bulb to the maximum iteration count instead of skipping them, so switching between flat and
+    shaded discards the iterations so far.
+    */
+    pub fn set_interior_colouring(&mut self, interior_colouring: colour::InteriorColouring) {
+        if interior_colouring == self.interior_colouring {
+            return;
+        }
+        let flat = colour::InteriorColouring::Flat;
+        let skips_changed = (interior_colouring == flat) != (self.interior_colouring == flat);
+
+        self.interior_colouring = interior_colouring;
+        self.interior_colouring_buffer
+            .write(&self.queue, interior_colouring.index());
+        if skips_changed {
+            self.reset();
+        } else {
+            let dirty = self.colour_interior();
+            self.write_colour_ranges(dirty);
+        }
+    }
+
+    /// Measure orbits against a different trap, discarding the iterations so far.
+    pub fn set_trap_shape(&mut self, trap_shape: compute::TrapShape) {
+        if trap_shape != self.trap_shape {
+            self.trap_shape = trap_shape;
+            self.trap_shape_buffer
+                .write(&self.queue, trap_shape.index());
+            self.reset();
+        }
+    }
+
+    /**
+    Iterate each unescaped pixel up to `iterations_per_frame` times in every [`Renderer::compute`].
+
+    Higher values finish heavy views in fewer frames, at the cost of longer compute passes. The
+    image still refines progressively, since pixels carry on from where they stopped. At least `1`.
+    */
+    pub fn set_iterations_per_frame(&mut self, iterations_per_frame: u32) {
+        self.iterations_per_frame = iterations_per_frame.max(1);
+        self.iterations_per_frame_buffer
+            .write(&self.queue, self.iterations_per_frame);
+    }
+
+    /**
+    Ease each pixel's shown colour toward the one it's just been given, moving `alpha` of the way
+    every frame, so that a batch of escapes doesn't visibly shift the whole image at once.
+
+    This eases the final colours uploaded to the GPU, whatever [`Renderer::colouring_mode`] is.
+    It's separate from [`HistogramColouring::with_temporal_smoothing`], which steadies the
+    histogram's positions before colours are computed from them. `alpha` is clamped to
+    `0.01..=1.0`, and defaults to `1.0`, which shows new colours at once.
+
+    Only frames from [`Renderer::compute`] are eased. [`Renderer::render_to_vec`] starts from a
+    reset, so nothing is eased in its images.
+    */
+    pub fn set_colour_smoothing(&mut self, alpha: f32) {
+        self.colour_smoothing = alpha.clamp(0.01, 1.0);
+        debug!("colour smoothing set to {}", self.colour_smoothing);
+        if self.colour_smoothing == 1.0 && !self.shown_colour_ranges.is_empty() {
+            // Catch up with colours that were still being eased.
+            self.shown_colour_ranges = Vec::new();
+            self.unsettled_colours = None;
+            self.write_colour_ranges(Some(0..self.colour_ranges.len()));
+        }
+    }
+
+    /**
+    Iterate each unescaped pixel a little more in every [`Renderer::compute`], carrying on from
+    where it stopped, or (with `false`) iterate every pixel from scratch to completion in each one.
+
+    Incremental iteration is what makes the image refine progressively, and after the first few
+    frames only the unescaped pixels are dispatched and copied back. Iterating from scratch skips
+    all of that bookkeeping, so it's a reference to check the incremental path against, and it can
+    be quicker for shallow views where most pixels escape within a few iterations. Each frame
+    takes as long as the whole view, though, so deep views become unresponsive.
+
+    Only [`colour::ColouringMode::GpuSmooth`] is unaffected, since it always iterates every pixel.
+    Not available on the web, which can't wait for the pixels to be copied back.
+    */
+    pub fn set_incremental(&mut self, incremental: bool) {
+        if !incremental && cfg!(target_arch = "wasm32") {
+            warn!("iterating from scratch isn't supported on the web");
+            return;
+        }
+        if incremental != self.incremental {
+            debug!("incremental iteration: {}", incremental);
+            self.incremental = incremental;
+            self.reset_pending = true;
+        }
+    }
+
+    /// The most [`Renderer::compute`] passes that can be needed to reach [`Renderer::max_iterations`].
+    fn max_passes(&self) -> u32 {
+        (self.max_iterations + self.iterations_per_frame - 1) / self.iterations_per_frame
+    }
+
+    /// Colour escaped pixels with `palette`. Takes effect on the next [`Renderer::render`], without recomputing anything.
+    pub fn set_palette(&mut self, palette: &dyn colour::Palette) {
+        self.palette = PaletteUniform::new(palette);
+        self.palette_buffer.write(&self.queue, self.palette);
+    }
+
+    /**
+    Shift where every pixel samples the palette, for animating it. Like [`Renderer::set_palette`],
+    nothing is recomputed.
+
+    `palette_offset` wraps around at `1.0`, which leaves the colours as they were at `0.0`.
+    */
+    pub fn set_palette_offset(&mut self, palette_offset: f32) {
+        self.palette_offset = palette_offset.rem_euclid(1.0);
+        self.palette_offset_buffer
+            .write(&self.queue, self.palette_offset);
+    }
+
+    /// Adjust the rendered colours. Like [`Renderer::set_palette`], nothing is recomputed.
+    pub fn set_post_processing(&mut self, post_processing: PostProcessing) {
+        self.post_processing = post_processing;
+        self.post_processing_buffer
+            .write(&self.queue, post_processing);
+    }
+
+    /**
+    Iterate `ssaa * ssaa` pixels for every one that's displayed, and average them when
+    rendering. Discards the iterations so far.
+
+    Every pixel buffer grows with the square of `ssaa`, so it's lowered until the pixels fit in
+    a single storage buffer binding.
+    */
+    pub fn set_ssaa(&mut self, ssaa: u32) {
+        let ssaa = ssaa.max(1);
+        if ssaa != self.ssaa {
+            self.ssaa = ssaa;
+            self.resize_buffers(self.display_size);
+            self.reset();
+        }
+    }
+
+    /**
+    Draw with `msaa` samples per pixel, resolved into the target. Only `1` (off) and `4` are
+    allowed, since those are the counts WebGPU guarantees, and `4` falls back to `1` when the
+    adapter can't multisample the target format.
+
+    The fractal is a single full-screen quad, so this doesn't change how it looks. It's for
+    geometry drawn in the render pass; [`Renderer::set_ssaa`] anti-aliases the fractal itself.
+    */
+    pub fn set_msaa(&mut self, msaa: u32) {
+        let msaa = if msaa == 1 || (msaa == 4 && self.msaa_supported) {
+            msaa
+        } else {
+            warn!(
+                "{}x MSAA isn't supported for {:?}; using no MSAA",
+                msaa, self.format
+            );
+            1
+        };
+        if msaa == self.msaa {
+            return;
+        }
+        debug!("MSAA set to {}x", msaa);
+
+        self.msaa = msaa;
+        self.recreate_render_pipelines();
+    }
+
+    /**
+    Target textures with `format` from now on, recreating the render pipelines if it's changed.
+    See [`Renderer::render_into`].
+
+    Whether the new format can be multisampled is taken from what WebGPU guarantees for it,
+    since the adapter isn't kept, so [`Renderer::msaa`] may fall back to `1`. [`Renderer::capture`]
+    renders to `format` too, and only reads back 8-bit RGBA or BGRA formats.
+    */
+    pub fn set_format(&mut self, format: wgpu::TextureFormat) {
+        if format == self.format {
+            return;
+        }
+        debug!("render target format set to {:?}", format);
+
+        self.format = format;
+        self.msaa_supported = format.describe().guaranteed_format_features.flags.contains(
+            wgpu::TextureFormatFeatureFlags::MULTISAMPLE
+                | wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE,
+        );
+        if self.msaa > 1 && !self.msaa_supported {
+            warn!(
+                "{}x MSAA isn't supported for {:?}; using no MSAA",
+                self.msaa, format
+            );
+            self.msaa = 1;
+        }
+        self.recreate_render_pipelines();
+    }
+
+    /// Recreate the render pipelines and MSAA texture for the current `format` and `msaa`.
+    fn recreate_render_pipelines(&mut self) {
+        self.render_pipeline = create_render_pipeline(
+            &self.device,
+            &self.render_pipeline_layout,
+            &self.render_shader_module,
+            self.format,
+            "fragment_main",
+            self.msaa,
+        );
+        self.gpu_smooth_render_pipeline = create_render_pipeline(
+            &self.device,
+            &self.render_pipeline_layout,
+            &self.render_shader_module,
+            self.format,
+            "fragment_gpu_smooth",
+            self.msaa,
+        );
+        self.msaa_texture_view = if self.msaa > 1 {
+            Some(create_msaa_texture_view(
+                &self.device,
+                self.format,
+                self.display_size,
+                self.msaa,
+            ))
+        } else {
+            None
+        };
+    }
+
+    /// The largest factor up to `ssaa` whose pixels fit in a storage buffer binding at `display_size`.
+    fn clamp_ssaa(&self, display_size: screen::Size, ssaa: u32) -> u32 {
+        let max_bytes = self.device.limits().max_storage_buffer_binding_size as u64;
+        let bytes = |ssaa: u32| {
+            let size = display_size.scale(ssaa);
+            // The larger of the two pixel types, so switching precision can't overflow.
+            size.width as u64 * size.height as u64 * size_of::<PixelF64>() as u64
+        };
+
+        let mut clamped = ssaa;
+        while clamped > 1 && bytes(clamped) > max_bytes {
+            clamped -= 1;
+        }
+        if clamped != ssaa {
+            warn!(
+                "{}x SSAA doesn't fit in {} bytes at {:?}; using {}x",
+                ssaa, max_bytes, display_size, clamped
+            );
+        }
+        clamped
+    }
+
+    /**
+    Recreate the buffers and bind groups that depend on the screen size, discarding the
+    iterations so far.
+
+    Does nothing if `display_size` hasn't changed.
+    */
+    pub fn resize(&mut self, display_size: screen::Size) {
+        if display_size == self.display_size {
+            return;
+        }
+        self.resize_buffers(display_size);
+        self.reset();
+    }
+
+    /// [`Renderer::resize`], without discarding the colours or histogram.
+    fn resize_buffers(&mut self, display_size: screen::Size) {
+        self.ssaa = self.clamp_ssaa(display_size, self.ssaa);
+        self.ssaa_buffer.write(&self.queue, self.ssaa);
+
+        let screen_size = display_size.scale(self.ssaa);
+        self.display_size = display_size;
+        self.screen_size = screen_size;
+
+        self.uniforms.screen_size = screen_size;
+        self.write_uniforms();
+        self.write_grid();
+
+        self.pixels.resize(&self.device, screen_size);
+        self.colour_ranges_buffer.resize(
+            &self.device,
+            screen_size.width as u64 * screen_size.height as u64,
+            None,
+        );
+
+        if self.msaa > 1 {
+            self.msaa_texture_view = Some(create_msaa_texture_view(
+                &self.device,
+                self.format,
+                display_size,
+                self.msaa,
+            ));
+        }
+
+        self.compute_bind_group_1 = self.create_compute_bind_group_1();
+        self.render_bind_group_1 = create_render_bind_group_1(
+            &self.device,
+            &self.render_pipeline,
+            &self.uniforms_buffer,
+            &self.palette_buffer,
+            &self.ssaa_buffer,
+            &self.post_processing_buffer,
+            &self.palette_offset_buffer,
+            &self.grid_buffer,
+            &self.dither_buffer,
+            &self.selection_buffer,
+        );
+    }
+
+    /// Discard all iterations and colours, e.g. after the view has changed.
+    pub fn reset(&mut self) {
+        self.reset_pending = false;
+        self.colours_pending = false;
+        self.gpu_iterations = 0;
+        self.colour_ranges.clear();
+        self.colour_ranges.extend(
+            std::iter::repeat(ColourRange::default())
+                .take((self.screen_size.width * self.screen_size.height) as usize),
+        );
+        self.histogram_colouring.reset();
+        // Later frames only upload the colours that change, so the old ones have to be cleared here.
+        self.write_colour_ranges(Some(0..self.colour_ranges.len()));
+
+        if self.is_perturbed() {
+            self.update_reference_orbit();
+        }
+        self.pixels.reset(&self.queue, self.screen_size);
+    }
+
+    /// Whether the compute shader iterates relative to a reference orbit. See [`compute::Precision::Perturbed`].
+    fn is_perturbed(&self) -> bool {
+        self.precision == compute::Precision::Perturbed
+            && matches!(self.fractal_kind, compute::FractalKind::Mandelbrot)
+    }
+
+    /**
+    Iterate a new [`ReferenceOrbit`] from the current origin, zoom and maximum iteration count,
+    for [`compute::Precision::Perturbed`].
+
+    `perturbation.wgsl` finds the orbit's end from the size of its buffer, so the buffer and
+    `compute_bind_group_1` are recreated whenever its length changes.
+    */
+    pub fn update_reference_orbit(&mut self) {
+        let start = Instant::now();
+        let orbit = ReferenceOrbit::new(self.origin, self.zoom, self.max_iterations);
+        trace!(
+            "reference orbit of {} values at {} bits took {:?}",
+            orbit.values.len(),
+            orbit::precision_bits(self.zoom),
+            start.elapsed()
+        );
+
+        let len = orbit.values.len() as u64;
+        if len != self.reference_orbit_buffer.len() {
+            self.reference_orbit_buffer.resize(&self.device, len, None);
+            self.compute_bind_group_1 = self.create_compute_bind_group_1();
+        }
+        self.reference_orbit_buffer
+            .write(&self.queue, &orbit.values);
+        self.reference_centre = orbit.centre;
+        self.write_origin(self.origin);
+    }
+
+    /**
+    How long the GPU spent in the last [`Renderer::compute`] and [`Renderer::render`] passes.
+
+    Blocks until the last render has finished. Returns `None` when the device doesn't support
+    [`wgpu::Features::TIMESTAMP_QUERY`].
+    */
+    pub fn last_frame_timings(&self) -> Option<FrameTimings> {
+        self.timestamps
+            .as_ref()
+            .map(|timestamps| timestamps.read(&self.device))
+    }
+
+    /// Call `frame_callback` with each frame's [`FrameStats`] from [`Renderer::finish_frame`].
+    pub fn set_frame_callback(&mut self, frame_callback: impl FnMut(FrameStats) + 'static) {
+        self.frame_callback = Some(Box::new(frame_callback));
+    }
+
+    /**
+    Mark the end of a frame, and pass its [`FrameStats`] to the frame callback if there is one.
+
+    Reading the GPU timings blocks until the frame's render has finished, so nothing is read
+    without a callback.
+    */
+    pub fn finish_frame(&mut self) {
+        let now = Instant::now();
+        let frame_time = self
+            .last_frame
+            .map_or(Duration::ZERO, |last_frame| now.duration_since(last_frame));
+        self.last_frame = Some(now);
+
+        if self.frame_callback.is_some() {
+            let frame_stats = FrameStats {
+                frame_time,
+                gpu_timings: self.last_frame_timings(),
+                unescaped: self.pixels.unescaped_len(),
+                colour_bytes_written: self.colour_bytes_written,
+            };
+            (self.frame_callback.as_mut().unwrap())(frame_stats);
+        }
+        self.colour_bytes_written = 0;
+    }
+
+    /**
+    How far the view has refined, from `0.0` to `1.0`: the fraction of pixels that have escaped or
+    reached [`Renderer::max_iterations`].
+
+    In [`colour::ColouringMode::GpuSmooth`] the pixels aren't read back, so it's the fraction of
+    [`Renderer::max_iterations`] that have run instead.
+    */
+    pub fn convergence(&self) -> f32 {
+        if self.colouring_mode == colour::ColouringMode::GpuSmooth {
+            return (self.gpu_iterations as f32 / self.max_iterations.max(1) as f32).min(1.0);
+        }
+        let total_pixels = self.screen_size.width as usize * self.screen_size.height as usize;
+        1.0 - self.pixels.unescaped_len() as f32 / total_pixels.max(1) as f32
+    }
+
+    /**
+    Whether [`Renderer::compute`] has nothing left to do: every pixel has escaped or reached
+    [`Renderer::max_iterations`], and the colours have caught up.
+
+    Scans the unescaped pixels, but stops at the first one that's still iterating.
+    */
+    pub fn is_converged(&self) -> bool {
+        if self.colouring_mode == colour::ColouringMode::GpuSmooth {
+            return !self.reset_pending && self.gpu_iterations >= self.max_iterations;
+        }
+        !self.reset_pending
+            && !self.colours_pending
+            && self.unsettled_colours.is_none()
+            && self.pixels.is_finished(self.max_iterations)
+    }
+
+    /**
+    Run one compute pass over the unescaped pixels, and recolour any that escaped.
+
+    In [`colour::ColouringMode::GpuSmooth`], every pixel stays on the GPU and is coloured by
+    [`Renderer::render`] instead.
+    */
+    pub fn compute(&mut self) {
+        if self.reset_pending {
+            self.reset();
+        }
+        if self.colouring_mode == colour::ColouringMode::GpuSmooth {
+            self.iterate_on_gpu();
+            return;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if !self.incremental {
+            self.compute_from_scratch();
+            return;
+        }
+        let screen_size = self.screen_size;
+
+        self.iterate();
+        let mut dirty = None;
+        if self.pixels.newly_escaped_len() > 0 {
+            // Always recorded, so switching to `ColouringMode::Histogram` doesn't need to iterate again.
+            self.pixels.record_escaped(&mut self.histogram_colouring);
+            // Too few samples make the histogram colours flicker, so wait for more. A frame where
+            // nothing escapes colours them regardless.
+            if self.colouring_mode == colour::ColouringMode::Histogram
+                && !self.histogram_colouring.is_ready(self.colour_ranges.len())
+            {
+                self.colours_pending = true;
+            } else {
+                dirty = self.colour_pixels();
+            }
+        } else if self.colours_pending {
+            dirty = self.colour_pixels();
+        }
+        if self.pixels.newly_interior_len() > 0 {
+            dirty = colour::merge_dirty(dirty, self.colour_interior());
+        }
+        debug_assert!(
+            self.colour_ranges.len() == screen_size.width as usize * screen_size.height as usize,
+            "colour_ranges.len() == {}, expected {}",
+            self.colour_ranges.len(),
+            screen_size.width * screen_size.height,
+        );
+
+        self.write_colour_ranges(dirty);
+    }
+
+    /**
+    Run compute passes until the view has converged (see [`Renderer::is_converged`]), or for at
+    most `max_frames` passes, and colour the pixels once at the end. Returns the number of passes.
+
+    Unlike calling [`Renderer::compute`] each frame, nothing is coloured from a partial histogram,
+    so the same view always comes out the same, e.g. for screenshots and comparing images.
+    */
+    pub fn iterate_until_converged(&mut self, max_frames: u32) -> u32 {
+        if self.reset_pending {
+            self.reset();
+        }
+        let mut passes = 0;
+        if self.colouring_mode == colour::ColouringMode::GpuSmooth {
+            while passes < max_frames && self.gpu_iterations < self.max_iterations {
+                self.iterate_on_gpu();
+                passes += 1;
+            }
+            return passes;
+        }
+
+        while passes < max_frames && !self.pixels.is_finished(self.max_iterations) {
+            self.iterate();
+            if self.pixels.newly_escaped_len() > 0 {
+                self.pixels.record_escaped(&mut self.histogram_colouring);
+            }
+            passes += 1;
+        }
+        let dirty = colour::merge_dirty(self.colour_pixels(), self.colour_interior());
+        self.write_colour_ranges(dirty);
+        debug!("iterated until converged in {} passes", passes);
+        passes
+    }
+
+    /**
+    [`Renderer::compute`] when not [`Renderer::incremental`]: iterate every pixel from its
+    start to [`Renderer::max_iterations`] on the GPU, copy them all back once, and colour them.
+    */
+    #[cfg(not(target_arch = "wasm32"))]
+    fn compute_from_scratch(&mut self) {
+        self.histogram_colouring.reset();
+        self.pixels.reset(&self.queue, self.screen_size);
+        for _ in 0..self.max_passes() {
+            self.iterate_on_gpu();
+        }
+        self.pixels.receive_all(
+            &self.device,
+            &self.queue,
+            self.screen_size,
+            self.max_iterations,
+        );
+
+        if self.pixels.newly_escaped_len() > 0 {
+            self.pixels.record_escaped(&mut self.histogram_colouring);
+        }
+        // Pixels that escaped last time might not have this time, if the view moved.
+        self.colour_ranges.fill(ColourRange::default());
+        self.colour_pixels();
+        self.colour_interior();
+        self.write_colour_ranges(Some(0..self.colour_ranges.len()));
+    }
+
+    /// Upload the `dirty` range of [`Renderer::colour_ranges`] to the GPU, if there is one.
+    fn write_colour_ranges(&mut self, dirty: Option<Range<usize>>) {
+        if self.colour_smoothing < 1.0 {
+            self.write_eased_colour_ranges(dirty);
+            return;
+        }
+        if let Some(dirty) = dirty {
+            trace!("writing colour_ranges[{:?}]", dirty);
+            self.colour_bytes_written += (dirty.len() * size_of::<ColourRange>()) as u64;
+            self.colour_ranges_buffer.write_range(
+                &self.queue,
+                dirty.start as u64,
+                &self.colour_ranges[dirty],
+            );
+        }
+    }
+
+    /**
+    [`Renderer::write_colour_ranges`] with [`Renderer::colour_smoothing`]: ease what's shown of
+    `dirty`, and of anything that hadn't settled before, toward [`Renderer::colour_ranges`], and
+    upload that.
+    */
+    fn write_eased_colour_ranges(&mut self, dirty: Option<Range<usize>>) {
+        // Smoothing was just turned on, or the screen was resized, so there's nothing to ease from.
+        if self.shown_colour_ranges.len() != self.colour_ranges.len() {
+            self.shown_colour_ranges = self.colour_ranges.clone();
+            self.unsettled_colours = None;
+        }
+        let range = match colour::merge_dirty(dirty, self.unsettled_colours.take()) {
+            Some(range) => range,
+            None => return,
+        };
+        self.unsettled_colours = colour::ease_colour_ranges(
+            &mut self.shown_colour_ranges[range.clone()],
+            &self.colour_ranges[range.clone()],
+            self.colour_smoothing,
+        )
+        .map(|unsettled| range.start + unsettled.start..range.start + unsettled.end);
+
+        trace!("writing eased colour_ranges[{:?}]", range);
+        self.colour_bytes_written += (range.len() * size_of::<ColourRange>()) as u64;
+        self.colour_ranges_buffer.write_range(
+            &self.queue,
+            range.start as u64,
+            &self.shown_colour_ranges[range],
+        );
+    }
+
+    /**
+    Colour every escaped pixel with [`Renderer::colouring_mode`], or with [`HeatmapColouring`]
+    if [`Renderer::heatmap`] is on.
+
+    Returns the range of [`Renderer::colour_ranges`] that changed.
+    */
+    fn colour_pixels(&mut self) -> Option<Range<usize>> {
+        if self.heatmap {
+            self.colours_pending = false;
+            return self
+                .pixels
+                .colour_heatmap(self.max_iterations, &mut self.colour_ranges);
+        }
+        match self.colouring_mode {
+            colour::ColouringMode::Histogram => {
+                self.histogram_colouring.update_ranges();
+                let dirty = self
+                    .pixels
+                    .colour(&self.histogram_colouring, &mut self.colour_ranges);
+                // Temporally smoothed positions take a few more frames to reach their targets.
+                self.colours_pending =
+                    dirty.is_some() && self.histogram_colouring.is_temporally_smoothed();
+                dirty
+            }
+            colour::ColouringMode::OrbitTrap => {
+                self.colours_pending = false;
+                self.pixels
+                    .colour_orbit_traps(&self.orbit_trap_colouring, &mut self.colour_ranges)
+            }
+            colour::ColouringMode::Distance => {
+                self.colours_pending = false;
+                self.pixels
+                    .colour_distances(&self.distance_colouring, &mut self.colour_ranges)
+            }
+            colour::ColouringMode::EscapeTime => {
+                self.colours_pending = false;
+                self.pixels
+                    .colour_escape_times(self.max_iterations, &mut self.colour_ranges)
+            }
+            colour::ColouringMode::Root => {
+                self.colours_pending = false;
+                self.pixels
+                    .colour_roots(&self.root_colouring, &mut self.colour_ranges)
+            }
+            // Coloured in `render.wgsl`, so `colour_ranges` isn't used.
+            colour::ColouringMode::GpuSmooth => None,
+        }
+    }
+
+    /**
+    Colour the pixels that reached [`Renderer::max_iterations`] with
+    [`Renderer::interior_colouring`], or with the heatmap if it's on.
+
+    Returns the range of [`Renderer::colour_ranges`] that changed.
+    */
+    fn colour_interior(&mut self) -> Option<Range<usize>> {
+        if self.heatmap {
+            self.pixels
+                .colour_heatmap(self.max_iterations, &mut self.colour_ranges)
+        } else {
+            self.pixels.colour_interior(
+                self.interior_colouring,
+                self.max_iterations,
+                &mut self.colour_ranges,
+            )
+        }
+    }
+
+    /// Run one compute pass over every pixel, leaving them on the GPU. See [`Pixels::iterate_on_gpu`].
+    fn iterate_on_gpu(&mut self) {
+        let timestamps = self
+            .timestamps
+            .as_ref()
+            .map(|timestamps| timestamps.pass(COMPUTE_TIMESTAMPS));
+
+        match &mut self.pixels {
+            PrecisionPixels::Single(pixels) => pixels.iterate_on_gpu(
+                &self.device,
+                &self.queue,
+                &self.compute_pipeline,
+                &self.compute_bind_group_1,
+                &self.compute_bind_group_layout_2,
+                self.workgroup_size,
+                timestamps,
+            ),
+            PrecisionPixels::Double(pixels) => pixels.iterate_on_gpu(
+                &self.device,
+                &self.queue,
+                &self.compute_pipeline,
+                &self.compute_bind_group_1,
+                &self.compute_bind_group_layout_2,
+                self.workgroup_size,
+                timestamps,
+            ),
+        }
+        self.gpu_iterations = self
+            .gpu_iterations
+            .saturating_add(self.iterations_per_frame);
+    }
+
+    /// Run one compute pass over the unescaped pixels, without colouring them.
+    fn iterate(&mut self) {
+        let timestamps = self
+            .timestamps
+            .as_ref()
+            .map(|timestamps| timestamps.pass(COMPUTE_TIMESTAMPS));
+
+        match &mut self.pixels {
+            PrecisionPixels::Single(pixels) => pixels.compute(
+                &self.device,
+                &self.queue,
+                &self.compute_pipeline,
+                &self.compute_bind_group_1,
+                &self.compute_bind_group_layout_2,
+                self.workgroup_size,
+                self.screen_size,
+                self.max_iterations,
+                timestamps,
+            ),
+            PrecisionPixels::Double(pixels) => pixels.compute(
+                &self.device,
+                &self.queue,
+                &self.compute_pipeline,
+                &self.compute_bind_group_1,
+                &self.compute_bind_group_layout_2,
+                self.workgroup_size,
+                self.screen_size,
+                self.max_iterations,
+                timestamps,
+            ),
+        }
+    }
+
+    /**
+    Draw the current colours to the next texture of `surface` and present it. `overlay` can draw
+    anything else on top first, like a HUD.
+
+    Errors from acquiring the texture are returned without drawing anything. For
+    [`wgpu::SurfaceError::Outdated`] and [`wgpu::SurfaceError::Lost`], reconfigure the surface
+    and try again next frame.
+    */
+    pub fn render_frame(
+        &self,
+        surface: &wgpu::Surface,
+        overlay: impl FnOnce(&Self, &wgpu::TextureView),
+    ) -> Result<(), wgpu::SurfaceError> {
+        let surface_texture = surface.get_current_texture()?;
+        let view = surface_texture
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        self.render(&view);
+        overlay(self, &view);
+
+        surface_texture.present();
+        Ok(())
+    }
+
+    /**
+    Draw the current colours to `view`, a texture with `format` owned by the caller, e.g. a panel
+    in a larger application rather than a window's surface.
+
+    The render pipelines are rebuilt for `format` when it differs from the last one drawn to (see
+    [`Renderer::set_format`]), so switching between targets of different formats every frame is
+    slow. `view` should be [`Renderer::display_size`]; call [`Renderer::resize`] first if not.
+    */
+    pub fn render_into(&mut self, view: &wgpu::TextureView, format: wgpu::TextureFormat) {
+        self.set_format(format);
+        self.render(view);
+    }
+
+    /// Draw the current colours to `view`, which must have [`Renderer::format`].
+    pub fn render(&self, view: &wgpu::TextureView) {
+        let render_pipeline = if self.colouring_mode == colour::ColouringMode::GpuSmooth {
+            &self.gpu_smooth_render_pipeline
+        } else {
+            &self.render_pipeline
+        };
+        let render_bind_group_2 = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("render-bind-group-2"),
+            layout: &self.render_pipeline.get_bind_group_layout(1),
+            entries: &[
+                // render.wgsl#colour_ranges
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.colour_ranges_buffer.binding_resource(0, None),
+                },
+                // render.wgsl#pixels
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.pixels.binding_resource(),
+                },
+            ],
+        });
+
+        let render_command_buffer = command_buffer::create(
+            &self.device,
+            &wgpu::CommandEncoderDescriptor::default(),
+            |command_encoder| {
+                command_encoder.push_debug_group("render-pass");
+                command_encoder.with_timestamps(
+                    self.timestamps
+                        .as_ref()
+                        .map(|timestamps| timestamps.pass(RENDER_TIMESTAMPS)),
+                    |command_encoder| {
+                        command_encoder.with_render_pass(
+                            &wgpu::RenderPassDescriptor {
+                                label: Some("render-pass"),
+                                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                                    view: self.msaa_texture_view.as_ref().unwrap_or(view),
+                                    resolve_target: self.msaa_texture_view.as_ref().map(|_| view),
+                                    ops: wgpu::Operations {
+                                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                                            r: 0.5,
+                                            g: 0.5,
+                                            b: 0.0,
+                                            a: 1.0,
+                                        }),
+                                        store: true,
+                                    },
+                                })],
+                                depth_stencil_attachment: None,
+                            },
+                            |render_pass| {
+                                render_pass.set_pipeline(render_pipeline);
+                                render_pass.set_bind_group(0, &self.render_bind_group_1, &[]);
+                                render_pass.set_bind_group(1, &render_bind_group_2, &[]);
+                                render_pass.draw(0..4, 0..1);
+                            },
+                        );
+                    },
+                );
+                command_encoder.pop_debug_group();
+
+                if let Some(timestamps) = &self.timestamps {
+                    timestamps.resolve(command_encoder);
+                }
+            },
+        );
+
+        trace!("submitting render commands");
+        self.queue.submit([render_command_buffer]);
+    }
+
+    /**
+    Render the view at `origin` with `zoom`, and return it as tightly-packed RGBA8 rows.
+
+    Runs compute passes until every pixel has escaped or reached [`Renderer::max_iterations`]; see
+    [`Renderer::iterate_until_converged`].
+    */
+    pub fn render_to_vec(&mut self, origin: ComplexF64, zoom: f64) -> Vec<u8> {
+        self.write_origin(origin);
+        self.write_zoom(zoom);
+        self.apply_auto_iterations();
+        self.reset();
+        self.iterate_until_converged(self.max_passes());
+        self.capture()
+    }
+
+    /**
+    Render the current view at `total` size, in tiles of at most `tile` pixels, and stitch them
+    into one image. For images larger than the GPU's buffer and texture limits.
+
+    Colours depend on the histogram of the whole image, so each tile is iterated twice: first to
+    build the histogram, and then to colour the tile with it. The renderer is reset afterwards.
+
+    Sizes are in output pixels, so each tile iterates `ssaa * ssaa` times as many.
+    [`colour::ColouringMode::GpuSmooth`] tiles are coloured by histogram, since the pixels are
+    iterated on the CPU's terms here.
+    */
+    pub fn render_tiled(&mut self, total: screen::Size, tile: screen::Size) -> image::RgbaImage {
+        let display_size = self.display_size;
+        let colouring_mode = self.colouring_mode;
+        if colouring_mode == colour::ColouringMode::GpuSmooth {
+            self.colouring_mode = colour::ColouringMode::Histogram;
+        }
+        // The grid would be drawn over each tile separately.
+        let grid = self.grid;
+        self.set_grid(false);
+
+        let tiles: Vec<(u32, u32, screen::Size)> = (0..total.height)
+            .step_by(tile.height as usize)
+            .flat_map(|y| {
+                (0..total.width).step_by(tile.width as usize).map(move |x| {
+                    (
+                        x,
+                        y,
+                        // Tiles at the right and bottom edges may be cut short.
+                        screen::Size {
+                            width: tile.width.min(total.width - x),
+                            height: tile.height.min(total.height - y),
+                        },
+                    )
+                })
+            })
+            .collect();
+
+        self.histogram_colouring.reset();
+        for &(x, y, tile_size) in &tiles {
+            debug!("building histogram for tile at ({}, {})", x, y);
+            self.iterate_tile(total, x, y, tile_size, |renderer| {
+                renderer
+                    .pixels
+                    .record_escaped(&mut renderer.histogram_colouring)
+            });
+        }
+        self.histogram_colouring.update_ranges();
+
+        let mut image = image::RgbaImage::new(total.width, total.height);
+        for &(x, y, tile_size) in &tiles {
+            debug!("colouring tile at ({}, {})", x, y);
+            self.iterate_tile(total, x, y, tile_size, |_| {});
+
+            // `render.wgsl` uses `screen_size` to index `colour_ranges`, so it needs the tile's size.
+            self.uniforms.screen_size = self.screen_size;
+            self.write_uniforms();
+            self.colour_ranges.clear();
+            self.colour_ranges.extend(
+                std::iter::repeat(ColourRange::default())
+                    .take((self.screen_size.width * self.screen_size.height) as usize),
+            );
+            self.colour_pixels();
+            self.colour_interior();
+            self.colour_ranges_buffer
+                .write(&self.queue, &self.colour_ranges);
+
+            let tile_image =
+                image::RgbaImage::from_raw(tile_size.width, tile_size.height, self.capture())
+                    .unwrap();
+            image::imageops::replace(&mut image, &tile_image, x as i64, y as i64);
+        }
+
+        self.tile_offset_buffer.write(&self.queue, [0, 0]);
+        self.colouring_mode = colouring_mode;
+        self.resize(display_size);
+        self.uniforms.screen_size = self.screen_size;
+        self.write_uniforms();
+        self.set_grid(grid);
+        self.reset();
+
+        image
+    }
+
+    /**
+    Iterate the `tile_size` tile at `(x, y)` of a `total`-sized view to completion, calling
+    `on_iteration` after each compute pass.
+
+    Leaves the renderer's buffers sized to the tile, with `uniforms.screen_size` holding `total`.
+    All sizes and coordinates are in output pixels, before scaling by `ssaa`.
+    */
+    fn iterate_tile(
+        &mut self,
+        total: screen::Size,
+        x: u32,
+        y: u32,
+        tile_size: screen::Size,
+        mut on_iteration: impl FnMut(&mut Self),
+    ) {
+        if tile_size != self.display_size {
+            self.resize_buffers(tile_size);
+        }
+        // Pixel coordinates are mapped to the plane relative to the whole view.
+        self.uniforms.screen_size = total.scale(self.ssaa);
+        self.write_uniforms();
+        self.tile_offset_buffer
+            .write(&self.queue, [x * self.ssaa, y * self.ssaa]);
+        self.pixels.reset(&self.queue, self.screen_size);
+
+        for _ in 0..self.max_passes() {
+            if self.pixels.unescaped_len() == 0 {
+                break;
+            }
+            self.iterate();
+            on_iteration(self);
+        }
+    }
+
+    /**
+    Draw the current colours to an offscreen texture, and return them as tightly-packed RGBA8 rows.
+
+    The texture has the same [`Renderer::format`] as the window and goes through the same render
+    pipeline, so the palette, its offset, the post-processing, the dithering and any overlays drawn
+    by `render.wgsl` come out exactly as they're shown. Anything drawn over the top afterwards,
+    like the HUD, isn't included.
+
+    Panics unless the format is 8-bit RGBA or BGRA, the only ones that can be copied out as is.
+    */
+    pub fn capture(&self) -> Vec<u8> {
+        assert!(
+            matches!(
+                self.format,
+                wgpu::TextureFormat::Rgba8Unorm
+                    | wgpu::TextureFormat::Rgba8UnormSrgb
+                    | wgpu::TextureFormat::Bgra8Unorm
+                    | wgpu::TextureFormat::Bgra8UnormSrgb
+            ),
+            "can't capture {:?} as RGBA8",
+            self.format
+        );
+        let extent = wgpu::Extent3d {
+            width: self.display_size.width,
+            height: self.display_size.height,
+            depth_or_array_layers: 1,
+        };
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("capture-texture"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        });
+        self.render(&texture.create_view(&wgpu::TextureViewDescriptor::default()));
+        let output = self.read_texture(&texture);
+        texture.destroy();
+        output
+    }
+
+    /// Copy `texture`, which must be [`Renderer::display_size`] and [`Renderer::format`], out as
+    /// tightly-packed RGBA8 rows.
+    fn read_texture(&self, texture: &wgpu::Texture) -> Vec<u8> {
+        let extent = wgpu::Extent3d {
+            width: self.display_size.width,
+            height: self.display_size.height,
+            depth_or_array_layers: 1,
+        };
+
+        /*
+        Rows copied out of a texture must start on a multiple of `COPY_BYTES_PER_ROW_ALIGNMENT`,
+        so each row in `output_buffer` can be followed by some padding.
+        */
+        let bytes_per_row = self.display_size.width * 4;
+        let padded_bytes_per_row = (bytes_per_row + wgpu::COPY_BYTES_PER_ROW_ALIGNMENT - 1)
+            / wgpu::COPY_BYTES_PER_ROW_ALIGNMENT
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let output_buffer: typed_buffer::Buffer<u8> = typed_buffer::Builder::new(
+            padded_bytes_per_row as u64 * self.display_size.height as u64,
+        )
+        .with_label("capture-output-buffer")
+        .with_usage(wgpu::BufferUsages::MAP_READ)
+        .create(&self.device);
+
+        let copy_command_buffer = command_buffer::create(
+            &self.device,
+            &wgpu::CommandEncoderDescriptor::default(),
+            |command_encoder| {
+                command_encoder.copy_texture_to_buffer(
+                    texture.as_image_copy(),
+                    wgpu::ImageCopyBuffer {
+                        buffer: output_buffer.buffer(),
+                        layout: wgpu::ImageDataLayout {
+                            offset: 0,
+                            bytes_per_row: std::num::NonZeroU32::new(padded_bytes_per_row),
+                            rows_per_image: None,
+                        },
+                    },
+                    extent,
+                );
+            },
+        );
+        self.queue.submit([copy_command_buffer]);
+
+        let mut output = Vec::with_capacity((bytes_per_row * self.display_size.height) as usize);
+        {
+            let output_buffer_view = output_buffer.map_read_blocking(&self.device);
+            for row in output_buffer_view.chunks(padded_bytes_per_row as usize) {
+                output.extend_from_slice(&row[..bytes_per_row as usize]);
+            }
+        }
+
+        output_buffer.unmap();
+        output_buffer.destroy();
+
+        // Surfaces often prefer BGRA.
+        if matches!(
+            self.format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        ) {
+            for pixel in output.chunks_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        output
+    }
+}
+
+/// Any adapter for GPU tests, or `None` where there's no GPU, in which case they return early.
+#[cfg(all(test, not(target_arch = "wasm32")))]
+pub(crate) fn test_adapter() -> Option<wgpu::Adapter> {
+    let instance = wgpu::Instance::new(wgpu::Backends::all());
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: Default::default(),
+        force_fallback_adapter: false,
+        compatible_surface: None,
+    }));
+    if adapter.is_none() {
+        eprintln!("skipping: no adapter available");
+    }
+    adapter
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    const SIZE: screen::Size = screen::Size {
+        width: 16,
+        height: 16,
+    };
+
+    fn renderer(adapter: &wgpu::Adapter, size: screen::Size) -> Renderer {
+        Renderer::new(adapter, HEADLESS_FORMAT, size, ComplexF64::ZERO, 1.0).unwrap()
+    }
+
+    #[test]
+    fn broken_shader_is_a_compile_error() {
+        let adapter = match test_adapter() {
+            Some(adapter) => adapter,
+            None => return,
+        };
+        let mut renderer = renderer(&adapter, SIZE);
+
+        // The real shaders, except for a render shader that doesn't parse.
+        let dir = std::env::temp_dir().join(format!(
+            "wgpu-mandelbrot-broken-shader-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        for entry in std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/src")).unwrap() {
+            let path = entry.unwrap().path();
+            if path
+                .extension()
+                .map_or(false, |extension| extension == "wgsl")
+            {
+                std::fs::copy(&path, dir.join(path.file_name().unwrap())).unwrap();
+            }
+        }
+        std::fs::write(dir.join(shader::RENDER), "fn fragment_main( {").unwrap();
+
+        let result = renderer.set_shader_dir(Some(dir.clone()));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            matches!(result, Err(RendererError::ShaderCompile(_))),
+            "{:?}",
+            result
+        );
+        // The embedded shaders are kept.
+        assert_eq!(renderer.shader_dir, None);
+    }
+
+    /// `renderer`'s pixels, which are at single precision unless it's been changed.
+    fn single_pixels(renderer: &Renderer) -> &Pixels<Pixel> {
+        match &renderer.pixels {
+            PrecisionPixels::Single(pixels) => pixels,
+            PrecisionPixels::Double(_) => panic!("expected single precision"),
+        }
+    }
+
+    /// A renderer that has iterated the whole set to convergence at `max_iterations`.
+    fn converged(adapter: &wgpu::Adapter, max_iterations: u32) -> Renderer {
+        let mut renderer = renderer(adapter, SIZE);
+        renderer.set_colouring_mode(colour::ColouringMode::Histogram);
+        renderer.set_max_iterations(max_iterations);
+        renderer.reset();
+        renderer.iterate_until_converged(renderer.max_passes());
+        assert!(renderer.pixels.is_finished(max_iterations));
+        renderer
+    }
+
+    #[test]
+    fn raising_max_iterations_resumes() {
+        let adapter = match test_adapter() {
+            Some(adapter) => adapter,
+            None => return,
+        };
+        let mut renderer = converged(&adapter, 50);
+        let before = single_pixels(&renderer).all.clone();
+        let capped = before
+            .iter()
+            .filter(|pixel| pixel.is_iterating() && pixel.iteration_count == 50)
+            .count();
+        assert!(capped > 0);
+
+        renderer.set_max_iterations(100);
+        // Only the capped pixels carry on, and the escaped ones keep their counts.
+        assert_eq!(renderer.pixels.unescaped_len(), capped);
+        assert!(!renderer.pixels.is_finished(100));
+        for (pixel, previous) in single_pixels(&renderer).all.iter().zip(&before) {
+            assert_eq!(pixel.escaped, previous.escaped);
+            assert_eq!(pixel.iteration_count, previous.iteration_count);
+        }
+
+        renderer.iterate_until_converged(renderer.max_passes());
+        let after = &single_pixels(&renderer).all;
+        assert!(after
+            .iter()
+            .zip(&before)
+            .all(|(pixel, previous)| pixel.iteration_count >= previous.iteration_count));
+        assert!(after
+            .iter()
+            .any(|pixel| pixel.is_escaped() && pixel.iteration_count > 50));
+    }
+
+    #[test]
+    fn lowering_max_iterations_resets() {
+        let adapter = match test_adapter() {
+            Some(adapter) => adapter,
+            None => return,
+        };
+        let mut renderer = converged(&adapter, 50);
+
+        renderer.set_max_iterations(20);
+        // Every pixel starts again from nothing.
+        let pixels = single_pixels(&renderer);
+        assert_eq!(pixels.unescaped.len(), pixels.all.len());
+        assert!(pixels
+            .all
+            .iter()
+            .all(|pixel| pixel.is_iterating() && pixel.iteration_count == 0));
+    }
+
+    #[test]
+    fn capture_matches_the_screen() {
+        let adapter = match test_adapter() {
+            Some(adapter) => adapter,
+            None => return,
+        };
+        // Not a multiple of `COPY_BYTES_PER_ROW_ALIGNMENT`, so the rows are padded when copied out.
+        let size = screen::Size {
+            width: 37,
+            height: 21,
+        };
+        let mut renderer = renderer(&adapter, size);
+        renderer.set_dither(true);
+        renderer.set_grid(true);
+        renderer.iterate_until_converged(renderer.max_passes());
+
+        // What `render_frame` draws to a surface texture, with `COPY_SRC` to read it back.
+        let screen = renderer.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("screen-texture"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HEADLESS_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        });
+        renderer.render(&screen.create_view(&wgpu::TextureViewDescriptor::default()));
+        let on_screen = renderer.read_texture(&screen);
+
+        assert_eq!(renderer.capture(), on_screen);
+    }
+
+    #[test]
+    fn uniform_writes_reach_the_gpu() {
+        let adapter = match test_adapter() {
+            Some(adapter) => adapter,
+            None => return,
+        };
+        let mut renderer = renderer(&adapter, SIZE);
+        renderer.set_max_iterations(123);
+
+        let uniforms = renderer
+            .uniforms_buffer
+            .read(&renderer.device, &renderer.queue);
+        assert_eq!(uniforms, renderer.uniforms);
+        assert_eq!(uniforms.max_iterations, 123);
+    }
+
+    #[test]
+    fn new_headless_needs_an_adapter() {
+        let result = Renderer::new_headless(SIZE);
+        match test_adapter() {
+            Some(_) => assert_eq!(result.unwrap().display_size, SIZE),
+            None => assert!(matches!(result, Err(RendererError::NoAdapter))),
+        }
+    }
+}
@@ -1,11 +1,272 @@
 //! Screen data.
 
+use std::{
+    fmt,
+    num::ParseIntError,
+    ops::{Add, Mul, Sub},
+    str::FromStr,
+};
+
 use bytemuck::{Pod, Zeroable};
 
+use crate::pixel::{self, ComplexF64};
+
 /// [`bytemuck`]-compatible screen size.
 #[repr(C)]
-#[derive(Pod, Zeroable, Clone, Copy, Debug)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Size {
     pub width: u32,
     pub height: u32,
 }
+
+impl Size {
+    /// Both dimensions multiplied by `factor`.
+    pub fn scale(self, factor: u32) -> Self {
+        Size {
+            width: self.width * factor,
+            height: self.height * factor,
+        }
+    }
+
+    /// Width divided by height.
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+}
+
+/// Why a string couldn't be parsed as a [`Size`].
+#[derive(Debug)]
+pub enum ParseSizeError {
+    /// There was no `x` between the width and height.
+    Format,
+    Int(ParseIntError),
+    /// The width or height was zero.
+    Zero,
+}
+
+impl fmt::Display for ParseSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseSizeError::Format => write!(f, "expected `WIDTHxHEIGHT`, e.g. `1920x1080`"),
+            ParseSizeError::Int(err) => write!(f, "{}", err),
+            ParseSizeError::Zero => write!(f, "width and height must be greater than zero"),
+        }
+    }
+}
+
+impl std::error::Error for ParseSizeError {}
+
+impl From<ParseIntError> for ParseSizeError {
+    fn from(err: ParseIntError) -> Self {
+        ParseSizeError::Int(err)
+    }
+}
+
+impl FromStr for Size {
+    type Err = ParseSizeError;
+
+    /// Parse `WIDTHxHEIGHT`, e.g. `1920x1080`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = s.trim().split_once('x').ok_or(ParseSizeError::Format)?;
+        let size = Size {
+            width: width.parse()?,
+            height: height.parse()?,
+        };
+        if size.width == 0 || size.height == 0 {
+            return Err(ParseSizeError::Zero);
+        }
+        Ok(size)
+    }
+}
+
+/**
+The part of the complex plane covered by a view, from its top left corner `min` to its bottom
+right corner `max`.
+
+Its aspect ratio matches the screen's, so pixels are square in complex units. The compute shader
+derives the same rectangle from the `origin`, `zoom` and `screen_size` uniforms.
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewRect {
+    pub min: ComplexF64,
+    pub max: ComplexF64,
+}
+
+impl ViewRect {
+    /// The rectangle a `size` screen shows, centred on `origin` at `zoom`. See [`pixel::pixel_size`].
+    pub fn new(origin: ComplexF64, zoom: f64, size: Size) -> Self {
+        let pixel_size = pixel::pixel_size(size, zoom);
+        let half_width = size.width as f64 / 2.0 * pixel_size;
+        let half_height = size.height as f64 / 2.0 * pixel_size;
+        ViewRect {
+            min: ComplexF64 {
+                real: origin.real - half_width,
+                imaginary: origin.imaginary - half_height,
+            },
+            max: ComplexF64 {
+                real: origin.real + half_width,
+                imaginary: origin.imaginary + half_height,
+            },
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max.real - self.min.real
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max.imaginary - self.min.imaginary
+    }
+
+    /// Whether `point` is inside the rectangle, including its edges.
+    pub fn contains(&self, point: ComplexF64) -> bool {
+        (self.min.real..=self.max.real).contains(&point.real)
+            && (self.min.imaginary..=self.max.imaginary).contains(&point.imaginary)
+    }
+
+    /// The point under `position` on a `size` screen showing this rectangle.
+    pub fn point_at(&self, position: Vec2, size: Size) -> ComplexF64 {
+        ComplexF64 {
+            real: self.min.real + position.x as f64 / size.width as f64 * self.width(),
+            imaginary: self.min.imaginary + position.y as f64 / size.height as f64 * self.height(),
+        }
+    }
+
+    /// The inverse of [`ViewRect::point_at`]: where `point` is on the screen, in pixels.
+    pub fn position_of(&self, point: ComplexF64, size: Size) -> (f64, f64) {
+        (
+            (point.real - self.min.real) / self.width() * size.width as f64,
+            (point.imaginary - self.min.imaginary) / self.height() * size.height as f64,
+        )
+    }
+}
+
+/// [`bytemuck`]-compatible screen position or offset, in pixels from the top left.
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Prints `(x, y)`, passing the precision on to both, e.g. `{:.1}` for `(12.0, 34.5)`.
+impl fmt::Display for Vec2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match f.precision() {
+            Some(precision) => write!(f, "({:.*}, {:.*})", precision, self.x, precision, self.y),
+            None => write!(f, "({}, {})", self.x, self.y),
+        }
+    }
+}
+
+impl Vec2 {
+    pub const ZERO: Self = Vec2 { x: 0.0, y: 0.0 };
+
+    pub fn length(self) -> f32 {
+        self.x.hypot(self.y)
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Vec2 {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Vec2 {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+}
+
+impl Mul<f32> for Vec2 {
+    type Output = Self;
+
+    fn mul(self, factor: f32) -> Self {
+        Vec2 {
+            x: self.x * factor,
+            y: self.y * factor,
+        }
+    }
+}
+
+/**
+[`bytemuck`]-compatible layout of `render.wgsl#Selection`: the rectangle being dragged out to
+zoom into, in screen pixels. An empty rectangle, like [`SelectionUniform::NONE`], draws nothing.
+*/
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, Default, PartialEq)]
+pub struct SelectionUniform {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl SelectionUniform {
+    pub const NONE: Self = SelectionUniform {
+        min: Vec2::ZERO,
+        max: Vec2::ZERO,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each pixel of the rect covers a square of the plane, so its sides are in the screen's ratio.
+    #[test]
+    fn view_rect_is_square_in_complex_units() {
+        let origin = ComplexF64 {
+            real: -0.5,
+            imaginary: 0.25,
+        };
+        for (width, height) in [(1920, 1080), (600, 800), (512, 512)] {
+            let size = Size { width, height };
+            let rect = ViewRect::new(origin, 2.0, size);
+            let pixel_width = rect.width() / width as f64;
+            let pixel_height = rect.height() / height as f64;
+            assert!(
+                (pixel_width - pixel_height).abs() < 1e-12,
+                "{}x{}: {} != {}",
+                width,
+                height,
+                pixel_width,
+                pixel_height
+            );
+            assert!((rect.width() / rect.height() - size.aspect_ratio() as f64).abs() < 1e-6);
+            // The shorter side spans `4.0 / zoom`.
+            assert!((rect.width().min(rect.height()) - 2.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn view_rect_contains() {
+        let rect = ViewRect::new(
+            ComplexF64::ZERO,
+            1.0,
+            Size {
+                width: 200,
+                height: 100,
+            },
+        );
+        assert!(rect.contains(ComplexF64::ZERO));
+        assert!(rect.contains(rect.min) && rect.contains(rect.max));
+        assert!(rect.contains(ComplexF64 {
+            real: 3.5,
+            imaginary: -1.5,
+        }));
+        assert!(!rect.contains(ComplexF64 {
+            real: 0.0,
+            imaginary: 2.5,
+        }));
+    }
+}
@@ -0,0 +1,36 @@
+//! Saving rendered images.
+
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{pixel::ComplexF64, screen};
+
+/**
+A unique path in the current directory for a screenshot of the view at `origin` with `zoom`.
+
+The origin and zoom are part of the filename, so the view can be found again.
+*/
+pub fn path(origin: ComplexF64, zoom: f64) -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    PathBuf::from(format!(
+        "./mandelbrot-{}_origin={},{}_zoom={}.png",
+        timestamp, origin.real, origin.imaginary, zoom
+    ))
+}
+
+/// Write tightly-packed RGBA8 rows (e.g. from [`crate::renderer::Renderer::capture`]) to a PNG.
+pub fn save(path: &Path, size: screen::Size, rgba: &[u8]) -> image::ImageResult<()> {
+    image::save_buffer_with_format(
+        path,
+        rgba,
+        size.width,
+        size.height,
+        image::ColorType::Rgba8,
+        image::ImageFormat::Png,
+    )
+}
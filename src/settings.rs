@@ -0,0 +1,114 @@
+//! Startup settings that can be read from a TOML file, so a render can be reproduced.
+
+use std::{fmt, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    colour::{ColouringMode, PaletteName},
+    compute::{self, FractalKind},
+    pixel::ComplexF64,
+    renderer::{Renderer, RendererError},
+};
+
+/**
+Everything needed to set up a view, as read by [`load`].
+
+Missing keys take their [`Default`] values, so a file only needs the ones it changes:
+
+```toml
+origin = { real = -0.74529, imaginary = 0.113075 }
+zoom = 250.0
+palette = "fire-ice"
+max_iterations = 5000
+```
+*/
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub origin: ComplexF64,
+    pub zoom: f64,
+    pub palette: PaletteName,
+    pub max_iterations: u32,
+    pub escape_radius: f32,
+    pub fractal_kind: FractalKind,
+    /// See [`Renderer::set_ssaa`].
+    pub ssaa: u32,
+    /// See [`Renderer::set_incremental`].
+    pub incremental: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            origin: ComplexF64 {
+                real: -0.74529,
+                imaginary: 0.113075,
+            },
+            zoom: 1.0,
+            palette: PaletteName::Green,
+            max_iterations: compute::DEFAULT_MAX_ITERATIONS,
+            escape_radius: compute::DEFAULT_ESCAPE_RADIUS,
+            fractal_kind: FractalKind::Mandelbrot,
+            ssaa: 1,
+            incremental: true,
+        }
+    }
+}
+
+impl Settings {
+    /**
+    Set up a `renderer` that hasn't started iterating to show this view.
+
+    Nothing changes if the shader for the fractal kind doesn't compile.
+    */
+    pub fn apply(&self, renderer: &mut Renderer) -> Result<(), RendererError> {
+        renderer.set_fractal_kind(self.fractal_kind)?;
+        if let FractalKind::Newton = self.fractal_kind {
+            renderer.set_colouring_mode(ColouringMode::Root);
+        }
+        renderer.set_origin(self.origin);
+        renderer.set_zoom(self.zoom);
+        renderer.set_palette(self.palette.palette().as_ref());
+        renderer.set_max_iterations(self.max_iterations);
+        renderer.set_escape_radius(self.escape_radius);
+        renderer.set_ssaa(self.ssaa);
+        renderer.set_incremental(self.incremental);
+        Ok(())
+    }
+}
+
+/// Why [`load`] couldn't read a settings file.
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io(err) => write!(f, "{}", err),
+            SettingsError::Toml(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl From<io::Error> for SettingsError {
+    fn from(err: io::Error) -> Self {
+        SettingsError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for SettingsError {
+    fn from(err: toml::de::Error) -> Self {
+        SettingsError::Toml(err)
+    }
+}
+
+/// Read the settings in the TOML file at `path`.
+pub fn load(path: impl AsRef<Path>) -> Result<Settings, SettingsError> {
+    Ok(toml::from_str(&fs::read_to_string(path)?)?)
+}
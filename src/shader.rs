@@ -0,0 +1,99 @@
+/*!
+Where the WGSL shaders come from.
+
+They're embedded in the binary, unless a shader directory is given (`--shader-dir`), in which case
+they're read from there instead, so that they can be edited and reloaded without recompiling.
+*/
+
+use std::{
+    borrow::Cow,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use log::warn;
+
+/// The render shader, drawn by [`crate::renderer::Renderer::render`].
+pub const RENDER: &str = "render.wgsl";
+
+/// The embedded source of the shader file `name`.
+fn embedded(name: &str) -> &'static str {
+    match name {
+        "compute.wgsl" => include_str!("compute.wgsl"),
+        "compute_f64.wgsl" => include_str!("compute_f64.wgsl"),
+        "newton.wgsl" => include_str!("newton.wgsl"),
+        "perturbation.wgsl" => include_str!("perturbation.wgsl"),
+        RENDER => include_str!("render.wgsl"),
+        _ => panic!("no shader named {}", name),
+    }
+}
+
+/**
+Read the shader file `name`, from `dir` if there is one and otherwise from the copy embedded in the
+binary.
+
+Panics if `name` isn't one of the shaders in `src`.
+*/
+pub fn read(name: &str, dir: Option<&Path>) -> Result<Cow<'static, str>, ShaderReadError> {
+    let source = embedded(name);
+    match dir {
+        None => Ok(Cow::Borrowed(source)),
+        Some(dir) => {
+            let path = dir.join(name);
+            std::fs::read_to_string(&path)
+                .map(Cow::Owned)
+                .map_err(|error| ShaderReadError { path, error })
+        }
+    }
+}
+
+/// A shader file in the shader directory couldn't be read.
+#[derive(Debug)]
+pub struct ShaderReadError {
+    pub path: PathBuf,
+    pub error: io::Error,
+}
+
+impl fmt::Display for ShaderReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "couldn't read {}: {}", self.path.display(), self.error)
+    }
+}
+
+impl std::error::Error for ShaderReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/**
+Watch `dir` for changes to the shaders, calling `on_change` with the name of each file that's
+written. The shaders stop being watched when the watcher is dropped.
+*/
+#[cfg(not(target_arch = "wasm32"))]
+pub fn watch(
+    dir: &Path,
+    on_change: impl Fn(String) + Send + 'static,
+) -> notify::Result<notify::RecommendedWatcher> {
+    use notify::{EventKind, RecursiveMode, Watcher};
+
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                event
+                    .paths
+                    .iter()
+                    .filter(|path| {
+                        path.extension()
+                            .map_or(false, |extension| extension == "wgsl")
+                    })
+                    .filter_map(|path| path.file_name()?.to_str())
+                    .for_each(|name| on_change(name.to_string()));
+            }
+            Ok(_) => {}
+            Err(error) => warn!("error watching the shaders: {}", error),
+        })?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    Ok(watcher)
+}
@@ -14,17 +14,31 @@ This module provides a type safe buffer API.
 pub mod var;
 
 use std::{
+    fmt,
+    future::Future,
     marker::PhantomData,
     mem::size_of,
     num::NonZeroU64,
     ops::{Deref, DerefMut, RangeBounds},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
 };
 
 use wgpu::util::DeviceExt;
 
+/// How many bytes `items` of `A` take up. Every item count is turned into bytes through here.
+fn byte_size<A>(items: u64) -> u64 {
+    items * size_of::<A>() as u64
+}
+
 /// A typed [`wgpu::Buffer`].
 pub struct Buffer<A> {
     buffer: wgpu::Buffer,
+    /// Kept so that [`Buffer::resize`] can create an identical buffer.
+    label: Option<String>,
+    usage: wgpu::BufferUsages,
+    len: u64,
     phantom_data: PhantomData<A>,
 }
 
@@ -33,10 +47,37 @@ impl<A: bytemuck::Pod + bytemuck::Zeroable> Buffer<A> {
         queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(contents));
     }
 
+    /**
+    Overwrite the items starting at item `offset` with `contents`, leaving the rest of the buffer alone.
+
+    `offset` and `contents` must both be a multiple of [`wgpu::COPY_BUFFER_ALIGNMENT`] bytes.
+    */
+    pub fn write_range(&self, queue: &wgpu::Queue, offset: u64, contents: &[A]) {
+        queue.write_buffer(
+            &self.buffer,
+            byte_size::<A>(offset),
+            bytemuck::cast_slice(contents),
+        );
+    }
+
     pub fn buffer(&self) -> &wgpu::Buffer {
         &self.buffer
     }
 
+    /// How many items the buffer holds.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// How many bytes the buffer holds: [`Buffer::len`] items of `A`.
+    pub fn byte_len(&self) -> u64 {
+        byte_size::<A>(self.len)
+    }
+
     pub fn slice<S: RangeBounds<wgpu::BufferAddress>>(&self, bounds: S) -> Slice<A> {
         Slice {
             slice: self.buffer.slice(bounds),
@@ -52,11 +93,130 @@ impl<A: bytemuck::Pod + bytemuck::Zeroable> Buffer<A> {
         })
     }
 
+    /**
+    Replace the underlying buffer with one that holds `len` items, with the same label and usages.
+
+    When `command_encoder` is given, the items that fit in both buffers are copied into the new
+    one, which needs [`wgpu::BufferUsages::COPY_SRC`] and [`wgpu::BufferUsages::COPY_DST`], and
+    their size in bytes has to be a multiple of [`wgpu::COPY_BUFFER_ALIGNMENT`]. The old buffer is
+    destroyed, or dropped once the copy has been submitted.
+    */
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        len: u64,
+        command_encoder: Option<&mut wgpu::CommandEncoder>,
+    ) {
+        // Both offsets are 0 and the items fit in both buffers, so only the size can be unaligned.
+        debug_assert!(
+            command_encoder.is_none()
+                || byte_size::<A>(self.len.min(len)) % wgpu::COPY_BUFFER_ALIGNMENT == 0,
+            "can't copy {} items of {} bytes when resizing",
+            self.len.min(len),
+            size_of::<A>()
+        );
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: self.label.as_deref(),
+            size: byte_size::<A>(len),
+            usage: self.usage,
+            mapped_at_creation: false,
+        });
+        let old = Buffer {
+            buffer: std::mem::replace(&mut self.buffer, buffer),
+            label: self.label.clone(),
+            usage: self.usage,
+            len: std::mem::replace(&mut self.len, len),
+            phantom_data: PhantomData,
+        };
+
+        match command_encoder {
+            Some(command_encoder) => {
+                copy_buffer_to_buffer(command_encoder, &old, 0, self, 0, old.len.min(len))
+                    .expect("the copied items fit in both buffers and are a whole number of words");
+            }
+            None => old.destroy(),
+        }
+    }
+
+    /**
+    Map the whole buffer for reading, blocking until every submitted command has finished.
+
+    The buffer needs [`wgpu::BufferUsages::MAP_READ`]. Drop the view before calling
+    [`Buffer::unmap`].
+    */
+    pub fn map_read_blocking(&self, device: &wgpu::Device) -> View<A> {
+        let slice = self.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |map_result| {
+            map_result.unwrap_or_else(|err| panic!("buffer async error: {}", err));
+        });
+
+        // Blocks until the queue is idle, and runs the `map_async` callback before returning.
+        device.poll(wgpu::Maintain::Wait);
+        slice.get_mapped_range()
+    }
+
+    /**
+    Map the whole buffer for reading, without blocking.
+
+    Something has to poll the device for this to finish: the browser does on the web, but
+    native code has to call [`wgpu::Device::poll`] itself.
+    */
+    pub async fn map_read(&self) -> View<'_, A> {
+        let slice = self.slice(..);
+        let state = Arc::new(Mutex::new(MapState::default()));
+        {
+            let state = state.clone();
+            slice.map_async(wgpu::MapMode::Read, move |map_result| {
+                let mut state = state.lock().unwrap();
+                state.result = Some(map_result);
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            });
+        }
+
+        MapFuture { state }
+            .await
+            .unwrap_or_else(|err| panic!("buffer async error: {}", err));
+        slice.get_mapped_range()
+    }
+
+    pub fn unmap(&self) {
+        self.buffer.unmap()
+    }
+
     pub fn destroy(self) {
         self.buffer.destroy()
     }
 }
 
+/// Shared between [`Buffer::map_read`]'s `map_async` callback and the future it awaits.
+#[derive(Default)]
+struct MapState {
+    result: Option<Result<(), wgpu::BufferAsyncError>>,
+    waker: Option<Waker>,
+}
+
+/// Resolves once the `map_async` callback has run.
+struct MapFuture {
+    state: Arc<Mutex<MapState>>,
+}
+
+impl Future for MapFuture {
+    type Output = Result<(), wgpu::BufferAsyncError>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(context.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
 /// A typed [`wgpu::BufferSlice`].
 pub struct Slice<'a, A> {
     slice: wgpu::BufferSlice<'a>,
@@ -93,11 +253,30 @@ pub struct View<'a, A> {
     phantom_data: PhantomData<A>,
 }
 
+impl<'a, A: bytemuck::Pod + bytemuck::Zeroable> View<'a, A> {
+    /**
+    The mapped items. A buffer's whole range is mapped, whatever was last copied into it, so slice
+    this down to the items that are actually wanted.
+
+    Panics if the mapped bytes aren't aligned for `A`, or aren't a whole number of `A`s.
+    */
+    pub fn as_slice(&self) -> &[A] {
+        bytemuck::try_cast_slice(&*self.view).unwrap_or_else(|err| {
+            panic!(
+                "can't read {} mapped bytes as {}: {}",
+                self.view.len(),
+                std::any::type_name::<A>(),
+                err
+            )
+        })
+    }
+}
+
 impl<'a, A: bytemuck::Pod + bytemuck::Zeroable> Deref for View<'a, A> {
     type Target = [A];
 
     fn deref(&self) -> &Self::Target {
-        bytemuck::cast_slice(&*self.view)
+        self.as_slice()
     }
 }
 
@@ -137,6 +316,7 @@ pub struct Builder<'a, A> {
     label: Option<&'a str>,
     contents: Contents<'a>,
     usage: wgpu::BufferUsages,
+    mapped_at_creation: bool,
     phantom_data: PhantomData<A>,
 }
 
@@ -146,6 +326,7 @@ impl<'a, A: bytemuck::Pod + bytemuck::Zeroable> From<&'a [A]> for Builder<'a, A>
             label: None,
             contents: Contents::Contents(bytemuck::cast_slice(value)),
             usage: wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
             phantom_data: PhantomData,
         }
     }
@@ -157,6 +338,7 @@ impl<'a, A: bytemuck::Pod + bytemuck::Zeroable> Builder<'a, A> {
             label: None,
             contents: Contents::Size(size),
             usage: wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
             phantom_data: PhantomData,
         }
     }
@@ -171,8 +353,40 @@ impl<'a, A: bytemuck::Pod + bytemuck::Zeroable> Builder<'a, A> {
         self
     }
 
+    /**
+    Create the buffer mapped, so that a [`Builder::new`] buffer's initial contents can be written
+    straight into it with [`Slice::get_mapped_range_mut`], instead of being staged by
+    [`wgpu::Queue::write_buffer`]. Drop the view and call [`Buffer::unmap`] before using it.
+
+    A [`Builder::from`] buffer is left mapped too, with its contents already written, so that they
+    can be read back or changed before it's used. Without this it's unmapped as soon as they are.
+
+    The buffer's size in bytes has to be a multiple of [`wgpu::COPY_BUFFER_ALIGNMENT`].
+    */
+    pub fn with_mapped_at_creation(mut self, mapped_at_creation: bool) -> Self {
+        self.mapped_at_creation = mapped_at_creation;
+        self
+    }
+
     pub fn create(self, device: &wgpu::Device) -> Buffer<A> {
+        let len = match self.contents {
+            Contents::Contents(contents) => (contents.len() / size_of::<A>()) as u64,
+            Contents::Size(size) => size,
+        };
         let buffer = match self.contents {
+            Contents::Contents(contents) if self.mapped_at_creation => {
+                let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: self.label,
+                    size: contents.len() as u64,
+                    usage: self.usage,
+                    mapped_at_creation: true,
+                });
+                buffer
+                    .slice(..)
+                    .get_mapped_range_mut()
+                    .copy_from_slice(contents);
+                buffer
+            }
             Contents::Contents(contents) => {
                 device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                     label: self.label,
@@ -182,23 +396,69 @@ impl<'a, A: bytemuck::Pod + bytemuck::Zeroable> Builder<'a, A> {
             }
             Contents::Size(size) => device.create_buffer(&wgpu::BufferDescriptor {
                 label: self.label,
-                size: size * size_of::<A>() as u64,
+                size: byte_size::<A>(size),
                 usage: self.usage,
-                mapped_at_creation: false,
+                mapped_at_creation: self.mapped_at_creation,
             }),
         };
 
         Buffer {
             buffer,
+            label: self.label.map(str::to_owned),
+            usage: self.usage,
+            len,
             phantom_data: PhantomData,
         }
     }
 }
 
+/// Why [`copy_buffer_to_buffer`] refused to copy.
+#[derive(Debug)]
+pub enum CopyError {
+    /// The items it was asked for don't fit in a buffer.
+    OutOfRange {
+        /// `"source"` or `"destination"`.
+        buffer: &'static str,
+        /// One past the last item the copy would touch.
+        end: u64,
+        len: u64,
+    },
+    /// An offset or the size, in bytes, isn't a multiple of [`wgpu::COPY_BUFFER_ALIGNMENT`].
+    Unaligned {
+        /// `"source offset"`, `"destination offset"` or `"size"`.
+        what: &'static str,
+        bytes: u64,
+    },
+}
+
+impl fmt::Display for CopyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CopyError::OutOfRange { buffer, end, len } => write!(
+                f,
+                "copy ends at item {}, past the end of the {} buffer of {} items",
+                end, buffer, len
+            ),
+            CopyError::Unaligned { what, bytes } => write!(
+                f,
+                "copy {} of {} bytes isn't a multiple of {}",
+                what,
+                bytes,
+                wgpu::COPY_BUFFER_ALIGNMENT
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CopyError {}
+
 /**
 A typed version of [`wgpu::CommandEncoder::copy_buffer_to_buffer`].
 
-`source_index`, `destination_index`, and `copy_size` count buffer items, not bytes.
+`source_index`, `destination_index`, and `copy_size` count buffer items, not bytes. Nothing is
+copied unless the whole range fits in both buffers, and the offsets and size in bytes are
+multiples of [`wgpu::COPY_BUFFER_ALIGNMENT`], which wgpu would otherwise reject when the commands
+are submitted.
 */
 pub fn copy_buffer_to_buffer<A: bytemuck::Pod + bytemuck::Zeroable>(
     command_encoder: &mut wgpu::CommandEncoder,
@@ -207,25 +467,121 @@ pub fn copy_buffer_to_buffer<A: bytemuck::Pod + bytemuck::Zeroable>(
     destination: &Buffer<A>,
     destination_index: u64,
     copy_size: u64,
-) {
+) -> Result<(), CopyError> {
+    for (buffer, index, len) in [
+        ("source", source_index, source.len()),
+        ("destination", destination_index, destination.len()),
+    ] {
+        let end = index.saturating_add(copy_size);
+        if end > len {
+            return Err(CopyError::OutOfRange { buffer, end, len });
+        }
+    }
+
+    // In range, so none of these can overflow.
+    let source_offset = byte_size::<A>(source_index);
+    let destination_offset = byte_size::<A>(destination_index);
+    let size = byte_size::<A>(copy_size);
+    for (what, bytes) in [
+        ("source offset", source_offset),
+        ("destination offset", destination_offset),
+        ("size", size),
+    ] {
+        if bytes % wgpu::COPY_BUFFER_ALIGNMENT != 0 {
+            return Err(CopyError::Unaligned { what, bytes });
+        }
+    }
+
     command_encoder.copy_buffer_to_buffer(
         source.buffer(),
-        source_index * size_of::<A>() as u64,
+        source_offset,
         destination.buffer(),
-        destination_index * size_of::<A>() as u64,
-        copy_size * size_of::<A>() as u64,
-    )
+        destination_offset,
+        size,
+    );
+    Ok(())
 }
 
-/// A structure for double-buffering.
+/**
+A structure for double-buffering.
+
+Each pass reads [`DoubleBuffer::current`] and writes [`DoubleBuffer::next`], then
+[`DoubleBuffer::swap`] makes its output the next pass's input. Swap exactly once per pass, after
+recording it: a missed or doubled swap makes the next pass iterate stale data. Writing new
+contents goes to both halves, with [`DoubleBuffer::write`], so either can be read next.
+
+Both halves always have the same length and usages, so anything that changes one should go
+through [`DoubleBuffer::for_each_mut`]. Halves that drift apart don't fail anywhere in wgpu: the
+pass just reads or writes past what the other half holds, and the image comes out corrupted.
+Debug builds check [`DoubleBuffer::assert_consistent`] after every swap and change.
+*/
 pub struct DoubleBuffer<A> {
     pub input: Buffer<A>,
     pub output: Buffer<A>,
 }
 
 impl<A: bytemuck::Pod + bytemuck::Zeroable> DoubleBuffer<A> {
+    /// Double-buffer between `input` and `output`, which must have the same length and usages.
+    pub fn new(input: Buffer<A>, output: Buffer<A>) -> Self {
+        let double_buffer = DoubleBuffer { input, output };
+        double_buffer.assert_consistent();
+        double_buffer
+    }
+
+    /// Panic unless both halves have the same length and usages.
+    pub fn assert_consistent(&self) {
+        assert_eq!(
+            (self.input.len, self.input.usage),
+            (self.output.len, self.output.usage),
+            "the halves of double buffer {:?} and {:?} differ",
+            self.input.label,
+            self.output.label
+        );
+    }
+
     pub fn swap(&mut self) {
-        std::mem::swap(&mut self.input, &mut self.output)
+        std::mem::swap(&mut self.input, &mut self.output);
+        #[cfg(debug_assertions)]
+        self.assert_consistent();
+    }
+
+    /// The half that the next pass reads, which holds the last pass's results.
+    pub fn current(&self) -> &Buffer<A> {
+        &self.input
+    }
+
+    /// The half that the next pass writes.
+    pub fn next(&self) -> &Buffer<A> {
+        &self.output
+    }
+
+    /// Call `f` on both halves, `current` first.
+    pub fn for_each(&self, mut f: impl FnMut(&Buffer<A>)) {
+        f(&self.input);
+        f(&self.output);
+    }
+
+    /// Call `f` on both halves mutably, `current` first. `f` has to change them the same way.
+    pub fn for_each_mut(&mut self, mut f: impl FnMut(&mut Buffer<A>)) {
+        f(&mut self.input);
+        f(&mut self.output);
+        #[cfg(debug_assertions)]
+        self.assert_consistent();
+    }
+
+    /// `f` of both halves, `current` first.
+    pub fn map<B>(&self, mut f: impl FnMut(&Buffer<A>) -> B) -> [B; 2] {
+        [f(&self.input), f(&self.output)]
+    }
+
+    /// Overwrite both halves with `contents`.
+    pub fn write(&self, queue: &wgpu::Queue, contents: &[A]) {
+        self.for_each(|buffer| buffer.write(queue, contents));
+    }
+
+    /// [`Buffer::resize`] both halves, without copying their contents.
+    pub fn resize(&mut self, device: &wgpu::Device, len: u64) {
+        self.for_each_mut(|buffer| buffer.resize(device, len, None));
     }
 
     pub fn destroy(self) {
@@ -233,3 +589,68 @@ impl<A: bytemuck::Pod + bytemuck::Zeroable> DoubleBuffer<A> {
         self.output.destroy();
     }
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    const DATA: [u32; 8] = [0, 1, 2, 3, 5, 8, 13, 21];
+
+    /// A device for the tests, or `None` where there's no GPU, in which case they return early.
+    fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+        let adapter = crate::renderer::test_adapter()?;
+        Some(
+            pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn map_read_blocking_reads_back_a_copy() {
+        let (device, queue) = match device() {
+            Some(device) => device,
+            None => return,
+        };
+        let source = Builder::from(DATA.as_slice())
+            .with_usage(wgpu::BufferUsages::COPY_SRC)
+            .create(&device);
+        let readback = Builder::<u32>::new(DATA.len() as u64)
+            .with_usage(wgpu::BufferUsages::MAP_READ)
+            .create(&device);
+
+        let mut command_encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        copy_buffer_to_buffer(
+            &mut command_encoder,
+            &source,
+            0,
+            &readback,
+            0,
+            readback.len(),
+        )
+        .unwrap();
+        queue.submit([command_encoder.finish()]);
+
+        let view = readback.map_read_blocking(&device);
+        assert_eq!(view.as_slice(), DATA.as_slice());
+        drop(view);
+        readback.unmap();
+    }
+
+    #[test]
+    fn from_honours_mapped_at_creation() {
+        let (device, _queue) = match device() {
+            Some(device) => device,
+            None => return,
+        };
+        let buffer = Builder::from(DATA.as_slice())
+            .with_usage(wgpu::BufferUsages::MAP_READ)
+            .with_mapped_at_creation(true)
+            .create(&device);
+
+        let view = buffer.slice(..).get_mapped_range();
+        assert_eq!(view.as_slice(), DATA.as_slice());
+        drop(view);
+        buffer.unmap();
+    }
+}
@@ -1,5 +1,5 @@
 //! Single-element buffers.
-use std::marker::PhantomData;
+use std::{marker::PhantomData, mem::size_of};
 
 use wgpu::util::DeviceExt;
 
@@ -13,6 +13,36 @@ impl<A: bytemuck::Pod + bytemuck::Zeroable> Var<A> {
         queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[contents]));
     }
 
+    /**
+    Copy the value back from the GPU, blocking until every submitted command has finished. For
+    debugging: it checks what a [`Var::write`] actually left in the buffer.
+
+    `A` has to be a multiple of [`wgpu::COPY_BUFFER_ALIGNMENT`] bytes, like every uniform is.
+    */
+    pub fn read(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> A {
+        let staging = super::Builder::<A>::new(1)
+            .with_label("var-read-staging")
+            .with_usage(wgpu::BufferUsages::MAP_READ)
+            .create(device);
+
+        let mut command_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("var-read-command-encoder"),
+        });
+        command_encoder.copy_buffer_to_buffer(
+            &self.buffer,
+            0,
+            staging.buffer(),
+            0,
+            size_of::<A>() as u64,
+        );
+        queue.submit(Some(command_encoder.finish()));
+
+        let value = staging.map_read_blocking(device)[0];
+        staging.unmap();
+        staging.destroy();
+        value
+    }
+
     pub fn binding_resource(&self) -> wgpu::BindingResource {
         wgpu::BindingResource::Buffer(wgpu::BufferBinding {
             buffer: &self.buffer,
@@ -33,11 +63,13 @@ pub struct Builder<'a, A> {
 }
 
 impl<'a, A: bytemuck::Pod + bytemuck::Zeroable> Builder<'a, A> {
+    /// Always [`wgpu::BufferUsages::COPY_DST`] for [`Var::write`], and
+    /// [`wgpu::BufferUsages::COPY_SRC`] for [`Var::read`].
     pub fn new(contents: A) -> Self {
         Self {
             label: None,
             contents,
-            usage: wgpu::BufferUsages::COPY_DST,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
         }
     }
 
@@ -0,0 +1,101 @@
+//! Recording videos from rendered frames.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+};
+
+use log::warn;
+
+use crate::{screen, screenshot};
+
+/// Where [`FrameSink::write`] sends frames.
+pub enum FrameSink {
+    /// An `ffmpeg` process, encoding the raw RGBA frames written to its stdin.
+    Ffmpeg(Child),
+    /// A numbered PNG for each frame.
+    Png {
+        directory: PathBuf,
+        size: screen::Size,
+        next_frame: u32,
+    },
+}
+
+impl FrameSink {
+    /**
+    Encode `size` frames to the video at `path`, playing at `fps`.
+
+    When `ffmpeg` isn't installed, the frames are saved as a PNG sequence in a directory named
+    after `path` without its extension instead.
+    */
+    pub fn new(path: &Path, size: screen::Size, fps: u32) -> io::Result<Self> {
+        let ffmpeg = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+            .args(["-s", &format!("{}x{}", size.width, size.height)])
+            .args(["-r", &fps.to_string()])
+            .args(["-i", "-"])
+            // Most players can't decode yuv444p, which ffmpeg would otherwise pick for RGBA input.
+            .args(["-pix_fmt", "yuv420p"])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn();
+
+        match ffmpeg {
+            Ok(child) => Ok(FrameSink::Ffmpeg(child)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                let directory = path.with_extension("");
+                warn!(
+                    "ffmpeg not found, saving frames to {:?} instead of {:?}",
+                    directory, path
+                );
+                fs::create_dir_all(&directory)?;
+                Ok(FrameSink::Png {
+                    directory,
+                    size,
+                    next_frame: 0,
+                })
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Add a frame of tightly-packed RGBA8 rows, e.g. from [`crate::renderer::Renderer::render_to_vec`].
+    pub fn write(&mut self, rgba: &[u8]) -> io::Result<()> {
+        match self {
+            FrameSink::Ffmpeg(child) => child.stdin.as_mut().unwrap().write_all(rgba),
+            FrameSink::Png {
+                directory,
+                size,
+                next_frame,
+            } => {
+                let path = directory.join(format!("frame-{:05}.png", next_frame));
+                *next_frame += 1;
+                screenshot::save(&path, *size, rgba)
+                    .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+            }
+        }
+    }
+
+    /// Wait for the video to be written.
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            FrameSink::Ffmpeg(mut child) => {
+                // Closing stdin tells ffmpeg there are no more frames.
+                drop(child.stdin.take());
+                let status = child.wait()?;
+                if status.success() {
+                    Ok(())
+                } else {
+                    Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("ffmpeg exited with {}", status),
+                    ))
+                }
+            }
+            FrameSink::Png { .. } => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,1283 @@
+//! Interactive navigation: the view state that window events change, on top of a [`Renderer`].
+
+use std::path::Path;
+
+use instant::Instant;
+use log::{debug, error, info, warn};
+use winit::{
+    event::{ModifiersState, WindowEvent},
+    window::{Fullscreen, Window},
+};
+
+use crate::{
+    bookmark::{self, Bookmark},
+    colour::{self, Palette, PaletteName},
+    compute, grid,
+    history::History,
+    location::{Location, SeedLocation, SEED_LOCATIONS},
+    pixel::{Complex, ComplexF64},
+    renderer::Renderer,
+    screen::{self, Vec2},
+    screenshot,
+};
+
+/// How far the arrow keys pan each second, in units of `2.0 / zoom` (half the view's shorter side).
+const PAN_SPEED: f32 = 0.5;
+
+/// The window title, which `G` extends with the location being typed.
+pub const TITLE: &str = "wgpu-mandelbrot";
+
+/// Where `E` exports the current palette to.
+const PALETTE_PATH: &str = "palette.json";
+
+/// The size of the image saved with `W`.
+const WALLPAPER_SIZE: screen::Size = screen::Size {
+    width: 7680,
+    height: 4320,
+};
+const WALLPAPER_TILE_SIZE: screen::Size = screen::Size {
+    width: 1024,
+    height: 1024,
+};
+
+/// How far, in pixels, the cursor can move while the left button is held and still count as a click.
+const DRAG_THRESHOLD: f32 = 4.0;
+
+/// The left mouse button is held.
+struct Drag {
+    /// Where the cursor was when `origin` last followed it. Stays at the press position until
+    /// the cursor has moved past `DRAG_THRESHOLD`.
+    last_position: Vec2,
+    dragging: bool,
+}
+
+/**
+The point on the plane under the cursor at `physical_position`, in a `size` view centred on
+`origin` at `zoom`.
+
+winit reports cursor positions in physical pixels, and `size` is the renderer's physical size, so
+the window's scale factor doesn't come into it. Mixing in logical sizes would put clicks on HiDPI
+screens off by that factor.
+*/
+fn cursor_to_complex(
+    physical_position: Vec2,
+    size: screen::Size,
+    origin: ComplexF64,
+    zoom: f64,
+) -> ComplexF64 {
+    ComplexF64::from_screen(physical_position, size, origin, zoom)
+}
+
+/// The left half of a `size` screen, where [`compute::FractalKind::Split`] shows the Mandelbrot set.
+fn left_half(size: screen::Size) -> screen::Size {
+    screen::Size {
+        width: (size.width / 2).max(1),
+        height: size.height,
+    }
+}
+
+/**
+The rectangle from `start` to `end`, grown along one axis to the aspect ratio of a `size` screen.
+Returns its top left and bottom right corners.
+*/
+fn fit_selection(start: Vec2, end: Vec2, size: screen::Size) -> (Vec2, Vec2) {
+    let centre = (start + end) * 0.5;
+    let mut half = Vec2 {
+        x: (end.x - start.x).abs() / 2.0,
+        y: (end.y - start.y).abs() / 2.0,
+    };
+    if half.x < half.y * size.aspect_ratio() {
+        half.x = half.y * size.aspect_ratio();
+    } else {
+        half.y = half.x / size.aspect_ratio();
+    }
+    (centre - half, centre + half)
+}
+
+/// A zoom animation has settled once it's within this fraction of its target.
+const ZOOM_SETTLED: f64 = 1e-3;
+
+/// How much `[`/`]` scale the gamma, and `,`/`.` scale the contrast, per press.
+const POST_PROCESSING_FACTOR: f32 = 1.1;
+
+/// How much `;`/`'` lower or raise the brightness per press.
+const BRIGHTNESS_STEP: f32 = 0.05;
+
+/**
+How many pixels of [`winit::event::MouseScrollDelta::PixelDelta`], from trackpads, count as one
+line of [`winit::event::MouseScrollDelta::LineDelta`], i.e. one notch of a mouse wheel.
+*/
+const PIXELS_PER_LINE: f64 = 100.0;
+
+/// How much `+` and `-` raise and lower the maximum iteration count.
+const MAX_ITERATIONS_STEP: u32 = 100;
+
+/// The arrow keys that are currently held.
+#[derive(Default)]
+struct PanKeys {
+    left: bool,
+    right: bool,
+    up: bool,
+    down: bool,
+}
+
+impl PanKeys {
+    /// The direction to pan in screen space, where `y` points down.
+    fn direction(&self) -> Vec2 {
+        let axis = |negative: bool, positive: bool| positive as i32 as f32 - negative as i32 as f32;
+        Vec2 {
+            x: axis(self.left, self.right),
+            y: axis(self.up, self.down),
+        }
+    }
+}
+
+/// Whether `event` is input that should resume a pause. `Space` toggles pausing by itself.
+fn is_interaction(event: &WindowEvent) -> bool {
+    match event {
+        WindowEvent::KeyboardInput {
+            input:
+                winit::event::KeyboardInput {
+                    state: winit::event::ElementState::Pressed,
+                    virtual_keycode,
+                    ..
+                },
+            ..
+        } => *virtual_keycode != Some(winit::event::VirtualKeyCode::Space),
+        WindowEvent::MouseInput { .. } | WindowEvent::MouseWheel { .. } => true,
+        _ => false,
+    }
+}
+
+/**
+A [`Renderer`] driven by keyboard and mouse input.
+
+[`Viewer::handle_window_event`] turns window events into changes to the view, [`Viewer::update`]
+advances the animations between frames, and [`Viewer::render_frame`] computes and draws. The
+window, its surface and the event loop stay with the caller.
+
+`origin` and `zoom` always match the renderer's. They're kept here as well so that animations can
+write to the renderer without resetting it.
+*/
+pub struct Viewer {
+    pub renderer: Renderer,
+    origin: ComplexF64,
+    zoom: f64,
+    /// Scrolling sets this, and `zoom` eases towards it.
+    target_zoom: f64,
+    /**
+    In physical pixels, like [`Renderer::display_size`] and the window's `inner_size`, which is
+    what winit reports cursor positions in whatever the scale factor.
+    */
+    cursor_position: Vec2,
+    drag: Option<Drag>,
+    /// Where the right mouse button was pressed, while it's held to drag out a rectangle to zoom into.
+    selection_start: Option<Vec2>,
+    modifiers: ModifiersState,
+    /// `Ctrl+Z` and `Ctrl+Y` step through this.
+    history: History,
+    /// Changing the origin or zoom resets the renderer by itself, but changing the fractal doesn't.
+    fractal_kind_changed: bool,
+    /// Toggled by `A`.
+    cycling_palette: bool,
+    pan_keys: PanKeys,
+    /// The `re,im,zoom` typed so far, while `G` has been pressed and Enter hasn't.
+    location_input: Option<String>,
+    bookmarks: Vec<Bookmark>,
+    /// The last of [`SEED_LOCATIONS`] shown, so that `N` and `P` carry on from it.
+    seed_index: Option<usize>,
+    /// `Space` stops computing and rendering until the next interaction.
+    pub paused: bool,
+    palette_name: PaletteName,
+    palette: Box<dyn Palette>,
+    /// The size to go back to when `F11` leaves fullscreen.
+    windowed_size: Option<winit::dpi::PhysicalSize<u32>>,
+    /// How quickly zooming eases in, per second. Higher is snappier.
+    pub animation_speed: f64,
+    /// How many times a second `A` cycles the palette.
+    pub cycle_speed: f32,
+    /// How quickly [`Renderer::drift`] moves toward its target, per second. Higher is faster.
+    pub drift_speed: f64,
+    /// How much each notch of the scroll wheel zooms, e.g. `0.1` for 10%.
+    pub zoom_sensitivity: f64,
+    /// Scrolling up zooms out instead of in.
+    pub invert_zoom: bool,
+    last_update: Instant,
+}
+
+impl Viewer {
+    /**
+    Start navigating from `renderer`'s current view, showing `palette`.
+
+    `palette_name` is what `C` cycles on from, even if `palette` was loaded from a file. Bookmarks
+    are loaded from [`bookmark::PATH`], or start empty if they can't be.
+    */
+    pub fn new(renderer: Renderer, palette_name: PaletteName, palette: Box<dyn Palette>) -> Self {
+        let origin = renderer.origin();
+        let zoom = renderer.zoom();
+        let bookmarks = bookmark::load(bookmark::PATH).unwrap_or_else(|err| {
+            warn!(
+                "failed to load bookmarks from {:?}: {}",
+                bookmark::PATH,
+                err
+            );
+            Vec::new()
+        });
+        Viewer {
+            renderer,
+            origin,
+            zoom,
+            target_zoom: zoom,
+            cursor_position: Vec2::ZERO,
+            drag: None,
+            selection_start: None,
+            modifiers: ModifiersState::empty(),
+            history: History::new((origin, zoom)),
+            fractal_kind_changed: false,
+            cycling_palette: false,
+            pan_keys: PanKeys::default(),
+            location_input: None,
+            bookmarks,
+            seed_index: None,
+            paused: false,
+            palette_name,
+            palette,
+            windowed_size: None,
+            animation_speed: 10.0,
+            cycle_speed: 0.05,
+            drift_speed: 0.5,
+            zoom_sensitivity: 0.1,
+            invert_zoom: false,
+            last_update: Instant::now(),
+        }
+    }
+
+    pub fn with_animation_speed(mut self, animation_speed: f64) -> Self {
+        self.animation_speed = animation_speed;
+        self
+    }
+
+    pub fn with_cycle_speed(mut self, cycle_speed: f32) -> Self {
+        self.cycle_speed = cycle_speed;
+        self
+    }
+
+    pub fn with_drift_speed(mut self, drift_speed: f64) -> Self {
+        self.drift_speed = drift_speed;
+        self
+    }
+
+    pub fn with_zoom_sensitivity(mut self, zoom_sensitivity: f64) -> Self {
+        self.zoom_sensitivity = zoom_sensitivity;
+        self
+    }
+
+    pub fn with_invert_zoom(mut self, invert_zoom: bool) -> Self {
+        self.invert_zoom = invert_zoom;
+        self
+    }
+
+    /// Start cycling [`SEED_LOCATIONS`] from `seed`, which the renderer is already showing.
+    pub fn with_seed_location(mut self, seed: &SeedLocation) -> Self {
+        self.seed_index = SEED_LOCATIONS
+            .iter()
+            .position(|other| other.name == seed.name);
+        self
+    }
+
+    /// The size `F11` returns the window to when leaving fullscreen, if it started fullscreen.
+    pub fn with_windowed_size(mut self, windowed_size: winit::dpi::PhysicalSize<u32>) -> Self {
+        self.windowed_size = Some(windowed_size);
+        self
+    }
+
+    /**
+    Apply the controls bound to `event`. Resizing, closing and anything else that needs the
+    surface or event loop is left to the caller.
+
+    Input that could change the image resumes a pause and requests a redraw of `window`.
+    */
+    pub fn handle_window_event(&mut self, window: &Window, event: WindowEvent) {
+        // Settings like the palette change the image without making it compute again.
+        if is_interaction(&event) {
+            if self.paused {
+                self.paused = false;
+                info!("resumed");
+            }
+            window.request_redraw();
+        }
+
+        match event {
+            WindowEvent::ModifiersChanged(state) => {
+                self.modifiers = state;
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode:
+                            Some(
+                                keycode @ (winit::event::VirtualKeyCode::Z
+                                | winit::event::VirtualKeyCode::Y),
+                            ),
+                        ..
+                    },
+                ..
+            } if self.modifiers.ctrl() => {
+                let view = if keycode == winit::event::VirtualKeyCode::Z {
+                    self.history.undo()
+                } else {
+                    self.history.redo()
+                };
+                match view {
+                    Some(view) => {
+                        debug!("returning to {:?}", view);
+                        (self.origin, self.zoom) = view;
+                        self.target_zoom = self.zoom;
+                        self.renderer.set_origin(self.origin);
+                        self.renderer.set_zoom(self.zoom);
+                    }
+                    None => debug!("nothing to undo or redo"),
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position.x = position.x as f32;
+                self.cursor_position.y = position.y as f32;
+                let view_size = self.view_size();
+
+                if let Some(drag) = &mut self.drag {
+                    if (self.cursor_position - drag.last_position).length() > DRAG_THRESHOLD {
+                        drag.dragging = true;
+                    }
+
+                    if drag.dragging {
+                        // Move the plane with the cursor, i.e. the origin against it.
+                        let previous = cursor_to_complex(
+                            drag.last_position,
+                            view_size,
+                            self.origin,
+                            self.zoom,
+                        );
+                        let current = cursor_to_complex(
+                            self.cursor_position,
+                            view_size,
+                            self.origin,
+                            self.zoom,
+                        );
+                        self.origin = ComplexF64 {
+                            real: self.origin.real + previous.real - current.real,
+                            imaginary: self.origin.imaginary + previous.imaginary
+                                - current.imaginary,
+                        };
+                        drag.last_position = self.cursor_position;
+                        self.renderer.set_origin(self.origin);
+                    }
+                }
+
+                if let Some(start) = self.selection_start {
+                    self.renderer.set_selection(Some(fit_selection(
+                        start,
+                        self.cursor_position,
+                        view_size,
+                    )));
+                    window.request_redraw();
+                }
+
+                // The Julia set follows the point under the cursor, while it's over the Mandelbrot set.
+                if let compute::FractalKind::Split { .. } = self.renderer.fractal_kind {
+                    if self.cursor_position.x < view_size.width as f32 {
+                        self.renderer.set_julia_c(Complex::from(cursor_to_complex(
+                            self.cursor_position,
+                            view_size,
+                            self.origin,
+                            self.zoom,
+                        )));
+                        window.request_redraw();
+                    }
+                }
+            }
+            WindowEvent::ReceivedCharacter(character) if self.location_input.is_some() => {
+                // Only keep characters that can be part of `re,im,zoom`.
+                if character.is_ascii_digit() || "+-.,eE ".contains(character) {
+                    let input = self.location_input.as_mut().unwrap();
+                    input.push(character);
+                    window.set_title(&format!("{} - go to: {}", TITLE, input));
+                }
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(keycode),
+                        ..
+                    },
+                ..
+            } if self.location_input.is_some() => match keycode {
+                winit::event::VirtualKeyCode::Return
+                | winit::event::VirtualKeyCode::NumpadEnter => {
+                    let input = self.location_input.take().unwrap();
+                    window.set_title(TITLE);
+                    match input.parse::<Location>() {
+                        Ok(location) => {
+                            info!("going to {:?}", location);
+                            self.origin = location.origin;
+                            self.zoom = location.zoom;
+                            self.target_zoom = self.zoom;
+                            self.renderer.set_origin(self.origin);
+                            self.renderer.set_zoom(self.zoom);
+                        }
+                        Err(err) => warn!("ignoring location {:?}: {}", input, err),
+                    }
+                }
+                winit::event::VirtualKeyCode::Back => {
+                    let input = self.location_input.as_mut().unwrap();
+                    input.pop();
+                    window.set_title(&format!("{} - go to: {}", TITLE, input));
+                }
+                winit::event::VirtualKeyCode::Escape => {
+                    self.location_input = None;
+                    window.set_title(TITLE);
+                }
+                // Don't trigger other controls while typing.
+                _ => {}
+            },
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::G),
+                        ..
+                    },
+                ..
+            } => {
+                self.location_input = Some(String::new());
+                window.set_title(&format!("{} - go to: ", TITLE));
+            }
+            WindowEvent::MouseInput {
+                state: winit::event::ElementState::Pressed,
+                button: winit::event::MouseButton::Left,
+                ..
+            } => {
+                self.drag = Some(Drag {
+                    last_position: self.cursor_position,
+                    dragging: false,
+                });
+            }
+            WindowEvent::MouseInput {
+                state: winit::event::ElementState::Released,
+                button: winit::event::MouseButton::Left,
+                ..
+            } => {
+                // A drag has already moved the view.
+                if let Some(Drag { dragging: true, .. }) = self.drag.take() {
+                    return;
+                }
+
+                debug!("mouse clicked at {}", self.cursor_position);
+
+                self.origin = cursor_to_complex(
+                    self.cursor_position,
+                    self.view_size(),
+                    self.origin,
+                    self.zoom,
+                );
+                debug!("origin set to {}", self.origin);
+                self.renderer.set_origin(self.origin);
+            }
+            WindowEvent::MouseInput {
+                state: winit::event::ElementState::Pressed,
+                button: winit::event::MouseButton::Right,
+                ..
+            } => {
+                self.selection_start = Some(self.cursor_position);
+            }
+            WindowEvent::MouseInput {
+                state: winit::event::ElementState::Released,
+                button: winit::event::MouseButton::Right,
+                ..
+            } => {
+                let start = match self.selection_start.take() {
+                    Some(start) => start,
+                    None => return,
+                };
+                self.renderer.set_selection(None);
+                // Too small to be anything but a click.
+                if (self.cursor_position - start).length() <= DRAG_THRESHOLD {
+                    return;
+                }
+
+                let size = self.view_size();
+                let (min, max) = fit_selection(start, self.cursor_position, size);
+                self.origin = cursor_to_complex((min + max) * 0.5, size, self.origin, self.zoom);
+                self.zoom *= size.width as f64 / (max.x - min.x) as f64;
+                self.target_zoom = self.zoom;
+                debug!(
+                    "zoomed to the selection: {:?} at {}",
+                    self.origin, self.zoom
+                );
+                self.renderer.set_origin(self.origin);
+                self.renderer.set_zoom(self.zoom);
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::J),
+                        ..
+                    },
+                ..
+            } => {
+                let fractal_kind = match self.renderer.fractal_kind {
+                    compute::FractalKind::Mandelbrot
+                    | compute::FractalKind::BurningShip
+                    | compute::FractalKind::Newton
+                    | compute::FractalKind::Split { .. } => compute::FractalKind::Julia {
+                        c: Complex::from(cursor_to_complex(
+                            self.cursor_position,
+                            self.view_size(),
+                            self.origin,
+                            self.zoom,
+                        )),
+                    },
+                    compute::FractalKind::Julia { .. } => compute::FractalKind::Mandelbrot,
+                };
+                debug!("fractal kind set to {:?}", fractal_kind);
+
+                if let Err(err) = self.renderer.set_fractal_kind(fractal_kind) {
+                    error!("{}", err);
+                }
+                self.fractal_kind_changed = true;
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::V),
+                        ..
+                    },
+                ..
+            } => {
+                let fractal_kind = match self.renderer.fractal_kind {
+                    compute::FractalKind::Split { .. } => compute::FractalKind::Mandelbrot,
+                    compute::FractalKind::Mandelbrot
+                    | compute::FractalKind::Julia { .. }
+                    | compute::FractalKind::BurningShip
+                    | compute::FractalKind::Newton => {
+                        // The Mandelbrot set moves into the left half, so the cursor's point does too.
+                        compute::FractalKind::Split {
+                            c: Complex::from(cursor_to_complex(
+                                self.cursor_position,
+                                left_half(self.renderer.display_size),
+                                self.origin,
+                                self.zoom,
+                            )),
+                        }
+                    }
+                };
+                debug!("fractal kind set to {:?}", fractal_kind);
+
+                if let Err(err) = self.renderer.set_fractal_kind(fractal_kind) {
+                    error!("{}", err);
+                }
+                self.fractal_kind_changed = true;
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::H),
+                        ..
+                    },
+                ..
+            } => {
+                self.renderer.go_home();
+                self.origin = self.renderer.origin();
+                self.zoom = self.renderer.zoom();
+                self.target_zoom = self.zoom;
+                debug!("returned home to {} at zoom {}", self.origin, self.zoom);
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::F),
+                        ..
+                    },
+                ..
+            } => {
+                /*
+                Cycle the fractals that don't need a point picked: Mandelbrot, Burning Ship, Newton.
+                The Julia set and the split view carry on from the Mandelbrot set. Root colouring is
+                switched on for Newton and off again after it.
+                */
+                let (fractal_kind, colouring_mode) = match self.renderer.fractal_kind {
+                    compute::FractalKind::Mandelbrot
+                    | compute::FractalKind::Julia { .. }
+                    | compute::FractalKind::Split { .. } => {
+                        (compute::FractalKind::BurningShip, None)
+                    }
+                    compute::FractalKind::BurningShip => (
+                        compute::FractalKind::Newton,
+                        Some(colour::ColouringMode::Root),
+                    ),
+                    compute::FractalKind::Newton => (
+                        compute::FractalKind::Mandelbrot,
+                        Some(colour::ColouringMode::Histogram),
+                    ),
+                };
+                debug!("fractal kind set to {:?}", fractal_kind);
+
+                match self.renderer.set_fractal_kind(fractal_kind) {
+                    Ok(()) => {
+                        if let Some(colouring_mode) = colouring_mode {
+                            self.renderer.set_colouring_mode(colouring_mode);
+                        }
+                        self.fractal_kind_changed = true;
+                    }
+                    Err(err) => error!("{}", err),
+                }
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::C),
+                        ..
+                    },
+                ..
+            } => {
+                self.palette_name = self.palette_name.next();
+                debug!("palette set to {:?}", self.palette_name);
+                self.palette = self.palette_name.palette();
+                self.renderer.set_palette(self.palette.as_ref());
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::F11),
+                        ..
+                    },
+                ..
+            } => {
+                // Resizing rebuilds everything through `WindowEvent::Resized`.
+                if window.fullscreen().is_some() {
+                    debug!("leaving fullscreen");
+                    window.set_fullscreen(None);
+                    if let Some(windowed_size) = self.windowed_size {
+                        window.set_inner_size(windowed_size);
+                    }
+                } else {
+                    debug!("entering fullscreen");
+                    self.windowed_size = Some(window.inner_size());
+                    window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+                }
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::Space),
+                        ..
+                    },
+                ..
+            } => {
+                self.paused = !self.paused;
+                info!("{}", if self.paused { "paused" } else { "resumed" });
+                window.request_redraw();
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::A),
+                        ..
+                    },
+                ..
+            } => {
+                self.cycling_palette = !self.cycling_palette;
+                debug!("palette cycling set to {}", self.cycling_palette);
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::L),
+                        ..
+                    },
+                ..
+            } => {
+                self.renderer.set_grid(!self.renderer.grid);
+                if self.renderer.grid {
+                    // The shader can't draw text, so this and the HUD are the scale bar's label.
+                    info!(
+                        "grid spacing {:e}",
+                        grid::spacing(self.renderer.display_size, self.zoom)
+                    );
+                } else {
+                    debug!("grid hidden");
+                }
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::D),
+                        ..
+                    },
+                ..
+            } => {
+                self.renderer.set_dither(!self.renderer.dither);
+                debug!("dithering: {}", self.renderer.dither);
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::K),
+                        ..
+                    },
+                ..
+            } => {
+                self.renderer.set_heatmap(!self.renderer.heatmap);
+                debug!("iteration heatmap: {}", self.renderer.heatmap);
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::E),
+                        ..
+                    },
+                ..
+            } => match self.palette.save(Path::new(PALETTE_PATH)) {
+                Ok(()) => info!("saved palette to {:?}", PALETTE_PATH),
+                Err(err) => error!("failed to save palette to {:?}: {}", PALETTE_PATH, err),
+            },
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::O),
+                        ..
+                    },
+                ..
+            } => {
+                let colouring_mode = match self.renderer.colouring_mode {
+                    colour::ColouringMode::Histogram => colour::ColouringMode::OrbitTrap,
+                    colour::ColouringMode::OrbitTrap => colour::ColouringMode::Distance,
+                    colour::ColouringMode::Distance => colour::ColouringMode::GpuSmooth,
+                    colour::ColouringMode::GpuSmooth => colour::ColouringMode::Root,
+                    colour::ColouringMode::Root => colour::ColouringMode::EscapeTime,
+                    colour::ColouringMode::EscapeTime => colour::ColouringMode::Histogram,
+                };
+                debug!("colouring mode set to {:?}", colouring_mode);
+                self.renderer.set_colouring_mode(colouring_mode);
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::T),
+                        ..
+                    },
+                ..
+            } => {
+                let trap_shape = match self.renderer.trap_shape {
+                    compute::TrapShape::Point => compute::TrapShape::Line,
+                    compute::TrapShape::Line => compute::TrapShape::Cross,
+                    compute::TrapShape::Cross => compute::TrapShape::Point,
+                };
+                debug!("trap shape set to {:?}", trap_shape);
+                self.renderer.set_trap_shape(trap_shape);
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::I),
+                        ..
+                    },
+                ..
+            } => {
+                let interior_colouring = match self.renderer.interior_colouring {
+                    colour::InteriorColouring::Flat => colour::InteriorColouring::Magnitude,
+                    colour::InteriorColouring::Magnitude => colour::InteriorColouring::OrbitTrap,
+                    colour::InteriorColouring::OrbitTrap => colour::InteriorColouring::Flat,
+                };
+                debug!("interior colouring set to {:?}", interior_colouring);
+                self.renderer.set_interior_colouring(interior_colouring);
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::X),
+                        ..
+                    },
+                ..
+            } => {
+                // Skip double precision where it isn't supported, rather than falling back to single.
+                let double_supported = self
+                    .renderer
+                    .device
+                    .features()
+                    .contains(wgpu::Features::SHADER_FLOAT64);
+                let precision = match self.renderer.precision {
+                    compute::Precision::Single if double_supported => compute::Precision::Double,
+                    compute::Precision::Single | compute::Precision::Double => {
+                        compute::Precision::Perturbed
+                    }
+                    compute::Precision::Perturbed => compute::Precision::Single,
+                };
+                if let Err(err) = self.renderer.set_precision(precision) {
+                    error!("{}", err);
+                }
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::W),
+                        ..
+                    },
+                ..
+            } => {
+                let path = screenshot::path(self.origin, self.zoom);
+                debug!("saving wallpaper to {:?}", path);
+                let image = self
+                    .renderer
+                    .render_tiled(WALLPAPER_SIZE, WALLPAPER_TILE_SIZE);
+                if let Err(err) = screenshot::save(&path, WALLPAPER_SIZE, image.as_raw()) {
+                    error!("failed to save wallpaper to {:?}: {}", path, err);
+                }
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::S),
+                        ..
+                    },
+                ..
+            } => {
+                let path = screenshot::path(self.origin, self.zoom);
+                debug!("saving screenshot to {:?}", path);
+                if let Err(err) =
+                    screenshot::save(&path, self.renderer.display_size, &self.renderer.capture())
+                {
+                    error!("failed to save screenshot to {:?}: {}", path, err);
+                }
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode:
+                            Some(
+                                keycode @ (winit::event::VirtualKeyCode::LBracket
+                                | winit::event::VirtualKeyCode::RBracket
+                                | winit::event::VirtualKeyCode::Semicolon
+                                | winit::event::VirtualKeyCode::Apostrophe
+                                | winit::event::VirtualKeyCode::Comma
+                                | winit::event::VirtualKeyCode::Period),
+                            ),
+                        ..
+                    },
+                ..
+            } => {
+                let mut post_processing = self.renderer.post_processing;
+                match keycode {
+                    winit::event::VirtualKeyCode::LBracket => {
+                        post_processing.gamma /= POST_PROCESSING_FACTOR
+                    }
+                    winit::event::VirtualKeyCode::RBracket => {
+                        post_processing.gamma *= POST_PROCESSING_FACTOR
+                    }
+                    winit::event::VirtualKeyCode::Semicolon => {
+                        post_processing.brightness -= BRIGHTNESS_STEP
+                    }
+                    winit::event::VirtualKeyCode::Apostrophe => {
+                        post_processing.brightness += BRIGHTNESS_STEP
+                    }
+                    winit::event::VirtualKeyCode::Comma => {
+                        post_processing.contrast /= POST_PROCESSING_FACTOR
+                    }
+                    _ => post_processing.contrast *= POST_PROCESSING_FACTOR,
+                }
+                post_processing.brightness = post_processing.brightness.clamp(-1.0, 1.0);
+                self.renderer.set_post_processing(post_processing);
+                debug!("post-processing set to {:?}", post_processing);
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode:
+                            Some(
+                                winit::event::VirtualKeyCode::Plus
+                                | winit::event::VirtualKeyCode::Equals
+                                | winit::event::VirtualKeyCode::NumpadAdd,
+                            ),
+                        ..
+                    },
+                ..
+            } => {
+                self.renderer.set_max_iterations(
+                    self.renderer
+                        .max_iterations
+                        .saturating_add(MAX_ITERATIONS_STEP),
+                );
+                debug!("max iterations set to {}", self.renderer.max_iterations);
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode:
+                            Some(
+                                winit::event::VirtualKeyCode::Minus
+                                | winit::event::VirtualKeyCode::NumpadSubtract,
+                            ),
+                        ..
+                    },
+                ..
+            } => {
+                // Never go below one step, or nothing would be iterated.
+                self.renderer.set_max_iterations(
+                    self.renderer
+                        .max_iterations
+                        .saturating_sub(MAX_ITERATIONS_STEP)
+                        .max(MAX_ITERATIONS_STEP),
+                );
+                debug!("max iterations set to {}", self.renderer.max_iterations);
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::B),
+                        ..
+                    },
+                ..
+            } => {
+                self.bookmarks.push(Bookmark {
+                    origin: self.origin,
+                    zoom: self.zoom,
+                    max_iterations: self.renderer.max_iterations,
+                    fractal_kind: self.renderer.fractal_kind,
+                });
+                match bookmark::save(bookmark::PATH, &self.bookmarks) {
+                    Ok(()) => info!("saved bookmark {}", self.bookmarks.len()),
+                    Err(err) => {
+                        error!("failed to save bookmarks to {:?}: {}", bookmark::PATH, err)
+                    }
+                }
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode:
+                            Some(
+                                keycode @ (winit::event::VirtualKeyCode::Key1
+                                | winit::event::VirtualKeyCode::Key2
+                                | winit::event::VirtualKeyCode::Key3
+                                | winit::event::VirtualKeyCode::Key4
+                                | winit::event::VirtualKeyCode::Key5
+                                | winit::event::VirtualKeyCode::Key6
+                                | winit::event::VirtualKeyCode::Key7
+                                | winit::event::VirtualKeyCode::Key8
+                                | winit::event::VirtualKeyCode::Key9),
+                            ),
+                        ..
+                    },
+                ..
+            } => {
+                // `Key1` to `Key9` are declared in order.
+                let index = keycode as usize - winit::event::VirtualKeyCode::Key1 as usize;
+                match self.bookmarks.get(index) {
+                    Some(bookmark) => {
+                        debug!("recalling bookmark {}: {:?}", index + 1, bookmark);
+                        self.origin = bookmark.origin;
+                        self.zoom = bookmark.zoom;
+                        self.target_zoom = self.zoom;
+                        self.renderer.set_origin(self.origin);
+                        self.renderer.set_zoom(self.zoom);
+                        self.renderer.set_max_iterations(bookmark.max_iterations);
+                        if let Err(err) = self.renderer.set_fractal_kind(bookmark.fractal_kind) {
+                            error!("{}", err);
+                        }
+                        self.fractal_kind_changed = true;
+                    }
+                    None => debug!("no bookmark {}", index + 1),
+                }
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Pressed,
+                        virtual_keycode:
+                            Some(
+                                keycode @ (winit::event::VirtualKeyCode::N
+                                | winit::event::VirtualKeyCode::P),
+                            ),
+                        ..
+                    },
+                ..
+            } => {
+                let len = SEED_LOCATIONS.len();
+                let next = keycode == winit::event::VirtualKeyCode::N;
+                let index = match (self.seed_index, next) {
+                    (None, true) => 0,
+                    (None, false) => len - 1,
+                    (Some(index), true) => (index + 1) % len,
+                    (Some(index), false) => (index + len - 1) % len,
+                };
+                let seed = &SEED_LOCATIONS[index];
+                info!(
+                    "going to {} at {} and zoom {}",
+                    seed.name, seed.origin, seed.zoom
+                );
+                self.seed_index = Some(index);
+                self.origin = seed.origin;
+                self.zoom = seed.zoom;
+                self.target_zoom = self.zoom;
+                self.renderer.set_origin(self.origin);
+                self.renderer.set_zoom(self.zoom);
+                self.renderer.set_max_iterations(seed.max_iterations);
+                if let Err(err) = self
+                    .renderer
+                    .set_fractal_kind(compute::FractalKind::Mandelbrot)
+                {
+                    error!("{}", err);
+                }
+                self.fractal_kind_changed = true;
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state,
+                        virtual_keycode:
+                            Some(
+                                keycode @ (winit::event::VirtualKeyCode::Left
+                                | winit::event::VirtualKeyCode::Right
+                                | winit::event::VirtualKeyCode::Up
+                                | winit::event::VirtualKeyCode::Down),
+                            ),
+                        ..
+                    },
+                ..
+            } => {
+                let pressed = state == winit::event::ElementState::Pressed;
+                match keycode {
+                    winit::event::VirtualKeyCode::Left => self.pan_keys.left = pressed,
+                    winit::event::VirtualKeyCode::Right => self.pan_keys.right = pressed,
+                    winit::event::VirtualKeyCode::Up => self.pan_keys.up = pressed,
+                    winit::event::VirtualKeyCode::Down => self.pan_keys.down = pressed,
+                    _ => unreachable!(),
+                }
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    winit::event::MouseScrollDelta::LineDelta(_, delta) => delta as f64,
+                    winit::event::MouseScrollDelta::PixelDelta(position) => {
+                        position.y / PIXELS_PER_LINE
+                    }
+                };
+                let lines = if self.invert_zoom { -lines } else { lines };
+                // Compounded per line, so a big scroll can't take the zoom past zero.
+                self.target_zoom *= (1.0 + self.zoom_sensitivity).powf(lines);
+            }
+            _ => {}
+        }
+    }
+
+    /**
+    Pan and zoom by however long has passed since the last call, so holding a key moves smoothly
+    instead of waiting on the OS's key repeat. Call it once all of a frame's events are handled.
+
+    Returns whether anything is still moving. Once nothing is and the image has converged, there's
+    nothing to draw until the next interaction.
+    */
+    pub fn update(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_update).as_secs_f32();
+        self.last_update = now;
+
+        let direction = self.pan_keys.direction();
+        if direction != Vec2::ZERO {
+            let step = (PAN_SPEED * elapsed) as f64 * (2.0 / self.zoom);
+            self.origin = ComplexF64 {
+                real: self.origin.real + direction.x as f64 * step,
+                imaginary: self.origin.imaginary + direction.y as f64 * step,
+            };
+            self.renderer.set_origin(self.origin);
+        }
+
+        if self.zoom != self.target_zoom {
+            let previous_zoom = self.zoom;
+
+            /*
+            Only reset the pixels once the animation has settled. Until then they keep
+            iterating from values computed at older zooms, which is fine for a few frames.
+            */
+            if ((self.target_zoom - self.zoom) / self.target_zoom).abs() < ZOOM_SETTLED {
+                self.zoom = self.target_zoom;
+                self.renderer.set_zoom(self.zoom);
+            } else {
+                // Exponential smoothing that doesn't depend on the frame rate.
+                let factor = 1.0 - (-self.animation_speed * elapsed as f64).exp();
+                self.zoom += (self.target_zoom - self.zoom) * factor;
+                self.renderer.write_zoom(self.zoom);
+            }
+
+            // Keep the point under the cursor in place.
+            let previous = cursor_to_complex(
+                self.cursor_position,
+                self.view_size(),
+                self.origin,
+                previous_zoom,
+            );
+            let current = cursor_to_complex(
+                self.cursor_position,
+                self.view_size(),
+                self.origin,
+                self.zoom,
+            );
+            self.origin = ComplexF64 {
+                real: self.origin.real + previous.real - current.real,
+                imaginary: self.origin.imaginary + previous.imaginary - current.imaginary,
+            };
+            self.renderer.write_origin(self.origin);
+        }
+
+        let drifting = self.renderer.target().is_some();
+        if drifting {
+            let factor = 1.0 - (-self.drift_speed * elapsed as f64).exp();
+            self.renderer.drift(factor);
+            self.origin = self.renderer.origin();
+        }
+
+        if self.cycling_palette {
+            self.renderer
+                .set_palette_offset(self.renderer.palette_offset + self.cycle_speed * elapsed);
+        }
+
+        let navigating = direction != Vec2::ZERO
+            || self.zoom != self.target_zoom
+            || drifting
+            || self.drag.is_some();
+        if !navigating {
+            self.history.push((self.origin, self.zoom));
+        }
+
+        direction != Vec2::ZERO
+            || self.zoom != self.target_zoom
+            || drifting
+            || self.cycling_palette
+            || self.fractal_kind_changed
+    }
+
+    /**
+    The size of the view that `origin` and `zoom` describe, for mapping the cursor onto the plane:
+    the whole window, or its left half while [`compute::FractalKind::Split`] shows the Mandelbrot
+    set there.
+    */
+    fn view_size(&self) -> screen::Size {
+        match self.renderer.fractal_kind {
+            compute::FractalKind::Split { .. } => left_half(self.renderer.display_size),
+            _ => self.renderer.display_size,
+        }
+    }
+
+    /// Whether there's nothing left to draw until the next interaction. See [`Viewer::update`].
+    pub fn is_idle(&self, animating: bool) -> bool {
+        self.paused || (!animating && self.renderer.is_converged())
+    }
+
+    /**
+    Compute unless paused, then draw to `surface` with [`Renderer::render_frame`].
+
+    The frame is only counted as finished if it was presented, so the caller can reconfigure the
+    surface and try again on an error.
+    */
+    pub fn render_frame(
+        &mut self,
+        surface: &wgpu::Surface,
+        overlay: impl FnOnce(&Renderer, &wgpu::TextureView),
+    ) -> Result<(), wgpu::SurfaceError> {
+        if self.fractal_kind_changed {
+            self.fractal_kind_changed = false;
+            self.renderer.reset();
+        }
+
+        if !self.paused && !self.renderer.is_converged() {
+            self.renderer.compute();
+        }
+
+        self.renderer.render_frame(surface, overlay)?;
+        self.renderer.finish_frame();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize};
+
+    use super::*;
+
+    /// A click on an 800x600 window at scale factor 2.0, which is 1600x1200 physical pixels.
+    #[test]
+    fn click_mapping_at_scale_factor_2() {
+        let scale_factor = 2.0;
+        let physical_size: PhysicalSize<u32> =
+            LogicalSize::new(800.0, 600.0).to_physical(scale_factor);
+        let size = screen::Size {
+            width: physical_size.width,
+            height: physical_size.height,
+        };
+        let origin = ComplexF64 {
+            real: -0.5,
+            imaginary: 0.25,
+        };
+        let zoom = 2.0;
+        let rect = screen::ViewRect::new(origin, zoom, size);
+
+        // What winit reports for a click at the logical position `(x, y)`.
+        let click = |x: f64, y: f64| {
+            let position: PhysicalPosition<f32> =
+                LogicalPosition::new(x, y).to_physical(scale_factor);
+            cursor_to_complex(
+                Vec2 {
+                    x: position.x,
+                    y: position.y,
+                },
+                size,
+                origin,
+                zoom,
+            )
+        };
+        let assert_near = |point: ComplexF64, expected: ComplexF64| {
+            assert!(
+                (point.real - expected.real).abs() < 1e-6
+                    && (point.imaginary - expected.imaginary).abs() < 1e-6,
+                "{} != {}",
+                point,
+                expected
+            );
+        };
+
+        assert_near(click(400.0, 300.0), origin);
+        assert_near(click(0.0, 0.0), rect.min);
+        assert_near(click(800.0, 600.0), rect.max);
+        assert_near(
+            click(200.0, 150.0),
+            ComplexF64 {
+                real: rect.min.real + rect.width() / 4.0,
+                imaginary: rect.min.imaginary + rect.height() / 4.0,
+            },
+        );
+    }
+}